@@ -0,0 +1,87 @@
+//! Build a `Service` storing activities in a directory, for embedding (see `Service::builder`).
+use crate::json_storage::{self, JsonStorage};
+use crate::jsonl_storage::{self, JsonlStorage};
+use crate::rtw_core::error::RtwResult;
+use crate::rtw_core::storage::Storage;
+use crate::service::Service;
+use crate::sqlite_storage::{self, SqliteStorage};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Storage opened from a directory, with the file names used by the CLI
+pub trait OpenStorage: Storage + Sized {
+    /// Open (or create on first write) the storage files inside `storage_dir_path`
+    ///
+    /// May fail depending on backend implementation
+    fn open(storage_dir_path: &Path) -> RtwResult<Self>;
+}
+
+impl OpenStorage for JsonStorage {
+    fn open(storage_dir_path: &Path) -> RtwResult<Self> {
+        Ok(JsonStorage::new(
+            storage_dir_path.join(json_storage::CURRENT_FILE_NAME),
+            storage_dir_path.join(json_storage::FINISHED_FILE_NAME),
+        ))
+    }
+}
+
+impl OpenStorage for JsonlStorage {
+    fn open(storage_dir_path: &Path) -> RtwResult<Self> {
+        Ok(JsonlStorage::new(
+            storage_dir_path.join(json_storage::CURRENT_FILE_NAME),
+            storage_dir_path.join(jsonl_storage::FINISHED_FILE_NAME),
+        ))
+    }
+}
+
+impl OpenStorage for SqliteStorage {
+    fn open(storage_dir_path: &Path) -> RtwResult<Self> {
+        SqliteStorage::new(&storage_dir_path.join(sqlite_storage::DATABASE_FILE_NAME))
+            .map_err(Into::into)
+    }
+}
+
+/// Builder of a `Service` backed by `S`, see `Service::builder`
+pub struct ServiceBuilder<S> {
+    storage_dir_path: PathBuf,
+    storage: PhantomData<S>,
+}
+
+impl<S> ServiceBuilder<S>
+where
+    S: OpenStorage,
+{
+    /// Open the storage, see `OpenStorage::open`
+    pub fn build(self) -> RtwResult<Service<S>> {
+        Ok(Service::new(S::open(&self.storage_dir_path)?))
+    }
+}
+
+impl<S> Service<S>
+where
+    S: OpenStorage,
+{
+    /// Build a service storing activities in `storage_dir_path`, the backend is chosen by `S`
+    ///
+    /// Files are the ones the CLI uses with the same storage directory and backend.
+    ///
+    /// ```
+    /// use rtw::rtw_core::activity::OngoingActivity;
+    /// use rtw::{ActivityService, JsonStorage, Service};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let storage_dir = tempfile::tempdir()?;
+    /// let mut service: Service<JsonStorage> = Service::builder(storage_dir.path()).build()?;
+    /// let work = OngoingActivity::new(chrono::Local::now().into(), vec!["work".into()], None);
+    /// service.start_activity(work, true, false)?;
+    /// assert_eq!(service.get_ongoing_activities()?.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder<P: Into<PathBuf>>(storage_dir_path: P) -> ServiceBuilder<S> {
+        ServiceBuilder {
+            storage_dir_path: storage_dir_path.into(),
+            storage: PhantomData,
+        }
+    }
+}
@@ -1,13 +1,21 @@
 //! Clock impl using chrono.
+use crate::range::{self, days_since_week_start};
 use crate::rtw_core::clock::{Clock, Time};
 use crate::rtw_core::datetimew::DateTimeW;
-use chrono::{Date, Datelike, Duration, Local};
+use chrono::{Date, DateTime, Datelike, Duration, Local, Weekday};
 
-pub struct ChronoClock {}
+/// Local time clock, optionally frozen at a fixed time (e.g. `--now`)
+#[derive(Default)]
+pub struct ChronoClock {
+    now: Option<DateTimeW>,
+}
 
 impl Clock for ChronoClock {
     fn get_time(&self) -> DateTimeW {
-        chrono::Local::now().into()
+        match self.now {
+            None => chrono::Local::now().into(),
+            Some(now) => now,
+        }
     }
 
     fn date_time(&self, time: Time) -> DateTimeW {
@@ -18,43 +26,105 @@ impl Clock for ChronoClock {
     }
 
     fn today_range(&self) -> (DateTimeW, DateTimeW) {
-        let today = chrono::Local::today();
+        let today = self.today();
         self.day_range(today)
     }
 
     fn yesterday_range(&self) -> (DateTimeW, DateTimeW) {
-        let today = chrono::Local::today();
+        let today = self.today();
         let yesterday = today - chrono::Duration::days(1); // so proud
         self.day_range(yesterday)
     }
 
-    fn last_week_range(&self) -> (DateTimeW, DateTimeW) {
-        let today = chrono::Local::today();
-        let weekday = today.weekday();
-        let this_week_monday = today - Duration::days(weekday.num_days_from_monday() as i64);
-        let last_week_monday = this_week_monday - Duration::days(7);
-        let last_week_sunday = this_week_monday - Duration::days(1);
-        self.days_range(last_week_monday, last_week_sunday)
+    fn last_week_range(&self, week_start: Weekday) -> (DateTimeW, DateTimeW) {
+        let this_week_first_day = self.this_week_first_day(week_start);
+        let last_week_first_day = this_week_first_day - Duration::days(7);
+        let last_week_last_day = this_week_first_day - Duration::days(1);
+        self.days_range(last_week_first_day, last_week_last_day)
     }
 
-    fn this_week_range(&self) -> (DateTimeW, DateTimeW) {
-        let today = chrono::Local::today();
-        let weekday = today.weekday();
-        let this_week_monday = today - Duration::days(weekday.num_days_from_monday() as i64);
-        let this_week_sunday = this_week_monday + Duration::days(6);
-        self.days_range(this_week_monday, this_week_sunday)
+    fn this_week_range(&self, week_start: Weekday) -> (DateTimeW, DateTimeW) {
+        let this_week_first_day = self.this_week_first_day(week_start);
+        let this_week_last_day = this_week_first_day + Duration::days(6);
+        self.days_range(this_week_first_day, this_week_last_day)
     }
 }
 
 impl ChronoClock {
+    /// Clock always returning `now`
+    pub fn fixed(now: DateTimeW) -> ChronoClock {
+        ChronoClock { now: Some(now) }
+    }
+
+    fn today(&self) -> Date<Local> {
+        let now: DateTime<Local> = self.get_time().into();
+        now.date()
+    }
+
+    fn this_week_first_day(&self, week_start: Weekday) -> Date<Local> {
+        let today = self.today();
+        today - Duration::days(days_since_week_start(today.weekday(), week_start))
+    }
+
     fn day_range(&self, day: Date<Local>) -> (DateTimeW, DateTimeW) {
         self.days_range(day, day)
     }
 
     fn days_range(&self, day_start: Date<Local>, day_end: Date<Local>) -> (DateTimeW, DateTimeW) {
-        (
-            day_start.and_hms(0, 0, 0).into(),
-            day_end.and_hms(23, 59, 59).into(),
-        )
+        range::days_range(day_start, day_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chrono_clock::ChronoClock;
+    use crate::rtw_core::clock::Clock;
+    use crate::rtw_core::test_utils::datetimew;
+    use chrono::Weekday;
+
+    #[test]
+    fn test_week_ranges_monday_start() {
+        // friday
+        let clock = ChronoClock::fixed(datetimew("2020-12-25T12:00:00"));
+        assert_eq!(
+            clock.this_week_range(Weekday::Mon),
+            (
+                datetimew("2020-12-21T00:00:00"),
+                datetimew("2020-12-27T23:59:59")
+            )
+        );
+        assert_eq!(
+            clock.last_week_range(Weekday::Mon),
+            (
+                datetimew("2020-12-14T00:00:00"),
+                datetimew("2020-12-20T23:59:59")
+            )
+        );
+    }
+
+    #[test]
+    fn test_week_ranges_sunday_start() {
+        // friday
+        let clock = ChronoClock::fixed(datetimew("2020-12-25T12:00:00"));
+        assert_eq!(
+            clock.this_week_range(Weekday::Sun),
+            (
+                datetimew("2020-12-20T00:00:00"),
+                datetimew("2020-12-26T23:59:59")
+            )
+        );
+        assert_eq!(
+            clock.last_week_range(Weekday::Sun),
+            (
+                datetimew("2020-12-13T00:00:00"),
+                datetimew("2020-12-19T23:59:59")
+            )
+        );
+        // first day of the week
+        let clock = ChronoClock::fixed(datetimew("2020-12-20T09:00:00"));
+        assert_eq!(
+            clock.this_week_range(Weekday::Sun).0,
+            datetimew("2020-12-20T00:00:00")
+        );
     }
 }
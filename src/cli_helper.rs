@@ -1,12 +1,46 @@
 //! CLI parsing helpers and clap App.
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
 
+use crate::export::{ExportFormat, ImportFormat, OutputFormat};
+use crate::range;
+use crate::report::period::Period;
+use crate::rtw_core::activity::ActivityOrder;
 use crate::rtw_core::clock::{Clock, Time};
 use crate::rtw_core::datetimew::DateTimeW;
-use crate::rtw_core::{ActivityId, Description, Tags};
+use crate::rtw_core::durationw::{DurationFormat, DurationPrecision, RoundMethod};
+use crate::rtw_core::{tag_key_value, ActivityId, Description, Tag, Tags};
 use crate::time_tools::TimeTools;
+use chrono::{DateTime, Duration, Local, Weekday};
+use std::ffi::OsString;
+use std::path::PathBuf;
 use std::str::FromStr;
 
+// ';' separates tags in CSV export
+const FORBIDDEN_TAG_CHARS: &[char] = &[';'];
+
+/// Trim tags and remove duplicates (first occurrence is kept)
+///
+/// Fails on empty or whitespace-only tags and on tags containing `;` or control characters.
+pub fn normalize_tags(raw: Vec<String>) -> anyhow::Result<Tags> {
+    let mut tags: Tags = vec![];
+    for tag in raw {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(anyhow::anyhow!("empty tag, tags cannot be blank"));
+        }
+        if tag.contains(FORBIDDEN_TAG_CHARS) || tag.contains(char::is_control) {
+            return Err(anyhow::anyhow!(
+                "invalid tag '{}', tags cannot contain ';' or control characters",
+                tag.escape_debug()
+            ));
+        }
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    Ok(tags)
+}
+
 // 09:00 foo -> (09:00, foo)
 // foo -> (Now, foo)
 // last friday 8pm foo -> (last friday 8pm, foo)
@@ -45,6 +79,59 @@ fn split_time_range_from_tags(
     }
 }
 
+// "09:00 +90m foo" -> (09:00, 10:30, foo), None without "+duration" token
+fn split_time_and_duration_from_tags(
+    tokens: &[String],
+    clock: &dyn Clock,
+) -> anyhow::Result<Option<(Time, Time, Tags)>> {
+    // "-" separates range start and end, tags may then start with '+'
+    if tokens.iter().any(|t| t == "-") {
+        return Ok(None);
+    }
+    match tokens.iter().position(|t| t.starts_with('+')) {
+        None => Ok(None),
+        Some(at) => {
+            let start = TimeTools::time_from_str(&tokens[..at].join(" "), clock)?;
+            let duration = parse_track_duration(&tokens[at][1..])?;
+            let end = time_after(start, duration, clock);
+            Ok(Some((start, end, tokens[at + 1..].to_vec())))
+        }
+    }
+}
+
+// "09:00 now foo" -> (09:00, Now, foo), None without "now" token
+fn split_time_until_now_from_tags(
+    tokens: &[String],
+    clock: &dyn Clock,
+) -> anyhow::Result<Option<(Time, Time, Tags)>> {
+    // "-" separates range start and end e.g. "09:00 - now foo"
+    if tokens.iter().any(|t| t == "-") {
+        return Ok(None);
+    }
+    match tokens.iter().position(|t| t.eq_ignore_ascii_case("now")) {
+        None | Some(0) => Ok(None),
+        Some(at) => {
+            let start = TimeTools::time_from_str(&tokens[..at].join(" "), clock)?;
+            Ok(Some((start, Time::Now, tokens[at + 1..].to_vec())))
+        }
+    }
+}
+
+// e.g. 90m, must be positive
+fn parse_track_duration(s: &str) -> anyhow::Result<Duration> {
+    let duration = TimeTools::duration_from_str(s)?;
+    if duration <= Duration::zero() {
+        Err(anyhow::anyhow!("duration '{}' must be positive", s))
+    } else {
+        Ok(duration)
+    }
+}
+
+fn time_after(time: Time, duration: Duration, clock: &dyn Clock) -> Time {
+    let time: DateTime<Local> = clock.date_time(time).into();
+    Time::DateTime((time + duration).into())
+}
+
 // 09:00 - 10:00 -> (09:00, 10:00)
 // 09:00 - -> (09:00, Now)
 fn split_time_range(tokens: &[String], clock: &dyn Clock) -> anyhow::Result<(Time, Time)> {
@@ -70,6 +157,22 @@ fn split_time_range(tokens: &[String], clock: &dyn Clock) -> anyhow::Result<(Tim
     }
 }
 
+// :week -> (week start, Now)
+// 09:00 - 10:00 -> (09:00, 10:00)
+fn split_time_range_or_token(
+    tokens: &[String],
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(Time, Time)> {
+    match tokens {
+        [token] if range::is_range_token(token) => {
+            let (range_start, range_end) = range::range_from_token(token, clock, week_start)?;
+            Ok((Time::DateTime(range_start), Time::DateTime(range_end)))
+        }
+        _ => split_time_range(tokens, clock),
+    }
+}
+
 pub fn get_app() -> App<'static, 'static> {
     App::new(crate_name!())
         .version(crate_version!())
@@ -81,7 +184,7 @@ pub fn get_app() -> App<'static, 'static> {
                 .long("dir")
                 .value_name("DIR")
                 .required(false)
-                .help("storage directory")
+                .help("storage directory (overrides $RTW_DIR and config)")
                 .hidden(true) // only useful for testing
                 .takes_value(true),
         )
@@ -108,6 +211,96 @@ pub fn get_app() -> App<'static, 'static> {
                 .conflicts_with("default")
                 .help("disallow overlapping activities"),
         )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("FILE")
+                .required(false)
+                .takes_value(true)
+                .conflicts_with("default")
+                .help("config file (json or toml) instead of the default config locations"),
+        )
+        .arg(
+            Arg::with_name("utc")
+                .long("utc")
+                .required(false)
+                .help("display dates in UTC"),
+        )
+        .arg(
+            Arg::with_name("now")
+                .long("now")
+                .value_name("DATETIME")
+                .takes_value(true)
+                .hidden(true)
+                .help("resolve times against DATETIME instead of the current time e.g. for demos"),
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .long("no-color")
+                .required(false)
+                .help("disable colors (also disabled by $NO_COLOR or when not a terminal)"),
+        )
+        .arg(
+            Arg::with_name("duration-format")
+                .long("duration-format")
+                .value_name("FORMAT")
+                .possible_values(&["clock", "human", "days", "decimal"])
+                .required(false)
+                .takes_value(true)
+                .help(
+                    "display durations as 02:15:03 (clock), 2h 15m 03s (human), 2d 2h 0m (days) or 2.25h (decimal)",
+                ),
+        )
+        .arg(
+            Arg::with_name("precision")
+                .long("precision")
+                .value_name("UNIT")
+                .possible_values(&["second", "minute"])
+                .required(false)
+                .takes_value(true)
+                .help("display durations to the second (default) or to the nearest minute e.g. 00:03 for 00:02:30"),
+        )
+        .arg(
+            Arg::with_name("week-start")
+                .long("week-start")
+                .value_name("DAY")
+                .possible_values(&["mon", "tue", "wed", "thu", "fri", "sat", "sun"])
+                .required(false)
+                .takes_value(true)
+                .help("first day of the week for :week, :lastweek and week reports (overrides config)"),
+        )
+        .arg(
+            Arg::with_name("backend")
+                .long("backend")
+                .value_name("BACKEND")
+                .possible_values(&["json", "sqlite", "jsonl"])
+                .required(false)
+                .help("storage backend (overrides config)")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .value_name("PROFILE")
+                .required(false)
+                .takes_value(true)
+                .help("prefix data file names with PROFILE e.g. work.rtw.json (overrides config)"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .required(false)
+                .conflicts_with("verbose")
+                .help("only print data and errors, no confirmations e.g. the started activity"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .required(false)
+                .help("log files read/written and resolved times to stderr"),
+        )
         .arg(
             Arg::with_name("dry-run")
                 .short("n")
@@ -131,8 +324,34 @@ pub fn get_app() -> App<'static, 'static> {
                     Arg::with_name("description")
                         .short("d")
                         .long("description")
+                        .visible_alias("annotation")
                         .takes_value(true)
                         .help("long activity description"),
+                )
+                .arg(
+                    Arg::with_name("parallel")
+                        .long("parallel")
+                        .help("do not stop ongoing activities"),
+                )
+                .arg(
+                    Arg::with_name("allow-future")
+                        .long("allow-future")
+                        .help("start activity even if start time is in the future"),
+                )
+                .arg(
+                    Arg::with_name("restart")
+                        .long("restart")
+                        .help("stop and start again an ongoing activity with the same tags"),
+                )
+                .arg(
+                    Arg::with_name("from-last")
+                        .long("from-last")
+                        .help("start at the end of the last finished activity (no time clue)"),
+                )
+                .arg(
+                    Arg::with_name("no-defaults")
+                        .long("no-defaults")
+                        .help("do not add the default tags of the config"),
                 ),
         )
         .subcommand(
@@ -144,28 +363,58 @@ pub fn get_app() -> App<'static, 'static> {
                         .required(true)
                         .help(concat!(
                             "interval time clue followed by at least 1 tag\n",
-                            "start - end tags...\n",
-                            "e.g '09:00 - 10:00 foo' "
+                            "start - end tags... or start +duration tags...\n",
+                            "e.g '09:00 - 10:00 foo' or '09:00 +90m foo' "
                         )),
                 )
+                .arg(
+                    Arg::with_name("for")
+                        .long("for")
+                        .value_name("DURATION")
+                        .takes_value(true)
+                        .help("duration e.g. 90m or 1h30m, end is start + duration (tokens: start tags...)"),
+                )
                 .arg(
                     Arg::with_name("description")
                         .short("d")
                         .long("description")
+                        .visible_alias("annotation")
                         .takes_value(true)
                         .help("long activity description"),
+                )
+                .arg(
+                    Arg::with_name("allow-overlap")
+                        .long("allow-overlap")
+                        .help("track activity even if it overlaps finished activities"),
+                )
+                .arg(
+                    Arg::with_name("allow-future")
+                        .long("allow-future")
+                        .help("track activity even if it ends in the future"),
+                )
+                .arg(
+                    Arg::with_name("if-not-exists")
+                        .long("if-not-exists")
+                        .help("skip the activity if one with the same start, end and tags is tracked"),
+                )
+                .arg(
+                    Arg::with_name("no-defaults")
+                        .long("no-defaults")
+                        .help("do not add the default tags of the config"),
                 ),
         )
         .subcommand(
             SubCommand::with_name("stop")
                 .about("Stop activity")
                 .arg(
-                    Arg::with_name("time")
+                    Arg::with_name("tokens")
                         .multiple(true)
                         .required(false)
                         .help(concat!(
-                            "optional time clue e.g. 4min ago\n",
-                            "current time is used when omitted"
+                            "optional time clue followed by optional tags\n",
+                            "e.g. '4min ago' or '4min' or '-4min' or 'foo' or '4min ago foo'\n",
+                            "current time is used when omitted\n",
+                            "only the ongoing activity with these tags is stopped"
                         )),
                 )
                 .arg(
@@ -176,6 +425,30 @@ pub fn get_app() -> App<'static, 'static> {
                             "optional activity id\n",
                             "current activity is stopped when omitted"
                         )),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .conflicts_with("id")
+                        .help("stop all ongoing activities"),
+                )
+                .arg(
+                    Arg::with_name("description")
+                        .short("d")
+                        .long("description")
+                        .visible_alias("annotation")
+                        .takes_value(true)
+                        .conflicts_with("all")
+                        .help("long activity description, set on the stopped activity"),
+                )
+                .arg(
+                    Arg::with_name("coalesce")
+                        .long("coalesce")
+                        .conflicts_with("all")
+                        .help(concat!(
+                            "merge into the previous finished activity if it has the same tags\n",
+                            "and stopped right before the stopped activity started"
+                        )),
                 ),
         )
         .subcommand(
@@ -189,7 +462,50 @@ pub fn get_app() -> App<'static, 'static> {
                         .help(concat!(
                             "optional interval time clue\n",
                             "start - end\n",
-                            "e.g '09:00 - 10:00' "
+                            "or :today :yesterday :week :lastweek :month followed by tags\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week) followed by tags\n",
+                            "e.g '09:00 - 10:00' or ':week foo bar'"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("any")
+                        .long("any")
+                        .help("activities tagged with any of the given tags (default: all)"),
+                )
+                .arg(
+                    Arg::with_name("since")
+                        .long("since")
+                        .value_name("TIME")
+                        .takes_value(true)
+                        .conflicts_with_all(&["yesterday", "lastweek", "week"])
+                        .help(concat!(
+                            "activities started since TIME (until now by default)\n",
+                            "remaining tokens are tags e.g. '--since 2019-12-01 foo'"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("longer-than")
+                        .long("longer-than")
+                        .value_name("DURATION")
+                        .takes_value(true)
+                        .help("activities longer than DURATION e.g. 4h, 1h30m, 90m, 45 (minutes)"),
+                )
+                .arg(
+                    Arg::with_name("shorter-than")
+                        .long("shorter-than")
+                        .value_name("DURATION")
+                        .takes_value(true)
+                        .help("activities shorter than DURATION e.g. 1m, 30s"),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .value_name("TIME")
+                        .takes_value(true)
+                        .conflicts_with_all(&["yesterday", "lastweek", "week"])
+                        .help(concat!(
+                            "activities started until TIME (since the beginning by default)\n",
+                            "a date without time includes the whole day e.g. '--until 2019-12-31'"
                         )),
                 )
                 .arg(
@@ -217,6 +533,118 @@ pub fn get_app() -> App<'static, 'static> {
                         .short("d")
                         .long("description")
                         .help("display activities descriptions"),
+                )
+                .arg(
+                    Arg::with_name("round")
+                        .long("round")
+                        .value_name("MINUTES")
+                        .takes_value(true)
+                        .help(concat!(
+                            "round durations to a multiple of MINUTES\n",
+                            "total is computed from rounded durations"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("round-method")
+                        .long("round-method")
+                        .takes_value(true)
+                        .possible_values(&["up", "nearest", "down"])
+                        .help("rounding method (default: up)"),
+                )
+                .arg(
+                    Arg::with_name("week-grid")
+                        .long("week-grid")
+                        .conflicts_with_all(&["id", "description", "sort", "reverse"])
+                        .help(concat!(
+                            "display daily totals as a week by day grid\n",
+                            "e.g. 'rtw summary --week-grid :month'"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("sort")
+                        .long("sort")
+                        .possible_values(&["start", "duration", "tags"])
+                        .default_value("start")
+                        .help(concat!(
+                            "sort activities by start time, duration or tags\n",
+                            "with --by tag: sort tags by total (default) or tags"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("by")
+                        .long("by")
+                        .takes_value(true)
+                        .possible_values(&["tag"])
+                        .conflicts_with_all(&["week-grid", "group-by", "id", "description"])
+                        .help(concat!(
+                            "display activities count and total per tag instead of activities\n",
+                            "e.g. 'rtw summary --by tag :week'"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("reverse")
+                        .long("reverse")
+                        .help("reverse the sort order"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .possible_values(&["text", "json", "markdown"])
+                        .default_value("text")
+                        .help("output format"),
+                )
+                .arg(
+                    Arg::with_name("max-width")
+                        .long("max-width")
+                        .value_name("COLUMNS")
+                        .takes_value(true)
+                        .help(concat!(
+                            "truncate tags so that lines fit in COLUMNS\n",
+                            "terminal width by default, no truncation when not a terminal"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("no-truncate")
+                        .long("no-truncate")
+                        .conflicts_with("max-width")
+                        .help("display tags in full"),
+                )
+                .arg(
+                    Arg::with_name("group-by")
+                        .long("group-by")
+                        .value_name("KEY")
+                        .takes_value(true)
+                        .conflicts_with("week-grid")
+                        .help(concat!(
+                            "display totals per value of KEY:value tags instead of per tag\n",
+                            "e.g. '--group-by project' for project:alpha project:beta"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("total-only")
+                        .long("total-only")
+                        .conflicts_with_all(&["week-grid", "by", "group-by", "id", "description"])
+                        .help("display only the total duration, in seconds"),
+                )
+                .arg(
+                    Arg::with_name("human")
+                        .long("human")
+                        .requires("total-only")
+                        .help("with --total-only: display the total as e.g. 1h 30m 00s"),
+                )
+                .arg(
+                    Arg::with_name("with-current")
+                        .long("with-current")
+                        .help(concat!(
+                            "display ongoing activities started within the range, until now\n",
+                            "default for today's summary"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("no-current")
+                        .long("no-current")
+                        .conflicts_with("with-current")
+                        .help("do not display ongoing activities"),
                 ),
         )
         .subcommand(
@@ -236,6 +664,8 @@ pub fn get_app() -> App<'static, 'static> {
                         .help(concat!(
                             "optional interval time clue\n",
                             "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
                             "e.g '09:00 - 10:00' "
                         )),
                 )
@@ -255,12 +685,14 @@ pub fn get_app() -> App<'static, 'static> {
                         .help("activities done this week"),
                 ),
         )
-        .subcommand(SubCommand::with_name("continue").about("Continue a finished activity"))
-        .subcommand(SubCommand::with_name("day").about("Display the current day as a timeline"))
-        .subcommand(SubCommand::with_name("week").about("Display the current week as a timeline"))
         .subcommand(
-            SubCommand::with_name("timeline")
-                .about("Display finished activities as a timeline")
+            SubCommand::with_name("report")
+                .about("Display activities count and total duration per day or week")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw report :week\n",
+                    "rtw report --by week :month\n"
+                ))
                 .arg(
                     Arg::with_name("tokens")
                         .multiple(true)
@@ -268,45 +700,626 @@ pub fn get_app() -> App<'static, 'static> {
                         .help(concat!(
                             "optional interval time clue\n",
                             "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are used when omitted\n",
                             "e.g 'last monday - now' "
                         )),
+                )
+                .arg(
+                    Arg::with_name("by")
+                        .long("by")
+                        .possible_values(&["day", "week"])
+                        .default_value("day")
+                        .help("group activities by day or ISO week"),
+                )
+                .arg(
+                    Arg::with_name("clip")
+                        .long("clip")
+                        .help(concat!(
+                            "count only the part of activities within the interval\n",
+                            "(activities started within the interval count fully by default)"
+                        )),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("delete")
-                .about("Delete activity")
-                .arg(Arg::with_name("id").required(true).help("activity id")),
-        )
-        .subcommand(
-            SubCommand::with_name("cancel")
-                .about("cancel current activity")
+            SubCommand::with_name("tags")
+                .about("Display tags with activities count and total duration")
                 .arg(
-                    Arg::with_name("id")
-                        .long("id")
-                        .takes_value(true)
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .conflicts_with_all(&["yesterday", "lastweek", "week"])
                         .help(concat!(
-                            "optional activity id\n",
-                            "current activity is stopped when omitted"
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are used when omitted\n",
+                            "e.g 'last monday - now' "
+                        )),
+                )
+                .arg(
+                    Arg::with_name("yesterday")
+                        .long("yesterday")
+                        .help("activities done yesterday"),
+                )
+                .arg(
+                    Arg::with_name("lastweek")
+                        .long("lastweek")
+                        .help("activities done last week"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("activities done this week"),
+                )
+                .arg(
+                    Arg::with_name("names")
+                        .long("names")
+                        .conflicts_with_all(&["tokens", "yesterday", "lastweek", "week"])
+                        .help(concat!(
+                            "only display distinct tag names, one per line (e.g. for completion)\n",
+                            "read from a cache rebuilt when activities changed"
                         )),
                 ),
         )
         .subcommand(
-            SubCommand::with_name("completion")
-                .about("generate completion file")
+            SubCommand::with_name("total")
+                .about("Display total duration of finished activities with a tag")
                 .arg(
-                    Arg::with_name("shell")
-                        .possible_values(&["bash", "zsh", "fish", "powershell", "elvish"])
-                        .takes_value(true)
-                        .required(true),
+                    Arg::with_name("tag")
+                        .required(true)
+                        .help("tag e.g. foo"),
+                )
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .conflicts_with_all(&["yesterday", "lastweek", "week"])
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are used when omitted\n",
+                            "e.g 'last monday - now' "
+                        )),
+                )
+                .arg(
+                    Arg::with_name("yesterday")
+                        .long("yesterday")
+                        .help("activities done yesterday"),
+                )
+                .arg(
+                    Arg::with_name("lastweek")
+                        .long("lastweek")
+                        .help("activities done last week"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("activities done this week"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("goal")
+                .about("Set duration goals per tag and compare them to tracked time")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw goal add foo 20h\n",
+                    "rtw goal status :week\n",
+                    "rtw goal remove foo\n"
+                ))
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about("Set the goal of a tag")
+                        .arg(Arg::with_name("tag").required(true).help("tag e.g. foo"))
+                        .arg(
+                            Arg::with_name("duration")
+                                .required(true)
+                                .help("target duration e.g. 20h or 1h30m"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("remove")
+                        .about("Remove the goal of a tag")
+                        .arg(Arg::with_name("tag").required(true).help("tag e.g. foo")),
+                )
+                .subcommand(
+                    SubCommand::with_name("status")
+                        .about("Display tracked duration, target and delta per goal (default)")
+                        .arg(
+                            Arg::with_name("tokens")
+                                .multiple(true)
+                                .required(false)
+                                .help(concat!(
+                                    "optional interval time clue (default :week)\n",
+                                    "start - end\n",
+                                    "or :today :yesterday :week :lastweek :month\n",
+                                    "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                                    "e.g 'last monday - now' "
+                                )),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("Export finished activities to stdout")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw export > activities.csv\n",
+                    "rtw export --lastweek > lastweek.csv\n",
+                    "rtw export --format json > backup.json\n"
+                ))
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .possible_values(&["csv", "json"])
+                        .default_value("csv")
+                        .help("export format"),
+                )
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .conflicts_with_all(&["yesterday", "lastweek", "week"])
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are exported when omitted\n",
+                            "e.g 'last monday - now' "
+                        )),
+                )
+                .arg(
+                    Arg::with_name("yesterday")
+                        .long("yesterday")
+                        .help("activities done yesterday"),
+                )
+                .arg(
+                    Arg::with_name("lastweek")
+                        .long("lastweek")
+                        .help("activities done last week"),
+                )
+                .arg(
+                    Arg::with_name("week")
+                        .long("week")
+                        .help("activities done this week"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about("Import finished activities exported with 'export --format json'")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw import backup.json\n",
+                    "timew export > timew.json && rtw import --from timewarrior timew.json\n"
+                ))
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .possible_values(&["rtw", "timewarrior"])
+                        .default_value("rtw")
+                        .help("format of the imported file"),
+                )
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .help("json file to import"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("continue")
+                .about("Continue a finished activity")
+                .arg(Arg::with_name("id").required(false).help(concat!(
+                    "optional activity id\n",
+                    "last finished activity is continued when omitted"
+                )))
+                .arg(
+                    Arg::with_name("pick")
+                        .long("pick")
+                        .required(false)
+                        .conflicts_with("id")
+                        .help("choose among the most recent tag sets (see continue_pick_count config)"),
+                )
+                .arg(
+                    Arg::with_name("time")
+                        .multiple(true)
+                        .required(false)
+                        .requires("id")
+                        .help(concat!(
+                            "optional time clue e.g. 4min ago\n",
+                            "current time is used when omitted"
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("day")
+                .about("Display a day as a chart, one row per activity")
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional day time clue e.g. 2020-12-25T00:00 or :yesterday\n",
+                            "today is used when omitted"
+                        )),
+                ),
+        )
+        .subcommand(SubCommand::with_name("week").about("Display the current week as a timeline"))
+        .subcommand(
+            SubCommand::with_name("timeline")
+                .about("Display finished activities as a timeline")
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "e.g 'last monday - now' "
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("gaps")
+                .about("Display untracked intervals between finished activities")
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "today is used when omitted"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("min")
+                        .long("min")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("minimum gap duration e.g. 5 (minutes), 90s, 1h"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Display count, total, shortest, longest and mean activity duration")
+                .after_help(concat!("examples:\n", "rtw stats :month\n"))
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are used when omitted\n",
+                            "e.g 'last monday - now' "
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("count")
+                .about("Display the number of finished activities")
+                .after_help(concat!("examples:\n", "rtw count :month\n"))
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are counted when omitted\n",
+                            "e.g 'last monday - now' "
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("last")
+                .about("Display the last finished activities, latest first")
+                .after_help(concat!("examples:\n", "rtw last\n", "rtw last 5\n"))
+                .arg(
+                    Arg::with_name("count")
+                        .required(false)
+                        .help("number of activities, 1 when omitted"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("overlaps")
+                .about("Display pairs of overlapping finished activities")
+                .after_help(concat!("examples:\n", "rtw overlaps :month\n"))
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "today is used when omitted"
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .about("Delete activity")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw delete 2\n",
+                    "rtw delete --tag foo --yes\n",
+                    "rtw delete --range :yesterday\n",
+                    "rtw delete --tag foo --range 09:00 - 12:00\n"
+                ))
+                .arg(
+                    Arg::with_name("id")
+                        .required_unless_one(&["tag", "range"])
+                        .conflicts_with_all(&["tag", "range"])
+                        .help("activity id"),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .help("delete finished activities with all these tags"),
+                )
+                .arg(
+                    Arg::with_name("range")
+                        .long("range")
+                        .takes_value(true)
+                        .multiple(true)
+                        .help(concat!(
+                            "delete finished activities starting within interval\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("yes")
+                        .long("yes")
+                        .short("y")
+                        .help("do not ask for confirmation before deleting several activities"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("annotate")
+                .about("Set the description of a finished activity")
+                .arg(Arg::with_name("id").required(true).help("activity id"))
+                .arg(
+                    Arg::with_name("text")
+                        .multiple(true)
+                        .required(true)
+                        .help("activity description"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("modify")
+                .about("Modify a finished activity")
+                .arg(Arg::with_name("id").required(true).help("activity id"))
+                .arg(
+                    Arg::with_name("start")
+                        .long("start")
+                        .takes_value(true)
+                        .help("new start time e.g. 09:00"),
+                )
+                .arg(
+                    Arg::with_name("end")
+                        .long("end")
+                        .takes_value(true)
+                        .help("new end time e.g. 10:00"),
+                )
+                .arg(
+                    Arg::with_name("tags")
+                        .long("tags")
+                        .takes_value(true)
+                        .help("new comma-separated tags e.g. foo,bar"),
+                )
+                .group(
+                    ArgGroup::with_name("fields")
+                        .args(&["start", "end", "tags"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("move")
+                .about("Shift a finished activity, preserving its duration")
+                // negative deltas e.g. -1h are not flags
+                .setting(AppSettings::AllowLeadingHyphen)
+                .arg(Arg::with_name("id").required(true).help("activity id"))
+                .arg(
+                    Arg::with_name("delta")
+                        .required(true)
+                        .help("signed duration e.g. +15m or -1h"),
+                )
+                .arg(
+                    Arg::with_name("allow-overlap")
+                        .long("allow-overlap")
+                        .help("move activity even if it overlaps finished activities"),
+                )
+                .arg(
+                    Arg::with_name("allow-future")
+                        .long("allow-future")
+                        .help("move activity even if it ends in the future"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("split")
+                .about("Split a finished activity into two adjacent activities")
+                .arg(Arg::with_name("id").required(true).help("activity id"))
+                .arg(
+                    Arg::with_name("time")
+                        .required(true)
+                        .help("split time e.g. 09:30 or 2020-12-25T09:30:00"),
+                )
+                .arg(
+                    Arg::with_name("tags")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional tags for the second activity\n",
+                            "original tags are used when omitted"
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Merge two finished activities")
+                .arg(Arg::with_name("first").required(true).help("activity id"))
+                .arg(Arg::with_name("second").required(true).help("activity id"))
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("merge activities even if there is a gap between them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rename")
+                .about("Rename a tag in finished and ongoing activities")
+                .arg(Arg::with_name("old").required(true).help("tag to rename"))
+                .arg(Arg::with_name("new").required(true).help("new tag"))
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("display affected activities ids without renaming"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("retag")
+                .about("Add and remove tags of finished activities")
+                .after_help(concat!(
+                    "examples:\n",
+                    "rtw retag :month --add billable --remove draft\n",
+                    "rtw retag --tag foo --add bar\n"
+                ))
+                .arg(
+                    Arg::with_name("tokens")
+                        .multiple(true)
+                        .required(false)
+                        .help(concat!(
+                            "optional interval time clue\n",
+                            "start - end\n",
+                            "or :today :yesterday :week :lastweek :month\n",
+                            "or 2019-12 (month) 2019-W52 (ISO week)\n",
+                            "all finished activities are retagged when omitted"
+                        )),
+                )
+                .arg(
+                    Arg::with_name("tag")
+                        .long("tag")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("only retag activities with all these tags"),
+                )
+                .arg(
+                    Arg::with_name("add")
+                        .long("add")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required_unless("remove")
+                        .help("tag to add"),
+                )
+                .arg(
+                    Arg::with_name("remove")
+                        .long("remove")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .help("tag to remove"),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("display affected activities ids without retagging"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cancel")
+                .about("cancel current activity")
+                .arg(
+                    Arg::with_name("id")
+                        .long("id")
+                        .takes_value(true)
+                        .help(concat!(
+                            "optional activity id\n",
+                            "current activity is stopped when omitted"
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("current")
+                .about("Display ongoing activities with elapsed time")
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .help("output format"),
+                )
+                .arg(
+                    Arg::with_name("tags-only")
+                        .long("tags-only")
+                        .help("only print tags (e.g. for status bars), exit status is 1 when nothing is tracked"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("edit")
+                .about("Edit finished activities as JSON with $EDITOR")
+                .after_help(concat!(
+                    "activities without id are added, removed ones are deleted\n",
+                    "nothing is changed if the edited file is invalid"
+                )),
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Check stored activities: overlaps, invalid or future times, duplicates")
+                .arg(
+                    Arg::with_name("fix")
+                        .long("fix")
+                        .help(concat!(
+                            "repair what can be repaired safely\n",
+                            "delete activities lasting 0s, remove ongoing activities already finished"
+                        )),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about("Undo the last command changing activities (start, stop, track, delete...)"),
+        )
+        .subcommand(
+            SubCommand::with_name("completion")
+                .about("generate completion file")
+                .visible_alias("completions")
+                .arg(
+                    Arg::with_name("shell")
+                        .possible_values(&["bash", "zsh", "fish", "powershell", "elvish"])
+                        .takes_value(true)
+                        .required(true),
                 ),
         )
 }
 
-pub fn parse_start_args(
-    start_m: &ArgMatches,
-    clock: &dyn Clock,
-) -> anyhow::Result<(Time, Tags, Option<Description>)> {
+// start time, tags, description, parallel, allow future, restart
+type StartArgs = (Time, Tags, Option<Description>, bool, bool, bool);
+
+pub fn parse_start_args(start_m: &ArgMatches, clock: &dyn Clock) -> anyhow::Result<StartArgs> {
     let description = start_m.value_of("description").map(|s| s.to_string());
+    let parallel = start_m.is_present("parallel");
+    let allow_future = start_m.is_present("allow-future");
+    let restart = start_m.is_present("restart");
     let values_arg = start_m.values_of("tokens"); // optional time clue, tags
     if let Some(values) = values_arg {
         let values: Tags = values.map(String::from).collect();
@@ -314,42 +1327,115 @@ pub fn parse_start_args(
         return if tags.is_empty() {
             Err(anyhow::anyhow!("no tags provided"))
         } else {
-            Ok((time, tags, description))
+            let tags = normalize_tags(tags)?;
+            Ok((time, tags, description, parallel, allow_future, restart))
         };
     }
     Err(anyhow::anyhow!("neither time clue nor tags provided")) // it should be prevented by clap
 }
 
-pub fn parse_track_args(
-    track_m: &ArgMatches,
-    clock: &dyn Clock,
-) -> anyhow::Result<(Time, Time, Tags, Option<Description>)> {
+// start time, stop time, tags, description, allow overlap, allow future, if not exists
+type TrackArgs = (Time, Time, Tags, Option<Description>, bool, bool, bool);
+
+pub fn parse_track_args(track_m: &ArgMatches, clock: &dyn Clock) -> anyhow::Result<TrackArgs> {
     let description = track_m.value_of("description").map(|s| s.to_string());
+    let allow_overlap = track_m.is_present("allow-overlap");
+    let allow_future = track_m.is_present("allow-future");
+    let if_not_exists = track_m.is_present("if-not-exists");
     let values_arg = track_m
         .values_of("tokens")
         .expect("start time, end time and at least 1 tag required");
     let values: Tags = values_arg.map(String::from).collect();
-    let (range_start, range_end, activity_tags) = split_time_range_from_tags(&values, clock)?;
-    Ok((range_start, range_end, activity_tags, description))
+    let for_duration = track_m
+        .value_of("for")
+        .map(parse_track_duration)
+        .transpose()?;
+    let (range_start, range_end, activity_tags) = match for_duration {
+        Some(duration) => {
+            let (range_start, activity_tags) = split_time_clue_from_tags(&values, clock);
+            let range_end = time_after(range_start, duration, clock);
+            (range_start, range_end, activity_tags)
+        }
+        None => match split_time_and_duration_from_tags(&values, clock)? {
+            Some(range_and_tags) => range_and_tags,
+            None => match split_time_until_now_from_tags(&values, clock)? {
+                Some(range_and_tags) => range_and_tags,
+                None => split_time_range_from_tags(&values, clock)?,
+            },
+        },
+    };
+    let activity_tags = normalize_tags(activity_tags)?;
+    Ok((
+        range_start,
+        range_end,
+        activity_tags,
+        description,
+        allow_overlap,
+        allow_future,
+        if_not_exists,
+    ))
 }
 
-pub fn parse_stop_args(
-    stop_m: &ArgMatches,
-    clock: &dyn Clock,
-) -> anyhow::Result<(Time, Option<ActivityId>)> {
+// stop time, stopped id, stop all
+// stop time, stopped id, stop all, tags
+// stop time, stopped id, stop all, tags, description
+type StopArgs = (Time, Option<ActivityId>, bool, Tags, Option<Description>);
+
+pub fn parse_stop_args(stop_m: &ArgMatches, clock: &dyn Clock) -> anyhow::Result<StopArgs> {
     let stopped_id_maybe = stop_m
         .value_of("id")
         .map(|id_str| usize::from_str(id_str))
         .transpose()?;
-    let time_arg = stop_m.values_of("time");
-    if let Some(values) = time_arg {
-        let values: Vec<String> = values.map(String::from).collect();
-        let time_str = values.join(" ");
-        let stop_time = TimeTools::time_from_str(&time_str, clock)?;
-        Ok((stop_time, stopped_id_maybe))
-    } else {
-        Ok((Time::Now, stopped_id_maybe))
+    let stop_all = stop_m.is_present("all");
+    let values: Vec<String> = stop_m
+        .values_of("tokens")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default();
+    let (stop_time, tags) = split_time_clue_from_tags(&values, clock);
+    // `stop 5min` or `stop -5min`: 5 minutes ago, like `stop 5min ago`
+    let (stop_time, tags) = match (stop_time, tags.split_first()) {
+        (Time::Now, Some((first, rest))) => match TimeTools::time_before_now_from_str(first, clock)
+        {
+            Some(stop_time) => (stop_time, rest.to_vec()),
+            None => (Time::Now, tags),
+        },
+        (stop_time, _) => (stop_time, tags),
+    };
+    let tags = normalize_tags(tags)?;
+    if !tags.is_empty() && stop_all {
+        return Err(anyhow::anyhow!("--all cannot be combined with tags"));
+    }
+    if !tags.is_empty() && stopped_id_maybe.is_some() {
+        return Err(anyhow::anyhow!("--id cannot be combined with tags"));
+    }
+    let description = stop_m.value_of("description").map(|s| s.to_string());
+    Ok((stop_time, stopped_id_maybe, stop_all, tags, description))
+}
+
+/// Command line arguments with `stop -5min` read as `stop 5min`
+///
+/// clap would take a negative duration following `stop` for a flag, other flags are left as is.
+pub fn unsigned_stop_duration<I>(args: I) -> Vec<OsString>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut args: Vec<OsString> = args.into_iter().collect();
+    if let Some(stop) = args.iter().skip(1).position(|arg| arg == "stop") {
+        let index = stop + 2;
+        let unsigned = args
+            .get(index)
+            .and_then(|arg| arg.to_str())
+            .and_then(|arg| arg.strip_prefix('-'))
+            .filter(|duration| {
+                duration.starts_with(|c: char| c.is_ascii_digit())
+                    && TimeTools::duration_before_now_from_str(duration).is_some()
+            })
+            .map(OsString::from);
+        if let Some(unsigned) = unsigned {
+            args[index] = unsigned;
+        }
     }
+    args
 }
 
 pub fn parse_cancel_args(cancel_m: &ArgMatches) -> anyhow::Result<Option<ActivityId>> {
@@ -360,21 +1446,70 @@ pub fn parse_cancel_args(cancel_m: &ArgMatches) -> anyhow::Result<Option<Activit
     Ok(cancelled_id_maybe)
 }
 
+// range, display id, display description, (tags, any)
+type SummaryArgs = ((DateTimeW, DateTimeW), bool, bool, (Tags, bool));
+
 pub fn parse_summary_args(
     summary_m: &ArgMatches,
     clock: &dyn Clock,
-) -> anyhow::Result<((DateTimeW, DateTimeW), bool, bool)> {
+    week_start: Weekday,
+) -> anyhow::Result<SummaryArgs> {
     let display_id = summary_m.is_present("id");
     let display_description = summary_m.is_present("description");
+    let any = summary_m.is_present("any");
     let values_arg = summary_m.values_of("tokens");
-    if let Some(values) = values_arg {
-        let values: Vec<String> = values.map(String::from).collect();
-        let range_maybe = split_time_range(&values, clock);
+    if summary_m.is_present("since") || summary_m.is_present("until") {
+        let range_start = match summary_m.value_of("since") {
+            None => range::beginning_of_time(),
+            Some(since) => clock.date_time(TimeTools::time_from_str(since, clock)?),
+        };
+        let range_end = match summary_m.value_of("until") {
+            None => clock.get_time(),
+            Some(until) if TimeTools::is_date(until) => {
+                let until = clock.date_time(TimeTools::time_from_str(until, clock)?);
+                range::day_range(until).1
+            }
+            Some(until) => clock.date_time(TimeTools::time_from_str(until, clock)?),
+        };
+        // remaining tokens are tags
+        let tags: Tags = values_arg
+            .map(|values| values.map(String::from).collect())
+            .unwrap_or_default();
+        return Ok((
+            (range_start, range_end),
+            display_id,
+            display_description,
+            (tags, any),
+        ));
+    }
+    // key:value tags may be given with any range e.g. `09:00 - 10:00 project:alpha`, or alone
+    let (key_values, values): (Tags, Tags) = values_arg
+        .map(|values| values.map(String::from).collect::<Tags>())
+        .unwrap_or_default()
+        .into_iter()
+        .partition(|value| tag_key_value(value).is_some());
+    if !values.is_empty() {
+        // tags may follow a range token e.g. `:week foo bar`
+        let (range_maybe, tags) = match values.as_slice() {
+            [token, tags @ ..] if range::is_range_token(token) => (
+                split_time_range_or_token(&values[..1], clock, week_start),
+                tags.to_vec(),
+            ),
+            _ => (
+                split_time_range_or_token(&values, clock, week_start),
+                vec![],
+            ),
+        };
         return match range_maybe {
             Ok((range_start, range_end)) => {
                 let range_start = clock.date_time(range_start);
                 let range_end = clock.date_time(range_end);
-                Ok(((range_start, range_end), display_id, display_description))
+                Ok((
+                    (range_start, range_end),
+                    display_id,
+                    display_description,
+                    ([tags, key_values].concat(), any),
+                ))
             }
             Err(e) => Err(anyhow::anyhow!(e)),
         };
@@ -383,25 +1518,229 @@ pub fn parse_summary_args(
         if summary_m.is_present("yesterday") {
             clock.yesterday_range()
         } else if summary_m.is_present("lastweek") {
-            clock.last_week_range()
+            clock.last_week_range(week_start)
         } else if summary_m.is_present("week") {
-            clock.this_week_range()
+            clock.this_week_range(week_start)
         } else {
             clock.today_range()
         }
     };
-    Ok((range, display_id, display_description))
+    Ok((range, display_id, display_description, (key_values, any)))
+}
+
+pub fn parse_duration_format_args(m: &ArgMatches) -> anyhow::Result<DurationFormat> {
+    match m.value_of("duration-format") {
+        None | Some("clock") => Ok(DurationFormat::Clock),
+        Some("human") => Ok(DurationFormat::Human),
+        Some("days") => Ok(DurationFormat::Days),
+        Some("decimal") => Ok(DurationFormat::Decimal),
+        _ => Err(anyhow::anyhow!("invalid duration format")), // should never happen thanks to clap check
+    }
+}
+
+pub fn parse_precision_args(m: &ArgMatches) -> anyhow::Result<DurationPrecision> {
+    match m.value_of("precision") {
+        None | Some("second") => Ok(DurationPrecision::Second),
+        Some("minute") => Ok(DurationPrecision::Minute),
+        _ => Err(anyhow::anyhow!("invalid precision")), // should never happen thanks to clap check
+    }
+}
+
+pub fn parse_week_start_args(m: &ArgMatches) -> anyhow::Result<Option<Weekday>> {
+    match m.value_of("week-start") {
+        None => Ok(None),
+        Some(day) => Weekday::from_str(day)
+            .map(Some)
+            .map_err(|_| anyhow::anyhow!("invalid week start {}", day)),
+    }
+}
+
+// longer than, shorter than
+pub fn parse_duration_filter_args(
+    m: &ArgMatches,
+) -> anyhow::Result<(Option<Duration>, Option<Duration>)> {
+    let longer_than = m
+        .value_of("longer-than")
+        .map(TimeTools::duration_from_str)
+        .transpose()?;
+    let shorter_than = m
+        .value_of("shorter-than")
+        .map(TimeTools::duration_from_str)
+        .transpose()?;
+    Ok((longer_than, shorter_than))
+}
+
+pub fn parse_round_args(
+    summary_m: &ArgMatches,
+) -> anyhow::Result<(Option<u32>, Option<RoundMethod>)> {
+    let minutes_maybe = summary_m.value_of("round").map(u32::from_str).transpose()?;
+    let method_maybe = match summary_m.value_of("round-method") {
+        None => None,
+        Some("up") => Some(RoundMethod::Up),
+        Some("nearest") => Some(RoundMethod::Nearest),
+        Some("down") => Some(RoundMethod::Down),
+        _ => return Err(anyhow::anyhow!("invalid round method")), // should never happen thanks to clap check
+    };
+    Ok((minutes_maybe, method_maybe))
+}
+
+// sort key, reverse
+pub fn parse_sort_args(m: &ArgMatches) -> anyhow::Result<(ActivityOrder, bool)> {
+    let order = match m.value_of("sort") {
+        None | Some("start") => ActivityOrder::Start,
+        Some("duration") => ActivityOrder::Duration,
+        Some("tags") => ActivityOrder::Tags,
+        _ => return Err(anyhow::anyhow!("invalid sort key")), // should never happen thanks to clap check
+    };
+    Ok((order, m.is_present("reverse")))
+}
+
+// sort key and reverse for `summary --by tag`: total (default, descending) or tags
+pub fn parse_tag_sort_args(m: &ArgMatches) -> anyhow::Result<(ActivityOrder, bool)> {
+    let (order, reverse) = parse_sort_args(m)?;
+    match (order, m.occurrences_of("sort")) {
+        // default value
+        (ActivityOrder::Start, 0) => Ok((ActivityOrder::Duration, reverse)),
+        (ActivityOrder::Start, _) => Err(anyhow::anyhow!(
+            "--by tag sorts by duration or tags, not start"
+        )),
+        _ => Ok((order, reverse)),
+    }
+}
+
+// --with-current or --no-current, None when omitted
+pub fn parse_with_current_args(m: &ArgMatches) -> Option<bool> {
+    if m.is_present("with-current") {
+        Some(true)
+    } else if m.is_present("no-current") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// summary line width: --max-width, else the terminal width when stdout is a terminal
+pub fn parse_max_width_args(m: &ArgMatches) -> anyhow::Result<Option<usize>> {
+    if m.is_present("no-truncate") {
+        return Ok(None);
+    }
+    match m.value_of("max-width") {
+        Some(width) => Ok(Some(usize::from_str(width)?)),
+        None if atty::is(atty::Stream::Stdout) => {
+            Ok(term_size::dimensions().map(|(width, _height)| width))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn parse_optional_range_args(
+    tags_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<Option<(DateTimeW, DateTimeW)>> {
+    let values_arg = tags_m.values_of("tokens");
+    if let Some(values) = values_arg {
+        let values: Vec<String> = values.map(String::from).collect();
+        let (range_start, range_end) = split_time_range_or_token(&values, clock, week_start)?;
+        let range_start = clock.date_time(range_start);
+        let range_end = clock.date_time(range_end);
+        return Ok(Some((range_start, range_end)));
+    }
+    if tags_m.is_present("yesterday") {
+        Ok(Some(clock.yesterday_range()))
+    } else if tags_m.is_present("lastweek") {
+        Ok(Some(clock.last_week_range(week_start)))
+    } else if tags_m.is_present("week") {
+        Ok(Some(clock.this_week_range(week_start)))
+    } else {
+        Ok(None)
+    }
+}
+
+pub fn parse_total_args(
+    total_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(Tag, Option<(DateTimeW, DateTimeW)>)> {
+    let tag = total_m
+        .value_of("tag")
+        .map(|tag| tag.trim().to_string())
+        .expect("tag is required");
+    let range_maybe = parse_optional_range_args(total_m, clock, week_start)?;
+    Ok((tag, range_maybe))
+}
+
+pub fn parse_goal_add_args(add_m: &ArgMatches) -> anyhow::Result<(Tag, Duration)> {
+    let tag = add_m
+        .value_of("tag")
+        .map(|tag| tag.trim().to_string())
+        .expect("tag is required");
+    let duration = add_m.value_of("duration").expect("duration is required");
+    let target = TimeTools::duration_from_str(duration)?;
+    if target <= Duration::zero() {
+        return Err(anyhow::anyhow!("goal '{}' must be positive", duration));
+    }
+    Ok((tag, target))
+}
+
+pub fn parse_goal_remove_args(remove_m: &ArgMatches) -> Tag {
+    remove_m
+        .value_of("tag")
+        .map(|tag| tag.trim().to_string())
+        .expect("tag is required")
+}
+
+// `rtw goal` without subcommand displays the status of this week
+pub fn parse_goal_status_args(
+    status_m: Option<&ArgMatches>,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(DateTimeW, DateTimeW)> {
+    match status_m.and_then(|m| m.values_of("tokens")) {
+        Some(values) => {
+            let values: Vec<String> = values.map(String::from).collect();
+            let (range_start, range_end) = split_time_range_or_token(&values, clock, week_start)?;
+            Ok((clock.date_time(range_start), clock.date_time(range_end)))
+        }
+        None => range::range_from_token(":week", clock, week_start),
+    }
+}
+
+pub fn parse_export_args(
+    export_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(ExportFormat, Option<(DateTimeW, DateTimeW)>)> {
+    let range_maybe = parse_optional_range_args(export_m, clock, week_start)?;
+    match export_m.value_of("format") {
+        Some("csv") => Ok((ExportFormat::Csv, range_maybe)),
+        Some("json") => Ok((ExportFormat::Json, range_maybe)),
+        _ => Err(anyhow::anyhow!("invalid format")), // should never happen thanks to clap check
+    }
+}
+
+pub fn parse_import_args(import_m: &ArgMatches) -> anyhow::Result<(PathBuf, ImportFormat)> {
+    let format = match import_m.value_of("from") {
+        Some("rtw") => Ok(ImportFormat::Rtw),
+        Some("timewarrior") => Ok(ImportFormat::Timewarrior),
+        _ => Err(anyhow::anyhow!("invalid format")), // should never happen thanks to clap check
+    }?;
+    match import_m.value_of("file") {
+        Some(file) => Ok((PathBuf::from(file), format)),
+        None => Err(anyhow::anyhow!("missing file")), // should never happen thanks to clap check
+    }
 }
 
 pub fn parse_timeline_args(
     timeline_m: &ArgMatches,
     clock: &dyn Clock,
+    week_start: Weekday,
 ) -> anyhow::Result<((DateTimeW, DateTimeW), bool)> {
     let display_id = timeline_m.is_present("id");
     let values_arg = timeline_m.values_of("tokens");
     if let Some(values) = values_arg {
         let values: Vec<String> = values.map(String::from).collect();
-        let range_maybe = split_time_range(&values, clock);
+        let range_maybe = split_time_range_or_token(&values, clock, week_start);
         match range_maybe {
             Ok((range_start, range_end)) => {
                 let range_start = clock.date_time(range_start);
@@ -415,6 +1754,102 @@ pub fn parse_timeline_args(
     }
 }
 
+pub fn parse_report_args(
+    report_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(Option<(DateTimeW, DateTimeW)>, Period)> {
+    let range_maybe = parse_optional_range_args(report_m, clock, week_start)?;
+    match report_m.value_of("by") {
+        Some("day") => Ok((range_maybe, Period::Day)),
+        Some("week") => Ok((range_maybe, Period::Week(week_start))),
+        _ => Err(anyhow::anyhow!("invalid period")), // should never happen thanks to clap check
+    }
+}
+
+pub fn parse_overlaps_args(
+    overlaps_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(DateTimeW, DateTimeW)> {
+    match overlaps_m.values_of("tokens") {
+        Some(values) => {
+            let values: Vec<String> = values.map(String::from).collect();
+            let (range_start, range_end) = split_time_range_or_token(&values, clock, week_start)?;
+            Ok((clock.date_time(range_start), clock.date_time(range_end)))
+        }
+        None => Ok(clock.today_range()),
+    }
+}
+
+pub fn parse_gaps_args(
+    gaps_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<((DateTimeW, DateTimeW), Duration)> {
+    let min_duration = gaps_m
+        .value_of("min")
+        .map(TimeTools::duration_from_str)
+        .transpose()?
+        .unwrap_or_else(|| Duration::minutes(1));
+    let values_arg = gaps_m.values_of("tokens");
+    if let Some(values) = values_arg {
+        let values: Vec<String> = values.map(String::from).collect();
+        let (range_start, range_end) = split_time_range_or_token(&values, clock, week_start)?;
+        let range_start = clock.date_time(range_start);
+        let range_end = clock.date_time(range_end);
+        Ok(((range_start, range_end), min_duration))
+    } else {
+        Ok((clock.today_range(), min_duration))
+    }
+}
+
+pub fn parse_day_args(
+    day_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(DateTimeW, DateTimeW)> {
+    let values_arg = day_m.values_of("tokens");
+    if let Some(values) = values_arg {
+        let values: Vec<String> = values.map(String::from).collect();
+        let day = match values.as_slice() {
+            [token] if range::is_range_token(token) => {
+                range::range_from_token(token, clock, week_start)?.0
+            }
+            _ => clock.date_time(TimeTools::time_from_str(&values.join(" "), clock)?),
+        };
+        Ok(range::day_range(day))
+    } else {
+        Ok(clock.today_range())
+    }
+}
+
+pub fn parse_continue_args(
+    continue_m: &ArgMatches,
+    clock: &dyn Clock,
+) -> anyhow::Result<(Option<ActivityId>, Time, bool)> {
+    let continued_id_maybe = continue_m.value_of("id").map(usize::from_str).transpose()?;
+    let pick = continue_m.is_present("pick");
+    let time_arg = continue_m.values_of("time");
+    if let Some(values) = time_arg {
+        let values: Vec<String> = values.map(String::from).collect();
+        let time_str = values.join(" ");
+        let start_time = TimeTools::time_from_str(&time_str, clock)?;
+        Ok((continued_id_maybe, start_time, pick))
+    } else {
+        Ok((continued_id_maybe, Time::Now, pick))
+    }
+}
+
+pub fn parse_last_args(last_m: &ArgMatches) -> anyhow::Result<usize> {
+    match last_m.value_of("count") {
+        None => Ok(1),
+        Some(count) => {
+            usize::from_str(count).map_err(|_| anyhow::anyhow!("could not parse count {}", count))
+        }
+    }
+}
+
 pub fn parse_delete_args(delete_m: &ArgMatches) -> anyhow::Result<ActivityId> {
     let id_opt = delete_m
         .value_of("id")
@@ -426,6 +1861,138 @@ pub fn parse_delete_args(delete_m: &ArgMatches) -> anyhow::Result<ActivityId> {
     }
 }
 
+// tags (all required), range, skip confirmation
+type DeleteFilterArgs = (Tags, Option<(DateTimeW, DateTimeW)>, bool);
+
+pub fn parse_delete_filter_args(
+    delete_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<DeleteFilterArgs> {
+    let tags: Tags = delete_m
+        .values_of("tag")
+        .map(|values| values.map(String::from).collect())
+        .unwrap_or_default();
+    let range_maybe = match delete_m.values_of("range") {
+        None => None,
+        Some(values) => {
+            let values: Vec<String> = values.map(String::from).collect();
+            let (range_start, range_end) = split_time_range_or_token(&values, clock, week_start)?;
+            Some((clock.date_time(range_start), clock.date_time(range_end)))
+        }
+    };
+    Ok((tags, range_maybe, delete_m.is_present("yes")))
+}
+
+pub fn parse_annotate_args(annotate_m: &ArgMatches) -> anyhow::Result<(ActivityId, Description)> {
+    let id = parse_delete_args(annotate_m)?;
+    let values_arg = annotate_m.values_of("text");
+    match values_arg {
+        Some(values) => Ok((id, values.collect::<Vec<&str>>().join(" "))),
+        None => Err(anyhow::anyhow!("missing description")), // should never happen thanks to clap check
+    }
+}
+
+// id, new start time, new stop time, new tags
+type ModifyArgs = (ActivityId, Option<Time>, Option<Time>, Option<Tags>);
+
+pub fn parse_split_args(
+    split_m: &ArgMatches,
+    clock: &dyn Clock,
+) -> anyhow::Result<(ActivityId, Time, Option<Tags>)> {
+    let id = parse_delete_args(split_m)?;
+    let time = TimeTools::time_from_str(split_m.value_of("time").unwrap(), clock)?;
+    let tags_maybe: Option<Tags> = split_m
+        .values_of("tags")
+        .map(|values| values.map(String::from).collect());
+    Ok((id, time, tags_maybe))
+}
+
+pub fn parse_merge_args(merge_m: &ArgMatches) -> anyhow::Result<(ActivityId, ActivityId, bool)> {
+    let first = usize::from_str(merge_m.value_of("first").unwrap())?;
+    let second = usize::from_str(merge_m.value_of("second").unwrap())?;
+    if first == second {
+        return Err(anyhow::anyhow!(
+            "cannot merge activity {} with itself",
+            first
+        ));
+    }
+    Ok((first, second, merge_m.is_present("force")))
+}
+
+pub fn parse_modify_args(modify_m: &ArgMatches, clock: &dyn Clock) -> anyhow::Result<ModifyArgs> {
+    let id = parse_delete_args(modify_m)?;
+    let start_time_maybe = modify_m
+        .value_of("start")
+        .map(|s| TimeTools::time_from_str(s, clock))
+        .transpose()?;
+    let stop_time_maybe = modify_m
+        .value_of("end")
+        .map(|s| TimeTools::time_from_str(s, clock))
+        .transpose()?;
+    let tags_maybe: Option<Tags> = modify_m
+        .value_of("tags")
+        .map(|s| normalize_tags(s.split(',').map(String::from).collect()))
+        .transpose()?;
+    Ok((id, start_time_maybe, stop_time_maybe, tags_maybe))
+}
+
+// id, delta, allow overlap, allow future
+pub fn parse_move_args(move_m: &ArgMatches) -> anyhow::Result<(ActivityId, Duration, bool, bool)> {
+    let id = parse_delete_args(move_m)?;
+    let delta = TimeTools::signed_duration_from_str(move_m.value_of("delta").unwrap())?;
+    let allow_overlap = move_m.is_present("allow-overlap");
+    let allow_future = move_m.is_present("allow-future");
+    Ok((id, delta, allow_overlap, allow_future))
+}
+
+pub fn parse_rename_args(rename_m: &ArgMatches) -> anyhow::Result<(Tag, Tag, bool)> {
+    let dry_run = rename_m.is_present("dry-run");
+    match (rename_m.value_of("old"), rename_m.value_of("new")) {
+        (Some(old), Some(new)) => {
+            // the new tag is validated like tags given to start or track
+            let new = normalize_tags(vec![new.to_string()])?.remove(0);
+            Ok((old.to_string(), new, dry_run))
+        }
+        _ => Err(anyhow::anyhow!("missing tag")), // should never happen thanks to clap check
+    }
+}
+
+// range, tags filter (all required), tags to add, tags to remove, dry run
+type RetagArgs = (Option<(DateTimeW, DateTimeW)>, Tags, Tags, Tags, bool);
+
+pub fn parse_retag_args(
+    retag_m: &ArgMatches,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<RetagArgs> {
+    let range_maybe = parse_optional_range_args(retag_m, clock, week_start)?;
+    let values = |name: &str| -> Vec<String> {
+        retag_m
+            .values_of(name)
+            .map(|values| values.map(String::from).collect())
+            .unwrap_or_default()
+    };
+    // added tags are validated like tags given to start or track
+    let add = normalize_tags(values("add"))?;
+    Ok((
+        range_maybe,
+        values("tag"),
+        add,
+        values("remove"),
+        retag_m.is_present("dry-run"),
+    ))
+}
+
+pub fn parse_output_format_args(m: &ArgMatches) -> anyhow::Result<OutputFormat> {
+    match m.value_of("format") {
+        Some("text") => Ok(OutputFormat::Text),
+        Some("json") => Ok(OutputFormat::Json),
+        Some("markdown") => Ok(OutputFormat::Markdown),
+        _ => Err(anyhow::anyhow!("invalid format")), // should never happen thanks to clap check
+    }
+}
+
 pub fn parse_completion_args(completion_m: &ArgMatches) -> anyhow::Result<clap::Shell> {
     let shell_maybe = completion_m.value_of("shell");
     match shell_maybe {
@@ -443,16 +2010,38 @@ pub fn parse_completion_args(completion_m: &ArgMatches) -> anyhow::Result<clap::
 mod tests {
     use crate::chrono_clock::ChronoClock;
     use crate::cli_helper::{
-        split_time_clue_from_tags, split_time_range, split_time_range_from_tags,
+        normalize_tags, split_time_clue_from_tags, split_time_range, split_time_range_from_tags,
+        split_time_until_now_from_tags, unsigned_stop_duration,
     };
     use crate::rtw_core::clock::Time;
     use crate::rtw_core::Tags;
     use crate::time_tools::TimeTools;
+    use std::ffi::OsString;
+
+    #[test]
+    fn test_unsigned_stop_duration() {
+        let args = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+        let table = vec![
+            (vec!["rtw", "stop", "-5min"], vec!["rtw", "stop", "5min"]),
+            (
+                vec!["rtw", "-d", "dir", "stop", "-1h30m", "foo"],
+                vec!["rtw", "-d", "dir", "stop", "1h30m", "foo"],
+            ),
+            // not durations: left to clap
+            (vec!["rtw", "stop", "--all"], vec!["rtw", "stop", "--all"]),
+            (vec!["rtw", "stop", "-5"], vec!["rtw", "stop", "-5"]),
+            (vec!["rtw", "stop", "--5min"], vec!["rtw", "stop", "--5min"]),
+            (vec!["rtw", "start", "-5min"], vec!["rtw", "start", "-5min"]),
+        ];
+        for (given, expected) in table {
+            assert_eq!(unsigned_stop_duration(args(&given)), args(&expected));
+        }
+    }
 
     #[test]
     // rtw start
     fn test_split_time_clue_from_tags_0_0() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let values: Tags = vec![];
         let (time, tags) = split_time_clue_from_tags(&values, &clock);
         assert_eq!(Time::Now, time);
@@ -462,7 +2051,7 @@ mod tests {
     #[test]
     // rtw start foo
     fn test_split_time_clue_from_tags_0_1() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let values: Tags = vec![String::from("foo")];
         let (time, tags) = split_time_clue_from_tags(&values, &clock);
         assert_eq!(Time::Now, time);
@@ -472,7 +2061,7 @@ mod tests {
     #[test]
     // rtw start foo bar
     fn test_split_time_clue_from_tags_0_2() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let values: Tags = vec![String::from("foo"), String::from("bar")];
         let (time, tags) = split_time_clue_from_tags(&values, &clock);
         assert_eq!(Time::Now, time);
@@ -482,7 +2071,7 @@ mod tests {
     #[test]
     // rtw start 1 h ago
     fn test_split_time_clue_from_tags_3_0() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let values: Tags = vec![String::from("1"), String::from("h"), String::from("ago")];
         let (time, tags) = split_time_clue_from_tags(&values, &clock);
         assert_ne!(Time::Now, time);
@@ -492,7 +2081,7 @@ mod tests {
     #[test]
     // rtw start 1 h ago foo
     fn test_split_time_clue_from_tags_3_1() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let tokens: Vec<String> = vec![
             String::from("1"),
             String::from("h"),
@@ -507,7 +2096,7 @@ mod tests {
     #[test]
     // rtw track 09:00 - 10:00 foo
     fn test_split_time_range_from_tags_1_1_1() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let tokens: Vec<String> = vec![
             String::from("09:00"),
             String::from("-"),
@@ -518,10 +2107,35 @@ mod tests {
         assert!(time_range_and_tags.is_ok());
     }
 
+    #[test]
+    // rtw track 09:00 now foo
+    fn test_split_time_until_now_from_tags() {
+        let clock = ChronoClock::default();
+        let tokens: Vec<String> = vec![
+            String::from("09:00"),
+            String::from("now"),
+            String::from("foo"),
+        ];
+        let (_start, end, tags) = split_time_until_now_from_tags(&tokens, &clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(end, Time::Now);
+        assert_eq!(tags, vec![String::from("foo")]);
+        let tokens: Vec<String> = vec![
+            String::from("09:00"),
+            String::from("-"),
+            String::from("now"),
+            String::from("foo"),
+        ];
+        assert!(split_time_until_now_from_tags(&tokens, &clock)
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     // rtw summary 09:00 - 10:00
     fn test_split_range_1_1() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let tokens: Vec<String> = vec![
             String::from("09:00"),
             String::from("-"),
@@ -543,10 +2157,46 @@ mod tests {
     #[test]
     // rtw summary 09:00 -
     fn test_split_range_1_0() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let tokens: Vec<String> = vec![String::from("09:00"), String::from("-")];
         let time_range = split_time_range(&tokens, &clock);
         assert!(time_range.is_ok());
         assert_eq!(time_range.unwrap().1, Time::Now)
     }
+
+    #[test]
+    fn test_normalize_tags_trim() {
+        let raw = vec![String::from(" foo"), String::from("bar ")];
+        assert_eq!(
+            normalize_tags(raw).unwrap(),
+            vec![String::from("foo"), String::from("bar")]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tags_dedup() {
+        let raw = vec![
+            String::from("foo"),
+            String::from("bar"),
+            String::from(" foo "),
+        ];
+        assert_eq!(
+            normalize_tags(raw).unwrap(),
+            vec![String::from("foo"), String::from("bar")]
+        );
+    }
+
+    #[test]
+    fn test_normalize_tags_reject_empty() {
+        assert!(normalize_tags(vec![String::from("")]).is_err());
+        assert!(normalize_tags(vec![String::from("foo"), String::from(" \t")]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_tags_reject_forbidden_chars() {
+        assert!(normalize_tags(vec![String::from("foo;bar")]).is_err());
+        assert!(normalize_tags(vec![String::from("foo\nbar")]).is_err());
+        // quoted in CSV export
+        assert!(normalize_tags(vec![String::from("foo,bar")]).is_ok());
+    }
 }
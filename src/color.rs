@@ -0,0 +1,59 @@
+//! Terminal colors for reports
+use ansi_term::{Color, Style};
+
+/// Environment variable disabling colors when set (see <https://no-color.org>)
+const NO_COLOR_ENV: &str = "NO_COLOR";
+
+/// Colors unless `--no-color`, `$NO_COLOR` or stdout is not a terminal
+pub fn should_enable(no_color_flag: bool) -> bool {
+    !no_color_flag
+        && std::env::var_os(NO_COLOR_ENV)
+            .filter(|v| !v.is_empty())
+            .is_none()
+        && atty::is(atty::Stream::Stdout)
+}
+
+/// Paints report strings, plain text when disabled
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Painter {
+    enabled: bool,
+}
+
+impl Painter {
+    pub fn new(enabled: bool) -> Self {
+        Painter { enabled }
+    }
+
+    fn paint(&self, style: Style, s: &str) -> String {
+        if self.enabled {
+            style.paint(s).to_string()
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// Highlight tags (pad before painting, escape codes break alignment)
+    pub fn tag(&self, s: &str) -> String {
+        self.paint(Color::Cyan.normal(), s)
+    }
+
+    /// Bold totals
+    pub fn bold(&self, s: &str) -> String {
+        self.paint(Style::new().bold(), s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::Painter;
+
+    #[test]
+    fn test_paint() {
+        let plain = Painter::new(false);
+        assert_eq!(plain.tag("foo"), "foo");
+        assert_eq!(plain.bold("Total"), "Total");
+        let colored = Painter::new(true);
+        assert_eq!(colored.tag("foo"), "\x1b[36mfoo\x1b[0m");
+        assert_eq!(colored.bold("Total"), "\x1b[1mTotal\x1b[0m");
+    }
+}
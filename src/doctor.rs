@@ -0,0 +1,288 @@
+//! Consistency checks of stored activities, for `rtw doctor`.
+//!
+//! Each check returns its findings, an empty list when everything is fine.
+use crate::report::overlaps;
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::{ActivityId, Tag};
+use itertools::Itertools;
+use std::fmt;
+
+type ActivityWithId = (ActivityId, Activity);
+type OngoingActivityWithId = (ActivityId, OngoingActivity);
+
+/// Problem found in stored activities
+#[derive(Debug, Clone, PartialEq)]
+pub enum Finding {
+    /// Storage files could not be read
+    Unreadable(String),
+    /// Finished activities sharing some time
+    Overlap(ActivityId, ActivityId),
+    /// Finished activity stopping before it starts
+    StopBeforeStart(ActivityId),
+    /// Finished activity stopping when it starts
+    ZeroLength(ActivityId),
+    /// Ongoing activity (first id) already finished (second id): same start and tags
+    OngoingAlsoFinished(ActivityId, ActivityId),
+    /// Finished activity stopping in the future
+    FinishedInFuture(ActivityId),
+    /// Ongoing activity starting in the future
+    OngoingInFuture(ActivityId),
+}
+
+impl Finding {
+    /// True if `rtw doctor --fix` repairs this finding without losing tracked time
+    ///
+    /// Zero-length activities are deleted, ongoing activities already finished are removed.
+    pub fn is_fixable(&self) -> bool {
+        matches!(
+            self,
+            Finding::ZeroLength(_) | Finding::OngoingAlsoFinished(_, _)
+        )
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::Unreadable(e) => write!(f, "unreadable storage: {}", e),
+            Finding::Overlap(id, other_id) => {
+                write!(f, "activities {} and {} overlap", id, other_id)
+            }
+            Finding::StopBeforeStart(id) => write!(f, "activity {} stops before it starts", id),
+            Finding::ZeroLength(id) => write!(f, "activity {} lasts 0s", id),
+            Finding::OngoingAlsoFinished(ongoing_id, finished_id) => write!(
+                f,
+                "ongoing activity {} is already finished as activity {}",
+                ongoing_id, finished_id
+            ),
+            Finding::FinishedInFuture(id) => write!(f, "activity {} stops in the future", id),
+            Finding::OngoingInFuture(id) => {
+                write!(f, "ongoing activity {} starts in the future", id)
+            }
+        }
+    }
+}
+
+/// Finished activities stopping before (or when) they start
+pub fn check_time_ranges(finished: &[ActivityWithId]) -> Vec<Finding> {
+    finished
+        .iter()
+        .filter_map(|(id, a)| {
+            if a.get_stop_time() < a.get_start_time() {
+                Some(Finding::StopBeforeStart(*id))
+            } else if a.get_stop_time() == a.get_start_time() {
+                Some(Finding::ZeroLength(*id))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Overlapping finished activities, see `report::overlaps`
+pub fn check_overlaps(finished: &[ActivityWithId]) -> Vec<Finding> {
+    overlaps(finished)
+        .into_iter()
+        .map(|((id, _a), (other_id, _other), _interval)| Finding::Overlap(id, other_id))
+        .collect()
+}
+
+/// Ongoing activities with the same start time and tags (in any order) as a finished activity
+pub fn check_ongoing_finished(
+    ongoing: &[OngoingActivityWithId],
+    finished: &[ActivityWithId],
+) -> Vec<Finding> {
+    ongoing
+        .iter()
+        .filter_map(|(ongoing_id, o)| {
+            let tags: Vec<&Tag> = o.tags.iter().sorted().collect();
+            finished
+                .iter()
+                .find(|(_id, a)| {
+                    a.get_start_time() == o.start_time
+                        && a.get_tags().iter().sorted().collect::<Vec<&Tag>>() == tags
+                })
+                .map(|(finished_id, _a)| Finding::OngoingAlsoFinished(*ongoing_id, *finished_id))
+        })
+        .collect()
+}
+
+/// Finished activities stopping after `now`, ongoing activities starting after `now`
+pub fn check_future(
+    ongoing: &[OngoingActivityWithId],
+    finished: &[ActivityWithId],
+    now: DateTimeW,
+) -> Vec<Finding> {
+    finished
+        .iter()
+        .filter(|(_id, a)| a.get_stop_time() > now)
+        .map(|(id, _a)| Finding::FinishedInFuture(*id))
+        .chain(
+            ongoing
+                .iter()
+                .filter(|(_id, o)| o.start_time > now)
+                .map(|(id, _o)| Finding::OngoingInFuture(*id)),
+        )
+        .collect()
+}
+
+/// Every check above, in order
+pub fn check_activities(
+    ongoing: &[OngoingActivityWithId],
+    finished: &[ActivityWithId],
+    now: DateTimeW,
+) -> Vec<Finding> {
+    check_time_ranges(finished)
+        .into_iter()
+        .chain(check_overlaps(finished))
+        .chain(check_ongoing_finished(ongoing, finished))
+        .chain(check_future(ongoing, finished, now))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::doctor::{
+        check_activities, check_future, check_ongoing_finished, check_overlaps, check_time_ranges,
+        Finding,
+    };
+    use crate::rtw_core::activity::{Activity, OngoingActivity};
+    use crate::rtw_core::test_utils::{activity, datetimew};
+
+    // activities stopping before they start cannot be built, only read from a corrupt store
+    fn reversed(activity: Activity) -> Activity {
+        let mut value = serde_json::to_value(activity).unwrap();
+        let start_time = value["start_time"].take();
+        value["start_time"] = value["stop_time"].take();
+        value["stop_time"] = start_time;
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_check_time_ranges() {
+        let finished = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                reversed(activity(
+                    "2020-12-25T10:30:00",
+                    "2020-12-25T11:00:00",
+                    &["foo"],
+                )),
+            ),
+            (
+                2,
+                activity("2020-12-25T12:00:00", "2020-12-25T12:00:00", &["foo"]),
+            ),
+        ];
+        assert_eq!(
+            check_time_ranges(&finished),
+            vec![Finding::StopBeforeStart(1), Finding::ZeroLength(2)]
+        );
+    }
+
+    #[test]
+    fn test_check_overlaps() {
+        let finished = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T09:30:00", "2020-12-25T11:00:00", &["bar"]),
+            ),
+            // touching is not overlapping
+            (
+                2,
+                activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["baz"]),
+            ),
+        ];
+        assert_eq!(check_overlaps(&finished), vec![Finding::Overlap(0, 1)]);
+    }
+
+    #[test]
+    fn test_check_ongoing_finished() {
+        let finished = vec![(
+            3,
+            activity(
+                "2020-12-25T09:00:00",
+                "2020-12-25T10:00:00",
+                &["foo", "bar"],
+            ),
+        )];
+        let ongoing = vec![
+            (
+                0,
+                OngoingActivity::new(
+                    datetimew("2020-12-25T09:00:00"),
+                    vec!["bar".to_string(), "foo".to_string()],
+                    None,
+                ),
+            ),
+            (
+                1,
+                OngoingActivity::new(
+                    datetimew("2020-12-25T09:00:00"),
+                    vec!["foo".to_string()],
+                    None,
+                ),
+            ),
+        ];
+        assert_eq!(
+            check_ongoing_finished(&ongoing, &finished),
+            vec![Finding::OngoingAlsoFinished(0, 3)]
+        );
+    }
+
+    #[test]
+    fn test_check_future() {
+        let now = datetimew("2020-12-25T12:00:00");
+        let finished = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T11:00:00", "2020-12-25T13:00:00", &["foo"]),
+            ),
+        ];
+        let ongoing = vec![(
+            0,
+            OngoingActivity::new(datetimew("2020-12-25T12:30:00"), vec![], None),
+        )];
+        assert_eq!(
+            check_future(&ongoing, &finished, now),
+            vec![Finding::FinishedInFuture(1), Finding::OngoingInFuture(0)]
+        );
+    }
+
+    #[test]
+    fn test_check_activities_all_good() {
+        let finished = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["foo"]),
+            ),
+        ];
+        let ongoing = vec![(
+            0,
+            OngoingActivity::new(datetimew("2020-12-25T11:00:00"), vec![], None),
+        )];
+        assert_eq!(
+            check_activities(&ongoing, &finished, datetimew("2020-12-25T12:00:00")),
+            vec![]
+        );
+        assert!(Finding::ZeroLength(0).is_fixable());
+        assert!(!Finding::Overlap(0, 1).is_fixable());
+    }
+}
@@ -0,0 +1,148 @@
+//! Edit finished activities with `$EDITOR`.
+//!
+//! Activities are exported as JSON to a file in the storage directory, the file is opened
+//! with `$EDITOR` (`vi` when unset) and read back once the editor exits.
+use crate::export::{export_activities_to_json, import_activities_with_ids_from_json};
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::ActivityId;
+use anyhow::anyhow;
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+use std::process::Command;
+
+/// File edited by `rtw edit`, kept when the edit is invalid
+pub const EDIT_FILE_NAME: &str = ".rtw-edit.json";
+
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Changes made to finished activities by an edit
+#[derive(Debug, Default, PartialEq)]
+pub struct EditedActivities {
+    pub deleted: Vec<ActivityId>,
+    pub modified: Vec<(ActivityId, Activity)>,
+    /// Activities without id
+    pub added: Vec<Activity>,
+}
+
+impl EditedActivities {
+    pub fn is_empty(&self) -> bool {
+        self.deleted.is_empty() && self.modified.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Compare `edited` activities with the `original` ones
+///
+/// Fails if an edited id is unknown or duplicated.
+pub fn edited_activities(
+    original: &[(ActivityId, Activity)],
+    edited: Vec<(Option<ActivityId>, Activity)>,
+) -> anyhow::Result<EditedActivities> {
+    let mut changes = EditedActivities::default();
+    let mut kept: HashSet<ActivityId> = HashSet::new();
+    for (id_maybe, activity) in edited {
+        match id_maybe {
+            None => changes.added.push(activity),
+            Some(id) => {
+                if !kept.insert(id) {
+                    return Err(anyhow!("duplicated id {}", id));
+                }
+                match original.iter().find(|(original_id, _)| *original_id == id) {
+                    None => return Err(anyhow!("unknown id {}", id)),
+                    Some((_, original)) if *original == activity => {}
+                    Some(_) => changes.modified.push((id, activity)),
+                }
+            }
+        }
+    }
+    changes.deleted = original
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| !kept.contains(id))
+        .collect();
+    Ok(changes)
+}
+
+/// Open `activities` with `$EDITOR` in `path`, return the edited activities
+///
+/// `path` is removed unless the edited file is invalid.
+pub fn edit_activities(
+    path: &Path,
+    activities: &[(ActivityId, Activity)],
+) -> anyhow::Result<Vec<(Option<ActivityId>, Activity)>> {
+    std::fs::write(path, export_activities_to_json(activities)?)?;
+    // $EDITOR may contain arguments e.g. `code --wait`
+    let editor = std::env::var("EDITOR")
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let mut editor_words = editor.split_whitespace();
+    let program = editor_words.next().unwrap_or(DEFAULT_EDITOR);
+    let status = Command::new(program)
+        .args(editor_words)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow!("could not run editor {}: {}", editor, e))?;
+    if !status.success() {
+        std::fs::remove_file(path)?;
+        return Err(anyhow!(
+            "editor {} failed ({}), nothing changed",
+            editor,
+            status
+        ));
+    }
+    let edited = import_activities_with_ids_from_json(File::open(path)?).map_err(|e| {
+        anyhow!(
+            "invalid edit, nothing changed ({}), edited file kept at {}",
+            e,
+            path.display()
+        )
+    })?;
+    std::fs::remove_file(path)?;
+    Ok(edited)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::{edited_activities, EditedActivities};
+    use crate::rtw_core::activity::Activity;
+    use crate::rtw_core::test_utils::activity;
+
+    #[test]
+    fn test_edited_activities() {
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        let baz = activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["baz"]);
+        let renamed = foo.rename_tag("foo", "qux").unwrap();
+        let original = vec![(0, foo.clone()), (1, bar.clone()), (2, baz.clone())];
+        let unchanged: Vec<(Option<usize>, Activity)> = original
+            .iter()
+            .map(|(id, a)| (Some(*id), a.clone()))
+            .collect();
+        assert!(edited_activities(&original, unchanged).unwrap().is_empty());
+        let edited = vec![
+            (Some(0), renamed.clone()),
+            (Some(2), baz),
+            (None, bar.clone()),
+        ];
+        assert_eq!(
+            edited_activities(&original, edited).unwrap(),
+            EditedActivities {
+                deleted: vec![1],
+                modified: vec![(0, renamed)],
+                added: vec![bar.clone()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_edited_activities_invalid_ids() {
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let original = vec![(0, foo.clone())];
+        let error = edited_activities(&original, vec![(Some(3), foo.clone())]).unwrap_err();
+        assert_eq!(error.to_string(), "unknown id 3");
+        let error =
+            edited_activities(&original, vec![(Some(0), foo.clone()), (Some(0), foo)]).unwrap_err();
+        assert_eq!(error.to_string(), "duplicated id 0");
+    }
+}
@@ -0,0 +1,507 @@
+//! Export finished activities (CSV, JSON, Markdown) and import them back (JSON).
+use crate::cli_helper::normalize_tags;
+use crate::rtw_config::RTWConfig;
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::{ActivityId, Description, Tags};
+use anyhow::anyhow;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+const CSV_HEADER: &str = "id,start,end,duration,tags";
+const CSV_TAGS_SEPARATOR: &str = ";";
+
+/// Supported export formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Output formats for commands displaying activities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    /// GitHub-flavored Markdown table
+    Markdown,
+}
+
+/// Supported import formats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    /// `rtw export --format json`
+    Rtw,
+    /// `timew export`
+    Timewarrior,
+}
+
+/// Finished activity as exported (CSV, JSON), imported (JSON) and summarized (JSON, Markdown)
+///
+/// `duration_seconds` is informative only, imported activities use `start` and `end`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ExportedActivity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<ActivityId>,
+    start: DateTimeW,
+    end: DateTimeW,
+    #[serde(default)]
+    duration_seconds: i64,
+    tags: Tags,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<Description>,
+    /// Ongoing activity summarized as if stopped now
+    #[serde(default, skip_serializing_if = "is_false")]
+    ongoing: bool,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+impl ExportedActivity {
+    pub(crate) fn new(id: ActivityId, activity: &Activity) -> Self {
+        let duration: Duration = activity.get_duration().into();
+        ExportedActivity {
+            id: Some(id),
+            start: activity.get_start_time(),
+            end: activity.get_stop_time(),
+            duration_seconds: duration.num_seconds(),
+            tags: activity.get_tags(),
+            description: activity.get_description(),
+            ongoing: false,
+        }
+    }
+
+    /// Mark as ongoing, ongoing activities have no finished id
+    pub(crate) fn ongoing(self) -> Self {
+        ExportedActivity {
+            id: None,
+            ongoing: true,
+            ..self
+        }
+    }
+
+    /// Override duration e.g. rounded duration
+    pub(crate) fn duration(self, duration: Duration) -> Self {
+        ExportedActivity {
+            duration_seconds: duration.num_seconds(),
+            ..self
+        }
+    }
+
+    /// Omit id
+    pub(crate) fn without_id(self) -> Self {
+        ExportedActivity { id: None, ..self }
+    }
+
+    /// Omit description
+    pub(crate) fn without_description(self) -> Self {
+        ExportedActivity {
+            description: None,
+            ..self
+        }
+    }
+
+    // fails if end < start
+    fn into_activity(self) -> anyhow::Result<Activity> {
+        let tags = normalize_tags(self.tags)?;
+        let activity =
+            OngoingActivity::new(self.start, tags, self.description).into_activity(self.end)?;
+        Ok(activity)
+    }
+}
+
+/// Finished activities with their total duration, as displayed by `summary --format json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Summary {
+    activities: Vec<ExportedActivity>,
+    total_seconds: i64,
+}
+
+/// Ongoing activity as displayed by `rtw current --format json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct CurrentActivity {
+    id: ActivityId,
+    tags: Tags,
+    start: DateTimeW,
+    elapsed_seconds: i64,
+}
+
+// quote field if needed, see RFC 4180
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_row(exported: &ExportedActivity, config: &RTWConfig) -> String {
+    [
+        exported.id.map(|id| id.to_string()).unwrap_or_default(),
+        config.format_datetime(exported.start),
+        config.format_datetime(exported.end),
+        exported.duration_seconds.to_string(),
+        csv_field(&exported.tags.join(CSV_TAGS_SEPARATOR)),
+    ]
+    .join(",")
+}
+
+fn export_activities_to_csv(activities: &[(ActivityId, Activity)], config: &RTWConfig) -> String {
+    std::iter::once(CSV_HEADER.to_string())
+        .chain(
+            activities
+                .iter()
+                .map(|(id, a)| csv_row(&ExportedActivity::new(*id, a), config)),
+        )
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Export finished activities to JSON, with their id
+pub(crate) fn export_activities_to_json(
+    activities: &[(ActivityId, Activity)],
+) -> anyhow::Result<String> {
+    let exported: Vec<ExportedActivity> = activities
+        .iter()
+        .map(|(id, a)| ExportedActivity::new(*id, a))
+        .collect();
+    Ok(serde_json::to_string_pretty(&exported)?)
+}
+
+/// Export finished activities using `format`, CSV dates are displayed as configured
+pub(crate) fn export_activities(
+    activities: &[(ActivityId, Activity)],
+    format: ExportFormat,
+    config: &RTWConfig,
+) -> anyhow::Result<String> {
+    match format {
+        ExportFormat::Csv => Ok(export_activities_to_csv(activities, config)),
+        ExportFormat::Json => export_activities_to_json(activities),
+    }
+}
+
+/// Export summarized activities to JSON, total is the sum of activities `duration_seconds`
+pub(crate) fn export_summary_to_json(activities: Vec<ExportedActivity>) -> anyhow::Result<String> {
+    let total_seconds = activities.iter().map(|a| a.duration_seconds).sum();
+    Ok(serde_json::to_string_pretty(&Summary {
+        activities,
+        total_seconds,
+    })?)
+}
+
+// escape pipes (cell separators), a cell spans a single line
+fn markdown_cell(field: &str) -> String {
+    field.replace('|', "\\|").replace(&['\n', '\r'][..], " ")
+}
+
+/// Export summarized activities to a Markdown table, total is the sum of activities `duration_seconds`
+///
+/// Id and description columns are included when activities have them,
+/// dates and durations are displayed as configured.
+pub(crate) fn export_summary_to_markdown(
+    activities: &[ExportedActivity],
+    config: &RTWConfig,
+) -> String {
+    let with_id = activities.iter().any(|a| a.id.is_some());
+    let with_description = activities.iter().any(|a| a.description.is_some());
+    let row = |id: String,
+               start: String,
+               end: String,
+               duration: String,
+               tags: String,
+               description: String| {
+        let mut cells = vec![];
+        if with_id {
+            cells.push(id);
+        }
+        cells.extend(vec![start, end, duration, tags]);
+        if with_description {
+            cells.push(description);
+        }
+        format!("| {} |", cells.join(" | "))
+    };
+    let header = row(
+        "Id".to_string(),
+        "Start".to_string(),
+        "End".to_string(),
+        "Duration".to_string(),
+        "Tags".to_string(),
+        "Description".to_string(),
+    );
+    let columns = 4 + with_id as usize + with_description as usize;
+    let separator = format!("|{}", "---|".repeat(columns));
+    let total_seconds: i64 = activities.iter().map(|a| a.duration_seconds).sum();
+    let rows = activities.iter().map(|a| {
+        row(
+            a.id.map(|id| id.to_string()).unwrap_or_default(),
+            config.format_datetime(a.start),
+            config.format_datetime(a.end),
+            format!(
+                "{}{}",
+                config.format_duration(Duration::seconds(a.duration_seconds)),
+                if a.ongoing { " (ongoing)" } else { "" }
+            ),
+            markdown_cell(&a.tags.join(" ")),
+            markdown_cell(a.description.as_deref().unwrap_or_default()),
+        )
+    });
+    // total in the first column, under the id when displayed
+    let total = if with_id {
+        row(
+            "**Total**".to_string(),
+            String::new(),
+            String::new(),
+            format!(
+                "**{}**",
+                config.format_duration(Duration::seconds(total_seconds))
+            ),
+            String::new(),
+            String::new(),
+        )
+    } else {
+        row(
+            String::new(),
+            "**Total**".to_string(),
+            String::new(),
+            format!(
+                "**{}**",
+                config.format_duration(Duration::seconds(total_seconds))
+            ),
+            String::new(),
+            String::new(),
+        )
+    };
+    std::iter::once(header)
+        .chain(std::iter::once(separator))
+        .chain(rows)
+        .chain(std::iter::once(total))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Export ongoing activities to JSON, elapsed time is computed from `now`
+pub(crate) fn export_ongoing_activities_to_json(
+    activities: &[(ActivityId, OngoingActivity)],
+    now: DateTimeW,
+) -> anyhow::Result<String> {
+    let current: Vec<CurrentActivity> = activities
+        .iter()
+        .map(|(id, a)| {
+            let elapsed: Duration = a.elapsed(now).into();
+            CurrentActivity {
+                id: *id,
+                tags: a.tags.clone(),
+                start: a.get_start_time(),
+                elapsed_seconds: elapsed.num_seconds(),
+            }
+        })
+        .collect();
+    Ok(serde_json::to_string_pretty(&current)?)
+}
+
+/// Import finished activities exported with `ExportFormat::Json`
+///
+/// Fails if any activity is malformed, ids are ignored.
+pub(crate) fn import_activities_from_json<R: Read>(reader: R) -> anyhow::Result<Vec<Activity>> {
+    Ok(import_activities_with_ids_from_json(reader)?
+        .into_iter()
+        .map(|(_id, activity)| activity)
+        .collect())
+}
+
+/// Import finished activities exported with `ExportFormat::Json`, keeping their optional id
+///
+/// Fails if any activity is malformed.
+pub(crate) fn import_activities_with_ids_from_json<R: Read>(
+    reader: R,
+) -> anyhow::Result<Vec<(Option<ActivityId>, Activity)>> {
+    let exported: Vec<ExportedActivity> = serde_json::from_reader(reader)?;
+    exported
+        .into_iter()
+        .map(|e| {
+            let id = e.id;
+            e.into_activity()
+                .map(|activity| (id, activity))
+                .map_err(|err| match id {
+                    None => anyhow!("invalid activity: {}", err),
+                    Some(id) => anyhow!("invalid activity (id {}): {}", id, err),
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::export::{
+        csv_field, export_activities, export_ongoing_activities_to_json, export_summary_to_json,
+        export_summary_to_markdown, import_activities_from_json, ExportFormat, ExportedActivity,
+    };
+    use crate::rtw_config::RTWConfig;
+    use crate::rtw_core::activity::OngoingActivity;
+    use chrono::Duration;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("foo"), "foo");
+        assert_eq!(csv_field("foo,bar"), "\"foo,bar\"");
+        assert_eq!(csv_field("foo \"bar\""), "\"foo \"\"bar\"\"\"");
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let activity = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo"), String::from("bar,baz")],
+            None,
+        )
+        .into_activity(
+            Local
+                .datetime_from_str("2020-12-25T10:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+        let exported =
+            export_activities(&[(0, activity)], ExportFormat::Csv, &RTWConfig::default()).unwrap();
+        assert_eq!(
+            exported,
+            "id,start,end,duration,tags\n0,2020-12-25T09:00:00,2020-12-25T10:00:00,3600,\"foo;bar,baz\""
+        );
+    }
+
+    #[test]
+    fn test_export_import_json() {
+        let activity = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo"), String::from("bar")],
+            Some(String::from("description")),
+        )
+        .into_activity(
+            Local
+                .datetime_from_str("2020-12-25T10:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+        let exported = export_activities(
+            &[(0, activity.clone())],
+            ExportFormat::Json,
+            &RTWConfig::default(),
+        )
+        .unwrap();
+        let imported = import_activities_from_json(exported.as_bytes()).unwrap();
+        assert_eq!(imported, vec![activity]);
+    }
+
+    #[test]
+    fn test_export_ongoing_json() {
+        let ongoing = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo")],
+            None,
+        );
+        let now = Local
+            .datetime_from_str("2020-12-25T10:30:00", "%Y-%m-%dT%H:%M:%S")
+            .unwrap()
+            .into();
+        let exported = export_ongoing_activities_to_json(&[(0, ongoing)], now).unwrap();
+        let exported: serde_json::Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(exported[0]["id"], 0);
+        assert_eq!(exported[0]["tags"], serde_json::json!(["foo"]));
+        assert_eq!(exported[0]["elapsed_seconds"], 5400);
+    }
+
+    #[test]
+    fn test_export_summary_json() {
+        let activity = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo")],
+            Some(String::from("description")),
+        )
+        .into_activity(
+            Local
+                .datetime_from_str("2020-12-25T09:50:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+        let activities = vec![
+            ExportedActivity::new(1, &activity),
+            ExportedActivity::new(0, &activity)
+                .duration(Duration::hours(1))
+                .without_id()
+                .without_description(),
+        ];
+        let exported = export_summary_to_json(activities).unwrap();
+        let exported: serde_json::Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(exported["total_seconds"], 3000 + 3600);
+        assert_eq!(exported["activities"][0]["id"], 1);
+        assert_eq!(exported["activities"][0]["duration_seconds"], 3000);
+        assert_eq!(exported["activities"][0]["description"], "description");
+        assert!(exported["activities"][1].get("id").is_none());
+        assert!(exported["activities"][1].get("description").is_none());
+        assert_eq!(exported["activities"][1]["duration_seconds"], 3600);
+    }
+
+    #[test]
+    fn test_export_summary_markdown() {
+        let activity = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo|bar"), String::from("baz")],
+            None,
+        )
+        .into_activity(
+            Local
+                .datetime_from_str("2020-12-25T10:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+        )
+        .unwrap();
+        let activities = vec![ExportedActivity::new(0, &activity)
+            .without_id()
+            .without_description()];
+        let exported = export_summary_to_markdown(&activities, &RTWConfig::default());
+        let lines: Vec<&str> = exported.lines().collect();
+        assert_eq!(lines[0], "| Start | End | Duration | Tags |");
+        assert_eq!(lines[1], "|---|---|---|---|");
+        assert!(lines[2].ends_with("| 01:00:00 | foo\\|bar baz |"));
+        assert_eq!(lines[3], "| **Total** |  | **01:00:00** |  |");
+    }
+
+    #[test]
+    fn test_import_json_end_before_start() {
+        let json = r#"[{"id": 0, "start": "2020-12-25T10:00:00+01:00", "end": "2020-12-25T09:00:00+01:00", "tags": ["foo"]}]"#;
+        assert!(import_activities_from_json(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_import_json_invalid_tag() {
+        let json = r#"[{"id": 0, "start": "2020-12-25T09:00:00+01:00", "end": "2020-12-25T10:00:00+01:00", "tags": ["foo;bar"]}]"#;
+        assert!(import_activities_from_json(json.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_import_json_malformed() {
+        let json = r#"[{"id": 0, "start": "2020-12-25T10:00:00+01:00", "tags": ["foo"]}]"#;
+        assert!(import_activities_from_json(json.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,129 @@
+//! Duration targets per tag e.g. 20h per week on `foo`, stored as Json.
+use crate::json_storage::tmp_path;
+use crate::rtw_config::RTWConfig;
+use crate::rtw_core::durationw::DurationW;
+use crate::rtw_core::Tag;
+use chrono::Duration;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+pub const GOALS_FILE_NAME: &str = ".rtw-goals.json";
+
+/// Target duration per tag
+pub type Goals = BTreeMap<Tag, Duration>;
+
+/// Goal status: tag, tracked, target, delta (positive when over target)
+pub type GoalStatus = (Tag, Duration, Duration, Duration);
+
+/// Load goals, no goals if `path` does not exist
+pub fn load_goals(path: &Path) -> anyhow::Result<Goals> {
+    if !path.exists() {
+        return Ok(Goals::new());
+    }
+    let reader = BufReader::new(File::open(path)?);
+    // durations are stored as seconds
+    let seconds: BTreeMap<Tag, i64> = serde_json::from_reader(reader)
+        .map_err(|e| anyhow::anyhow!("invalid goals file {}: {}", path.display(), e))?;
+    Ok(seconds
+        .into_iter()
+        .map(|(tag, seconds)| (tag, Duration::seconds(seconds)))
+        .collect())
+}
+
+/// Save goals, replacing `path`
+pub fn save_goals(path: &Path, goals: &Goals) -> anyhow::Result<()> {
+    let seconds: BTreeMap<&Tag, i64> = goals
+        .iter()
+        .map(|(tag, target)| (tag, target.num_seconds()))
+        .collect();
+    let tmp_path = tmp_path(path);
+    {
+        let writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer_pretty(writer, &seconds)?;
+    }
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Compare tracked durations per tag to goals, sorted by tag
+///
+/// Tags without tracked activities count as zero.
+pub fn goal_statuses(goals: &Goals, tracked: &HashMap<Tag, DurationW>) -> Vec<GoalStatus> {
+    goals
+        .iter()
+        .map(|(tag, target)| {
+            let tracked: Duration = tracked
+                .get(tag)
+                .map(|d| (*d).into())
+                .unwrap_or_else(|| Duration::seconds(0));
+            (tag.clone(), tracked, *target, tracked - *target)
+        })
+        .collect()
+}
+
+/// Signed delta e.g. `+01:00:00` or `-08:00:00`
+pub fn format_delta(delta: Duration, config: &RTWConfig) -> String {
+    if delta < Duration::zero() {
+        format!("-{}", config.format_duration(-delta))
+    } else {
+        format!("+{}", config.format_duration(delta))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goals::{goal_statuses, load_goals, save_goals, Goals, GOALS_FILE_NAME};
+    use crate::rtw_core::durationw::DurationW;
+    use chrono::Duration;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_goal_statuses_delta() {
+        let mut goals = Goals::new();
+        goals.insert("foo".to_string(), Duration::hours(20));
+        goals.insert("bar".to_string(), Duration::hours(5));
+        goals.insert("baz".to_string(), Duration::hours(1));
+        let mut tracked = HashMap::new();
+        tracked.insert("foo".to_string(), DurationW::from(Duration::hours(12)));
+        tracked.insert("bar".to_string(), DurationW::from(Duration::hours(6)));
+        tracked.insert("other".to_string(), DurationW::from(Duration::hours(3)));
+        let statuses = goal_statuses(&goals, &tracked);
+        assert_eq!(
+            statuses,
+            vec![
+                (
+                    "bar".to_string(),
+                    Duration::hours(6),
+                    Duration::hours(5),
+                    Duration::hours(1)
+                ),
+                (
+                    "baz".to_string(),
+                    Duration::seconds(0),
+                    Duration::hours(1),
+                    Duration::hours(-1)
+                ),
+                (
+                    "foo".to_string(),
+                    Duration::hours(12),
+                    Duration::hours(20),
+                    Duration::hours(-8)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_save_load_goals() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let path = test_dir.path().join(GOALS_FILE_NAME);
+        assert!(load_goals(&path).unwrap().is_empty());
+        let mut goals = Goals::new();
+        goals.insert("foo".to_string(), Duration::minutes(90));
+        save_goals(&path, &goals).unwrap();
+        assert_eq!(load_goals(&path).unwrap(), goals);
+    }
+}
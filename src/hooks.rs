@@ -0,0 +1,40 @@
+//! User scripts run on events e.g. `on-start`, `on-stop`.
+//!
+//! A hook is an executable named after the event inside the hooks directory,
+//! it receives the activity as json on stdin.
+use anyhow::anyhow;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Run after an activity is started, receives the ongoing activity
+pub const ON_START: &str = "on-start";
+/// Run after an activity is stopped, receives the finished activity
+pub const ON_STOP: &str = "on-stop";
+
+/// Run hook `event` from `hooks_dir` if it exists
+///
+/// Fails if the hook could not be run or exited with a non-zero status.
+pub fn run_hook<T: Serialize>(hooks_dir: &Path, event: &str, activity: &T) -> anyhow::Result<()> {
+    let hook_path = hooks_dir.join(event);
+    if !hook_path.is_file() {
+        return Ok(());
+    }
+    let mut payload = serde_json::to_vec(activity)?;
+    payload.push(b'\n');
+    let mut child = Command::new(&hook_path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("could not run hook {}: {}", hook_path.display(), e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the hook may exit without reading stdin
+        let _ignored = stdin.write_all(&payload);
+    }
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!("hook {} failed ({})", hook_path.display(), status))
+    }
+}
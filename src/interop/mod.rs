@@ -0,0 +1,2 @@
+//! Interoperability with other time trackers.
+pub mod timewarrior;
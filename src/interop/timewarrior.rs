@@ -0,0 +1,111 @@
+//! Import Timewarrior export format (`timew export`).
+use crate::cli_helper::normalize_tags;
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::Tags;
+use anyhow::anyhow;
+use chrono::{Local, TimeZone, Utc};
+use serde::Deserialize;
+use std::io::Read;
+
+/// Timewarrior datetime format e.g. 20191225T184300Z (UTC)
+const TIMEWARRIOR_DATETIME_FMT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Timewarrior interval as exported by `timew export`
+#[derive(Debug, Clone, Deserialize)]
+struct Interval {
+    #[serde(default)]
+    id: Option<usize>,
+    start: String,
+    #[serde(default)]
+    end: Option<String>,
+    #[serde(default)]
+    tags: Tags,
+}
+
+fn parse_datetime(s: &str) -> anyhow::Result<DateTimeW> {
+    let utc = Utc
+        .datetime_from_str(s, TIMEWARRIOR_DATETIME_FMT)
+        .map_err(|e| anyhow!("invalid timewarrior date {}: {}", s, e))?;
+    Ok(utc.with_timezone(&Local).into())
+}
+
+/// Activities imported from Timewarrior
+///
+/// Timewarrior intervals without end are imported as ongoing activities.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Imported {
+    pub finished: Vec<Activity>,
+    pub ongoing: Vec<OngoingActivity>,
+}
+
+/// Import Timewarrior intervals
+///
+/// Fails if any interval is malformed.
+pub fn import_intervals<R: Read>(reader: R) -> anyhow::Result<Imported> {
+    let intervals: Vec<Interval> = serde_json::from_reader(reader)?;
+    let mut imported = Imported::default();
+    for interval in intervals {
+        let id = interval.id;
+        let start_time = parse_datetime(&interval.start)?;
+        let tags = normalize_tags(interval.tags)
+            .map_err(|e| anyhow!("invalid interval (id {:?}): {}", id, e))?;
+        let ongoing = OngoingActivity::new(start_time, tags, None);
+        match interval.end {
+            None => imported.ongoing.push(ongoing),
+            Some(end) => {
+                let stop_time = parse_datetime(&end)?;
+                let finished = ongoing
+                    .into_activity(stop_time)
+                    .map_err(|e| anyhow!("invalid interval (id {:?}): {}", id, e))?;
+                imported.finished.push(finished);
+            }
+        }
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interop::timewarrior::{import_intervals, parse_datetime};
+    use chrono::{DateTime, Local, TimeZone, Utc};
+
+    #[test]
+    fn test_parse_datetime() {
+        let parsed: DateTime<Local> = parse_datetime("20191225T184300Z").unwrap().into();
+        assert_eq!(parsed, Utc.ymd(2019, 12, 25).and_hms(18, 43, 0));
+        assert!(parse_datetime("2019-12-25T18:43:00").is_err());
+    }
+
+    #[test]
+    fn test_import_intervals() {
+        let json = r#"[
+            {"id":3,"start":"20191225T090000Z","end":"20191225T100000Z","tags":["foo"]},
+            {"id":2,"start":"20191225T100000Z","end":"20191225T103000Z","tags":["write doc","bar"]},
+            {"id":1,"start":"20191225T110000Z","tags":["baz"]}
+        ]"#;
+        let imported = import_intervals(json.as_bytes()).unwrap();
+        assert_eq!(imported.finished.len(), 2);
+        assert_eq!(
+            imported.finished[1].get_tags(),
+            vec![String::from("write doc"), String::from("bar")]
+        );
+        assert_eq!(imported.finished[1].get_duration().to_string(), "00:30:00");
+        assert_eq!(imported.ongoing.len(), 1);
+        assert_eq!(imported.ongoing[0].get_title(), "baz");
+    }
+
+    #[test]
+    fn test_import_intervals_without_tags() {
+        let json = r#"[{"id":1,"start":"20191225T090000Z","end":"20191225T100000Z"}]"#;
+        let imported = import_intervals(json.as_bytes()).unwrap();
+        assert!(imported.finished[0].get_tags().is_empty());
+    }
+
+    #[test]
+    fn test_import_intervals_end_before_start() {
+        let json =
+            r#"[{"id":1,"start":"20191225T100000Z","end":"20191225T090000Z","tags":["foo"]}]"#;
+        assert!(import_intervals(json.as_bytes()).is_err());
+    }
+}
@@ -1,28 +1,113 @@
 //! Store activities (current, finished) as Json files.
+//!
+//! Files whose path ends with `.gz` are gzip-compressed.
 use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::error::RtwError;
 use crate::rtw_core::storage::Storage;
 use crate::rtw_core::ActivityId;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use itertools::Itertools;
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
-use std::fs::{File, OpenOptions};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use thiserror::Error;
 
-type Activities = Vec<Activity>;
+/// Current activities file name (also used by the jsonl storage)
+pub const CURRENT_FILE_NAME: &str = ".rtw.json";
+/// Finished activities file name
+pub const FINISHED_FILE_NAME: &str = ".rtwh.json";
+/// Gzip-compressed finished activities file name, see `RTWConfig::compress`
+pub const COMPRESSED_FINISHED_FILE_NAME: &str = ".rtwh.json.gz";
+
+type Activities = Vec<StoredActivity>;
 type ActivityWithId = (ActivityId, Activity);
 type OngoingActivityWithId = (ActivityId, OngoingActivity);
 
+/// Finished activity with its id, as written to files
+///
+/// Activities written by older versions have no id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct StoredActivity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<ActivityId>,
+    #[serde(flatten)]
+    activity: Activity,
+}
+
+impl StoredActivity {
+    pub(crate) fn new(id: ActivityId, activity: Activity) -> Self {
+        StoredActivity {
+            id: Some(id),
+            activity,
+        }
+    }
+
+    pub(crate) fn into_parts(self) -> (Option<ActivityId>, Activity) {
+        (self.id, self.activity)
+    }
+}
+
+/// Activities sorted by start date with their id
+///
+/// Activities without id get the lowest ids, latest first (0 is the latest, as numbered
+/// by older versions), so that ids are the same at each read until they are written with their id.
+pub(crate) fn with_ids(stored: Vec<StoredActivity>) -> Vec<ActivityWithId> {
+    let (identified, legacy): (Activities, Activities) =
+        stored.into_iter().partition(|s| s.id.is_some());
+    let mut legacy: Vec<Activity> = legacy.into_iter().map(|s| s.activity).collect();
+    legacy.sort();
+    let mut activities: Vec<ActivityWithId> = legacy
+        .into_iter()
+        .rev()
+        .enumerate()
+        .chain(
+            identified
+                .into_iter()
+                .filter_map(|s| s.id.map(|id| (id, s.activity))),
+        )
+        .collect();
+    activities.sort_by(|(id, a), (other_id, other)| a.cmp(other).then(id.cmp(other_id)));
+    activities
+}
+
+/// Id of the next written activity
+///
+/// `counter` is the stored next id (none for files written by older versions),
+/// so that ids are never reused, even when the highest id was deleted.
+pub(crate) fn next_id(counter: Option<ActivityId>, activities: &[ActivityWithId]) -> ActivityId {
+    activities
+        .iter()
+        .map(|(id, _)| id + 1)
+        .chain(counter)
+        .max()
+        .unwrap_or_default()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct FinishedActivities {
     #[serde(default)]
     pub semver: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_id: Option<ActivityId>,
     pub activities: Activities,
 }
 
+// finished activities file read to count activities, without deserializing them
+#[derive(Deserialize)]
+struct CountedActivities {
+    activities: Vec<IgnoredAny>,
+}
+
 impl Default for FinishedActivities {
     fn default() -> Self {
         FinishedActivities {
             semver: Some(crate_version!().to_string()),
+            next_id: None,
             activities: vec![],
         }
     }
@@ -36,6 +121,64 @@ pub enum JsonStorageError {
     SerdeJsonError(#[from] serde_json::error::Error),
 }
 
+impl From<JsonStorageError> for RtwError {
+    fn from(e: JsonStorageError) -> Self {
+        match e {
+            JsonStorageError::IOError(e) => RtwError::Io(e),
+            JsonStorageError::SerdeJsonError(e) => RtwError::Parse(e.to_string()),
+        }
+    }
+}
+
+/// Temporary file written next to `path` before being renamed to `path`
+pub(crate) fn tmp_path(path: &Path) -> PathBuf {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    PathBuf::from(tmp_path)
+}
+
+/// File a corrupt (unparsable) `path` is moved to
+pub(crate) fn corrupt_path(path: &Path) -> PathBuf {
+    let mut corrupt_path = path.as_os_str().to_owned();
+    corrupt_path.push(".corrupt");
+    PathBuf::from(corrupt_path)
+}
+
+// compression is detected by extension only
+fn is_gzip(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new("gz"))
+}
+
+// decompressed if `path` ends with `.gz`
+fn open_reader(path: &Path) -> Result<Box<dyn Read>, std::io::Error> {
+    let reader = BufReader::new(File::open(path)?);
+    if is_gzip(path) {
+        Ok(Box::new(GzDecoder::new(reader)))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+// write to a temporary file then rename it (atomic on the same filesystem),
+// so that a crash leaves either the previous or the new file
+fn write_json_atomically<T: Serialize>(path: &Path, value: &T) -> Result<(), JsonStorageError> {
+    let tmp_path = tmp_path(path);
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        if is_gzip(path) {
+            let mut encoder = GzEncoder::new(&mut writer, Compression::default());
+            serde_json::to_writer(&mut encoder, value)?;
+            encoder.finish()?;
+        } else {
+            serde_json::to_writer(&mut writer, value)?;
+        }
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+    std::fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
 pub struct JsonStorage {
     current_path: PathBuf,
     finished_path: PathBuf,
@@ -56,21 +199,16 @@ impl JsonStorage {
 
     fn get_finished_activities(&self) -> Result<FinishedActivities, JsonStorageError> {
         if Path::exists(&self.finished_path) {
-            let file = OpenOptions::new()
-                .read(true)
-                .write(false)
-                .open(&self.finished_path)?;
+            let file = open_reader(&self.finished_path)?;
             let finished_activities: serde_json::error::Result<FinishedActivities> =
                 serde_json::from_reader(file);
             finished_activities.or_else(|_| {
-                let file = OpenOptions::new()
-                    .read(true)
-                    .write(false)
-                    .open(&self.finished_path)?;
+                let file = open_reader(&self.finished_path)?;
                 // try to parse legacy format.
                 let activities: Activities = serde_json::from_reader(file)?;
                 Ok(FinishedActivities {
                     semver: None,
+                    next_id: None,
                     activities,
                 })
             })
@@ -79,40 +217,80 @@ impl JsonStorage {
         }
     }
 
+    // `counter` is the next id read before the change, stored so that deleted ids are not reused
+    fn write_finished_activities(
+        &self,
+        activities: Vec<ActivityWithId>,
+        counter: ActivityId,
+    ) -> Result<(), JsonStorageError> {
+        let finished_activities = FinishedActivities {
+            semver: Some(crate_version!().to_string()),
+            next_id: Some(next_id(Some(counter), &activities)),
+            activities: activities
+                .into_iter()
+                .map(|(id, activity)| StoredActivity::new(id, activity))
+                .collect(),
+        };
+        write_json_atomically(&self.finished_path, &finished_activities)
+    }
+
+    fn write_ongoing_activities(
+        &self,
+        activities: Vec<OngoingActivity>,
+    ) -> Result<(), JsonStorageError> {
+        write_json_atomically(
+            &self.current_path,
+            &OngoingActivities {
+                ongoing: activities.into_iter().sorted().collect(),
+            },
+        )
+    }
+
     fn get_sorted_activities(&self) -> Result<Vec<(ActivityId, Activity)>, JsonStorageError> {
-        let mut finished_activities = self.get_finished_activities()?;
-        finished_activities.activities.sort();
-        Ok((0..finished_activities.activities.len())
-            .rev()
-            .zip(finished_activities.activities)
-            .collect())
+        Ok(self.get_activities_and_next_id()?.0)
+    }
+
+    fn get_activities_and_next_id(
+        &self,
+    ) -> Result<(Vec<ActivityWithId>, ActivityId), JsonStorageError> {
+        // activities written by an older version keep the ids computed here,
+        // they are written with them by the next change (reads never write)
+        let finished_activities = self.get_finished_activities()?;
+        let activities = with_ids(finished_activities.activities);
+        let next_id = next_id(finished_activities.next_id, &activities);
+        Ok((activities, next_id))
     }
 }
 
 impl Storage for JsonStorage {
     type StorageError = JsonStorageError;
 
-    fn write_activity(&mut self, activity: Activity) -> Result<(), Self::StorageError> {
-        if !Path::exists(&self.finished_path) {
-            let file = File::create(&self.finished_path)?;
-            let activities: Activities = vec![activity];
-            let finished_activities = FinishedActivities {
-                semver: Some(crate_version!().to_string()),
-                activities,
-            };
-            serde_json::to_writer(file, &finished_activities)?;
-            Ok(())
-        } else {
-            let mut finished_activities = self.get_finished_activities()?;
-            finished_activities.activities.push(activity);
-            let file = OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .open(&self.finished_path)?;
-            finished_activities.semver = Some(crate_version!().to_string());
-            serde_json::to_writer(file, &finished_activities)?;
-            Ok(())
-        }
+    fn write_activity(&mut self, activity: Activity) -> Result<ActivityId, Self::StorageError> {
+        let (mut finished_activities, id) = self.get_activities_and_next_id()?;
+        finished_activities.push((id, activity));
+        self.write_finished_activities(finished_activities, id)?;
+        Ok(id)
+    }
+
+    fn write_activities(
+        &mut self,
+        activities: Vec<Activity>,
+    ) -> Result<Vec<ActivityId>, Self::StorageError> {
+        let (mut finished_activities, first_id) = self.get_activities_and_next_id()?;
+        let ids: Vec<ActivityId> = (first_id..first_id + activities.len()).collect();
+        finished_activities.extend(ids.iter().copied().zip(activities));
+        self.write_finished_activities(finished_activities, first_id)?;
+        Ok(ids)
+    }
+
+    fn restore_activity(
+        &mut self,
+        id: ActivityId,
+        activity: Activity,
+    ) -> Result<(), Self::StorageError> {
+        let (mut finished_activities, next_id) = self.get_activities_and_next_id()?;
+        finished_activities.push((id, activity));
+        self.write_finished_activities(finished_activities, next_id)
     }
 
     fn filter_activities<P>(&self, p: P) -> Result<Vec<ActivityWithId>, Self::StorageError>
@@ -124,34 +302,99 @@ impl Storage for JsonStorage {
         Ok(filtered.collect())
     }
 
+    // the whole file is read anyway
+    fn for_each_activity<P, F>(&self, p: P, f: F) -> Result<(), Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity)),
+    {
+        self.filter_activities(p)?.into_iter().for_each(f);
+        Ok(())
+    }
+
     fn get_finished_activities(&self) -> Result<Vec<ActivityWithId>, Self::StorageError> {
         self.get_sorted_activities()
     }
 
-    fn delete_activity(&self, id: usize) -> Result<Option<Activity>, Self::StorageError> {
+    fn count_activities(&self) -> Result<usize, Self::StorageError> {
+        if !Path::exists(&self.finished_path) {
+            return Ok(0);
+        }
+        let file = open_reader(&self.finished_path)?;
+        let counted: serde_json::error::Result<CountedActivities> = serde_json::from_reader(file);
+        match counted {
+            Ok(counted) => Ok(counted.activities.len()),
+            Err(_) => {
+                let file = open_reader(&self.finished_path)?;
+                // try to parse legacy format.
+                let activities: Vec<IgnoredAny> = serde_json::from_reader(file)?;
+                Ok(activities.len())
+            }
+        }
+    }
+
+    fn get_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError> {
         let finished_activities = self.get_sorted_activities()?;
+        Ok(finished_activities
+            .into_iter()
+            .find(|(finished_id, _)| *finished_id == id)
+            .map(|(_, activity)| activity))
+    }
+
+    fn delete_activity(&self, id: usize) -> Result<Option<Activity>, Self::StorageError> {
+        let (finished_activities, next_id) = self.get_activities_and_next_id()?;
         let (removed, kept): (Vec<&ActivityWithId>, Vec<&ActivityWithId>) = finished_activities
             .iter()
             .partition(|(finished_id, _)| *finished_id == id);
-        let kept: Vec<&Activity> = kept.iter().map(|(_, a)| a).collect();
-        let file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&self.finished_path)?;
-        serde_json::to_writer(file, &kept)?;
+        let kept: Vec<ActivityWithId> = kept.into_iter().cloned().collect();
+        self.write_finished_activities(kept, next_id)?;
         Ok(match removed.as_slice() {
             [(_, removed)] => Some(removed.clone()),
             _ => None,
         })
     }
 
+    fn update_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>,
+    {
+        let (finished_activities, next_id) = self.get_activities_and_next_id()?;
+        let mut updated_ids: Vec<ActivityId> = vec![];
+        let activities: Vec<ActivityWithId> = finished_activities
+            .iter()
+            .map(|(id, activity)| match f(&(*id, activity.clone())) {
+                None => (*id, activity.clone()),
+                Some(updated) => {
+                    updated_ids.push(*id);
+                    (*id, updated)
+                }
+            })
+            .collect();
+        if !updated_ids.is_empty() {
+            self.write_finished_activities(activities, next_id)?;
+        }
+        Ok(updated_ids)
+    }
+
     fn get_ongoing_activities(&self) -> Result<Vec<OngoingActivityWithId>, Self::StorageError> {
         if !Path::exists(&self.current_path) {
             Ok(vec![])
         } else {
-            let file = File::open(&self.current_path)?;
-            let ongoing_activities: OngoingActivities = serde_json::from_reader(file)?;
+            let file = open_reader(&self.current_path)?;
+            let ongoing_activities: OngoingActivities = match serde_json::from_reader(file) {
+                Ok(ongoing_activities) => ongoing_activities,
+                // e.g. half-written file: move it aside so that commands keep working
+                Err(e) => {
+                    let corrupt_path = corrupt_path(&self.current_path);
+                    std::fs::rename(&self.current_path, &corrupt_path)?;
+                    eprintln!(
+                        "Warning: invalid ongoing activities file ({}), moved to {}",
+                        e,
+                        corrupt_path.display()
+                    );
+                    return Ok(vec![]);
+                }
+            };
             Ok(ongoing_activities
                 .ongoing
                 .iter()
@@ -179,24 +422,13 @@ impl Storage for JsonStorage {
         activity: OngoingActivity,
     ) -> Result<(), Self::StorageError> {
         let ongoing_activities = self.get_ongoing_activities()?;
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.current_path)?;
-        serde_json::to_writer(
-            file,
-            &OngoingActivities {
-                ongoing: ongoing_activities
-                    .iter()
-                    .map(|(_a_id, a)| a)
-                    .sorted()
-                    .cloned()
-                    .chain(std::iter::once(activity))
-                    .collect(),
-            },
-        )?;
-        Ok(())
+        self.write_ongoing_activities(
+            ongoing_activities
+                .into_iter()
+                .map(|(_a_id, a)| a)
+                .chain(std::iter::once(activity))
+                .collect(),
+        )
     }
 
     fn remove_ongoing_activity(
@@ -209,19 +441,293 @@ impl Storage for JsonStorage {
                 .iter()
                 .cloned()
                 .partition(|(a_id, _a)| *a_id == id);
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&self.current_path)?;
         let kept_without_id: Vec<OngoingActivity> =
-            kept.iter().cloned().sorted().map(|(_a_id, a)| a).collect();
-        serde_json::to_writer(
-            file,
-            &OngoingActivities {
-                ongoing: kept_without_id,
-            },
-        )?;
+            kept.iter().cloned().map(|(_a_id, a)| a).collect();
+        self.write_ongoing_activities(kept_without_id)?;
         Ok(removed.first().cloned().map(|(_a_id, a)| a))
     }
+
+    fn update_ongoing_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>,
+    {
+        let ongoing_activities = self.get_ongoing_activities()?;
+        let mut updated_ids: Vec<ActivityId> = vec![];
+        let activities: Vec<OngoingActivity> = ongoing_activities
+            .iter()
+            .map(|(id, activity)| match f(&(*id, activity.clone())) {
+                None => activity.clone(),
+                Some(updated) => {
+                    updated_ids.push(*id);
+                    updated
+                }
+            })
+            .collect();
+        if !updated_ids.is_empty() {
+            self.write_ongoing_activities(activities)?;
+        }
+        Ok(updated_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json_storage::{corrupt_path, tmp_path, JsonStorage};
+    use crate::rtw_core::activity::OngoingActivity;
+    use crate::rtw_core::storage::Storage;
+    use crate::rtw_core::test_utils::activity;
+    use chrono::{Local, TimeZone};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_partial_write_keeps_original() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let current_path = test_dir.path().join(".rtw.json");
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let mut storage = JsonStorage::new(current_path.clone(), finished_path.clone());
+        let ongoing = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into(),
+            vec![String::from("foo")],
+            None,
+        );
+        let finished = ongoing
+            .clone()
+            .into_activity(Local.ymd(2020, 12, 25).and_hms(10, 0, 0).into())
+            .unwrap();
+        storage.write_activity(finished.clone()).unwrap();
+        storage.add_ongoing_activity(ongoing.clone()).unwrap();
+        assert!(!tmp_path(&finished_path).exists());
+        assert!(!tmp_path(&current_path).exists());
+        // simulate a crash during a write: garbage written to the temporary files, no rename
+        std::fs::write(tmp_path(&finished_path), "{\"activities\": [{\"start").unwrap();
+        std::fs::write(tmp_path(&current_path), "{\"ongoing\": [").unwrap();
+        let storage = JsonStorage::new(current_path, finished_path);
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            vec![(0, finished)]
+        );
+        assert_eq!(
+            storage.get_ongoing_activities().unwrap(),
+            vec![(0, ongoing)]
+        );
+    }
+
+    #[test]
+    fn test_write_after_partial_write() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let mut storage =
+            JsonStorage::new(test_dir.path().join(".rtw.json"), finished_path.clone());
+        // leftover temporary file from a previous crash is overwritten
+        std::fs::write(tmp_path(&finished_path), "garbage").unwrap();
+        let finished = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into(),
+            vec![String::from("foo")],
+            None,
+        )
+        .into_activity(Local.ymd(2020, 12, 25).and_hms(10, 0, 0).into())
+        .unwrap();
+        storage.write_activity(finished.clone()).unwrap();
+        assert!(!tmp_path(&finished_path).exists());
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            vec![(0, finished)]
+        );
+    }
+
+    #[test]
+    fn test_legacy_activities_get_ids() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let activity = |hour: u32, tag: &str| {
+            OngoingActivity::new(
+                Local.ymd(2020, 12, 25).and_hms(hour, 0, 0).into(),
+                vec![String::from(tag)],
+                None,
+            )
+            .into_activity(Local.ymd(2020, 12, 25).and_hms(hour + 1, 0, 0).into())
+            .unwrap()
+        };
+        let early = activity(9, "early");
+        let late = activity(11, "late");
+        // written by an older version: no ids, not sorted
+        let legacy = serde_json::json!({ "activities": [late, early] });
+        std::fs::write(&finished_path, legacy.to_string()).unwrap();
+        let mut storage = JsonStorage::new(test_dir.path().join(".rtw.json"), finished_path);
+        // ids displayed by older versions: 0 is the latest
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            vec![(1, early.clone()), (0, late.clone())]
+        );
+        let middle = activity(10, "middle");
+        assert_eq!(storage.write_activity(middle.clone()).unwrap(), 2);
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            vec![(1, early), (2, middle), (0, late)]
+        );
+    }
+
+    #[test]
+    fn test_legacy_ids_written_on_first_write() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let early = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["early"]);
+        let late = activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["late"]);
+        // legacy format: a bare array, without ids
+        std::fs::write(
+            &finished_path,
+            serde_json::json!([late.clone(), early.clone()]).to_string(),
+        )
+        .unwrap();
+        let legacy_content = std::fs::read_to_string(&finished_path).unwrap();
+        let mut storage =
+            JsonStorage::new(test_dir.path().join(".rtw.json"), finished_path.clone());
+        let expected = vec![(1, early), (0, late)];
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            expected
+        );
+        // reading does not write (read-only commands run without the lock)
+        assert_eq!(
+            std::fs::read_to_string(&finished_path).unwrap(),
+            legacy_content
+        );
+        let other = activity("2020-12-25T13:00:00", "2020-12-25T14:00:00", &["other"]);
+        assert_eq!(storage.write_activity(other.clone()).unwrap(), 2);
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&finished_path).unwrap()).unwrap();
+        let written_ids: Vec<u64> = written["activities"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|stored| stored["id"].as_u64().unwrap())
+            .collect();
+        assert_eq!(written_ids, vec![1, 0, 2]);
+        assert_eq!(written["next_id"], 3);
+        // same ids at each load
+        let storage = JsonStorage::new(test_dir.path().join(".rtw.json"), finished_path);
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            [expected, vec![(2, other)]].concat()
+        );
+    }
+
+    #[test]
+    fn test_deleted_highest_id_not_reused() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = JsonStorage::new(
+            test_dir.path().join(".rtw.json"),
+            test_dir.path().join(".rtwh.json"),
+        );
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        assert_eq!(storage.write_activity(foo).unwrap(), 0);
+        assert_eq!(storage.write_activity(bar.clone()).unwrap(), 1);
+        assert_eq!(storage.delete_activity(1).unwrap(), Some(bar.clone()));
+        assert_eq!(storage.write_activity(bar).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_corrupt_ongoing_file_moved_aside() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let current_path = test_dir.path().join(".rtw.json");
+        let mut storage =
+            JsonStorage::new(current_path.clone(), test_dir.path().join(".rtwh.json"));
+        std::fs::write(&current_path, "{\"ongoing\": [").unwrap();
+        assert!(storage.get_ongoing_activities().unwrap().is_empty());
+        assert!(!current_path.exists());
+        assert_eq!(
+            std::fs::read_to_string(corrupt_path(&current_path)).unwrap(),
+            "{\"ongoing\": ["
+        );
+        // still usable
+        let ongoing = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into(),
+            vec![String::from("foo")],
+            None,
+        );
+        storage.add_ongoing_activity(ongoing.clone()).unwrap();
+        assert_eq!(
+            storage.get_ongoing_activities().unwrap(),
+            vec![(0, ongoing)]
+        );
+    }
+
+    #[test]
+    fn test_gzip_round_trip() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let current_path = test_dir.path().join(".rtw.json.gz");
+        let finished_path = test_dir.path().join(".rtwh.json.gz");
+        let mut storage = JsonStorage::new(current_path.clone(), finished_path.clone());
+        let ongoing = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into(),
+            vec![String::from("foo")],
+            None,
+        );
+        let finished = ongoing
+            .clone()
+            .into_activity(Local.ymd(2020, 12, 25).and_hms(10, 0, 0).into())
+            .unwrap();
+        storage.write_activity(finished.clone()).unwrap();
+        storage.add_ongoing_activity(ongoing.clone()).unwrap();
+        // not plain json on disk
+        assert!(serde_json::from_slice::<serde_json::Value>(
+            &std::fs::read(&finished_path).unwrap()
+        )
+        .is_err());
+        let storage = JsonStorage::new(current_path, finished_path);
+        assert_eq!(
+            Storage::get_finished_activities(&storage).unwrap(),
+            vec![(0, finished)]
+        );
+        assert_eq!(
+            storage.get_ongoing_activities().unwrap(),
+            vec![(0, ongoing)]
+        );
+    }
+
+    #[test]
+    fn test_count_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let mut storage =
+            JsonStorage::new(test_dir.path().join(".rtw.json"), finished_path.clone());
+        assert_eq!(storage.count_activities().unwrap(), 0);
+        for hour in 9..12 {
+            let start = format!("2020-12-25T{:02}:00:00", hour);
+            let stop = format!("2020-12-25T{:02}:30:00", hour);
+            storage
+                .write_activity(activity(&start, &stop, &["foo"]))
+                .unwrap();
+        }
+        assert_eq!(storage.count_activities().unwrap(), 3);
+        storage.delete_activity(1).unwrap();
+        assert_eq!(storage.count_activities().unwrap(), 2);
+        // legacy format: a bare array
+        let legacy = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        std::fs::write(&finished_path, serde_json::json!([legacy]).to_string()).unwrap();
+        assert_eq!(storage.count_activities().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_last_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = JsonStorage::new(
+            test_dir.path().join(".rtw.json"),
+            test_dir.path().join(".rtwh.json"),
+        );
+        assert_eq!(storage.get_last_activities(1).unwrap(), vec![]);
+        let long = activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &["long"]);
+        let foo = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["foo"]);
+        storage.write_activity(long.clone()).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        assert_eq!(
+            storage.get_last_activities(1).unwrap(),
+            vec![(0, long.clone())]
+        );
+        assert_eq!(
+            storage.get_last_activities(3).unwrap(),
+            vec![(0, long), (1, foo)]
+        );
+    }
 }
@@ -0,0 +1,477 @@
+//! Store finished activities as Json Lines (one activity per line), current activities as Json.
+//!
+//! Writing a finished activity appends a single line (the next id is stored next to the file,
+//! in `<file>.next-id`), deleting or updating activities rewrites the whole file
+//! (temporary file then rename).
+use crate::json_storage::{
+    next_id, tmp_path, with_ids, JsonStorage, JsonStorageError, StoredActivity,
+};
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::error::RtwError;
+use crate::rtw_core::storage::Storage;
+use crate::rtw_core::ActivityId;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Finished activities file name
+pub const FINISHED_FILE_NAME: &str = ".rtwh.jsonl";
+
+type ActivityWithId = (ActivityId, Activity);
+type OngoingActivityWithId = (ActivityId, OngoingActivity);
+
+#[derive(Error, Debug)]
+pub enum JsonlStorageError {
+    #[error("storage io error")]
+    IOError(#[from] std::io::Error),
+    #[error("(de)serialization failed")]
+    SerdeJsonError(#[from] serde_json::error::Error),
+    #[error("invalid activity at line {0}")]
+    InvalidLine(usize, #[source] serde_json::error::Error),
+    #[error("activity without id at line {0}")]
+    MissingId(usize),
+    #[error("ongoing activities storage error")]
+    OngoingStorageError(#[from] JsonStorageError),
+}
+
+impl From<JsonlStorageError> for RtwError {
+    fn from(e: JsonlStorageError) -> Self {
+        match e {
+            JsonlStorageError::IOError(e) => RtwError::Io(e),
+            JsonlStorageError::SerdeJsonError(e) => RtwError::Parse(e.to_string()),
+            JsonlStorageError::InvalidLine(line, e) => {
+                RtwError::Parse(format!("invalid activity at line {}: {}", line, e))
+            }
+            JsonlStorageError::MissingId(line) => {
+                RtwError::Parse(format!("activity without id at line {}", line))
+            }
+            JsonlStorageError::OngoingStorageError(e) => e.into(),
+        }
+    }
+}
+
+pub struct JsonlStorage {
+    // ongoing activities are stored as Json, finished activities path is unused
+    ongoing: JsonStorage,
+    finished_path: PathBuf,
+}
+
+impl JsonlStorage {
+    pub fn new(current_path: PathBuf, finished_path: PathBuf) -> Self {
+        JsonlStorage {
+            ongoing: JsonStorage::new(current_path, finished_path.clone()),
+            finished_path,
+        }
+    }
+
+    // read activities line by line, in file order
+    fn read_activities(&self) -> Result<Vec<StoredActivity>, JsonlStorageError> {
+        if !Path::exists(&self.finished_path) {
+            return Ok(vec![]);
+        }
+        let reader = BufReader::new(File::open(&self.finished_path)?);
+        let mut activities = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let activity: StoredActivity = serde_json::from_str(&line)
+                .map_err(|e| JsonlStorageError::InvalidLine(index + 1, e))?;
+            activities.push(activity);
+        }
+        Ok(activities)
+    }
+
+    fn get_sorted_activities(&self) -> Result<Vec<ActivityWithId>, JsonlStorageError> {
+        Ok(with_ids(self.read_activities()?))
+    }
+
+    fn counter_path(&self) -> PathBuf {
+        let mut counter_path = self.finished_path.as_os_str().to_owned();
+        counter_path.push(".next-id");
+        PathBuf::from(counter_path)
+    }
+
+    // none if missing (files written by older versions) or unreadable
+    fn read_counter(&self) -> Option<ActivityId> {
+        std::fs::read_to_string(self.counter_path())
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+    }
+
+    // without a stored counter, the next id is computed from all activities once
+    fn next_id(&self) -> Result<ActivityId, JsonlStorageError> {
+        match self.read_counter() {
+            Some(counter) => Ok(counter),
+            None => Ok(next_id(None, &self.get_sorted_activities()?)),
+        }
+    }
+
+    // reserve `count` ids before appending activities:
+    // a crash in between leaves unused ids, never duplicate ones
+    fn reserve_ids(&self, count: usize) -> Result<ActivityId, JsonlStorageError> {
+        let first_id = self.next_id()?;
+        std::fs::write(self.counter_path(), (first_id + count).to_string())?;
+        Ok(first_id)
+    }
+
+    fn append_activity(&self, id: ActivityId, activity: Activity) -> Result<(), JsonlStorageError> {
+        self.append_activities(vec![(id, activity)])
+    }
+
+    // append all lines with a single write
+    fn append_activities(&self, activities: Vec<ActivityWithId>) -> Result<(), JsonlStorageError> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.finished_path)?;
+        let mut lines = vec![];
+        for (id, activity) in activities {
+            serde_json::to_writer(&mut lines, &StoredActivity::new(id, activity))?;
+            lines.push(b'\n');
+        }
+        file.write_all(&lines)?;
+        Ok(())
+    }
+
+    // write all activities to a temporary file then rename it, so that a crash
+    // leaves either the previous or the new file
+    fn rewrite_activities(&self, activities: &[ActivityWithId]) -> Result<(), JsonlStorageError> {
+        let tmp_path = tmp_path(&self.finished_path);
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            for (id, activity) in activities {
+                serde_json::to_writer(&mut writer, &StoredActivity::new(*id, activity.clone()))?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+        std::fs::rename(&tmp_path, &self.finished_path)?;
+        Ok(())
+    }
+}
+
+impl Storage for JsonlStorage {
+    type StorageError = JsonlStorageError;
+
+    fn write_activity(&mut self, activity: Activity) -> Result<ActivityId, Self::StorageError> {
+        let id = self.reserve_ids(1)?;
+        self.append_activity(id, activity)?;
+        Ok(id)
+    }
+
+    fn write_activities(
+        &mut self,
+        activities: Vec<Activity>,
+    ) -> Result<Vec<ActivityId>, Self::StorageError> {
+        let first_id = self.reserve_ids(activities.len())?;
+        let ids: Vec<ActivityId> = (first_id..first_id + activities.len()).collect();
+        self.append_activities(ids.iter().copied().zip(activities).collect())?;
+        Ok(ids)
+    }
+
+    fn restore_activity(
+        &mut self,
+        id: ActivityId,
+        activity: Activity,
+    ) -> Result<(), Self::StorageError> {
+        self.append_activity(id, activity)
+    }
+
+    fn filter_activities<P>(&self, p: P) -> Result<Vec<ActivityWithId>, Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        let indexed_finished_activities = self.get_sorted_activities()?;
+        Ok(indexed_finished_activities.into_iter().filter(p).collect())
+    }
+
+    // stream activities line by line, in file order
+    fn for_each_activity<P, F>(&self, p: P, mut f: F) -> Result<(), Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity)),
+    {
+        if !Path::exists(&self.finished_path) {
+            return Ok(());
+        }
+        let reader = BufReader::new(File::open(&self.finished_path)?);
+        let mut streamed = false;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let activity: StoredActivity = serde_json::from_str(&line)
+                .map_err(|e| JsonlStorageError::InvalidLine(index + 1, e))?;
+            match activity.into_parts() {
+                (Some(id), activity) => {
+                    streamed = true;
+                    let activity = (id, activity);
+                    if p(&activity) {
+                        f(activity);
+                    }
+                }
+                // activities written by older versions come first, their ids depend on
+                // the start dates of all of them: fall back to reading the whole file
+                (None, _) if !streamed => {
+                    self.filter_activities(p)?.into_iter().for_each(f);
+                    return Ok(());
+                }
+                (None, _) => return Err(JsonlStorageError::MissingId(index + 1)),
+            }
+        }
+        Ok(())
+    }
+
+    fn get_finished_activities(&self) -> Result<Vec<ActivityWithId>, Self::StorageError> {
+        self.get_sorted_activities()
+    }
+
+    // one activity per non-empty line, lines are not parsed
+    fn count_activities(&self) -> Result<usize, Self::StorageError> {
+        if !Path::exists(&self.finished_path) {
+            return Ok(0);
+        }
+        let reader = BufReader::new(File::open(&self.finished_path)?);
+        let mut count = 0;
+        for line in reader.lines() {
+            if !line?.trim().is_empty() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    fn get_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError> {
+        let finished_activities = self.get_sorted_activities()?;
+        Ok(finished_activities
+            .into_iter()
+            .find(|(finished_id, _)| *finished_id == id)
+            .map(|(_, activity)| activity))
+    }
+
+    fn delete_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError> {
+        let finished_activities = self.get_sorted_activities()?;
+        let (removed, kept): (Vec<&ActivityWithId>, Vec<&ActivityWithId>) = finished_activities
+            .iter()
+            .partition(|(finished_id, _)| *finished_id == id);
+        match removed.as_slice() {
+            [(_, removed)] => {
+                if self.read_counter().is_none() {
+                    // the deleted id may be the highest one
+                    let counter = next_id(None, &finished_activities);
+                    std::fs::write(self.counter_path(), counter.to_string())?;
+                }
+                let kept: Vec<ActivityWithId> = kept.into_iter().cloned().collect();
+                self.rewrite_activities(kept.as_slice())?;
+                Ok(Some(removed.clone()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn update_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>,
+    {
+        let finished_activities = self.get_sorted_activities()?;
+        let mut updated_ids: Vec<ActivityId> = vec![];
+        let activities: Vec<ActivityWithId> = finished_activities
+            .iter()
+            .map(|(id, activity)| match f(&(*id, activity.clone())) {
+                None => (*id, activity.clone()),
+                Some(updated) => {
+                    updated_ids.push(*id);
+                    (*id, updated)
+                }
+            })
+            .collect();
+        if !updated_ids.is_empty() {
+            self.rewrite_activities(activities.as_slice())?;
+        }
+        Ok(updated_ids)
+    }
+
+    fn get_ongoing_activities(&self) -> Result<Vec<OngoingActivityWithId>, Self::StorageError> {
+        Ok(self.ongoing.get_ongoing_activities()?)
+    }
+
+    fn get_ongoing_activity(
+        &self,
+        id: ActivityId,
+    ) -> Result<Option<OngoingActivity>, Self::StorageError> {
+        Ok(self.ongoing.get_ongoing_activity(id)?)
+    }
+
+    fn add_ongoing_activity(
+        &mut self,
+        activity: OngoingActivity,
+    ) -> Result<(), Self::StorageError> {
+        Ok(self.ongoing.add_ongoing_activity(activity)?)
+    }
+
+    fn remove_ongoing_activity(
+        &mut self,
+        id: ActivityId,
+    ) -> Result<Option<OngoingActivity>, Self::StorageError> {
+        Ok(self.ongoing.remove_ongoing_activity(id)?)
+    }
+
+    fn update_ongoing_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>,
+    {
+        Ok(self.ongoing.update_ongoing_activities(f)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jsonl_storage::JsonlStorage;
+    use crate::rtw_core::storage::Storage;
+    use crate::rtw_core::test_utils::activity;
+    use tempfile::{tempdir, TempDir};
+
+    fn build_storage(test_dir: &TempDir) -> JsonlStorage {
+        JsonlStorage::new(
+            test_dir.path().join(".rtw.json"),
+            test_dir.path().join(".rtwh.jsonl"),
+        )
+    }
+
+    #[test]
+    fn test_write_appends_lines() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        // written out of order
+        storage.write_activity(bar.clone()).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        let content = std::fs::read_to_string(test_dir.path().join(".rtwh.jsonl")).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        // sorted by start date, ids in write order
+        assert_eq!(
+            storage.get_finished_activities().unwrap(),
+            vec![(1, foo), (0, bar)]
+        );
+    }
+
+    #[test]
+    fn test_delete_and_update() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        storage.write_activity(foo.clone()).unwrap();
+        storage.write_activity(bar.clone()).unwrap();
+        assert_eq!(storage.delete_activity(2).unwrap(), None);
+        assert_eq!(storage.delete_activity(1).unwrap(), Some(bar));
+        let renamed = foo.rename_tag("foo", "baz").unwrap();
+        let updated = storage
+            .update_activities(|(_id, a)| a.rename_tag("foo", "baz"))
+            .unwrap();
+        assert_eq!(updated, vec![0]);
+        assert_eq!(
+            storage.get_finished_activities().unwrap(),
+            vec![(0, renamed)]
+        );
+        assert!(!test_dir.path().join(".rtwh.jsonl.tmp").exists());
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        storage
+            .write_activity(activity(
+                "2020-12-25T09:00:00",
+                "2020-12-25T10:00:00",
+                &["foo"],
+            ))
+            .unwrap();
+        let mut content = std::fs::read_to_string(test_dir.path().join(".rtwh.jsonl")).unwrap();
+        content.push_str("{\"truncated\n");
+        std::fs::write(test_dir.path().join(".rtwh.jsonl"), content).unwrap();
+        let error = storage.get_finished_activities().unwrap_err();
+        assert_eq!(error.to_string(), "invalid activity at line 2");
+    }
+
+    #[test]
+    fn test_for_each_activity_streams_in_file_order() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        let baz = activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["baz"]);
+        storage.write_activity(bar.clone()).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        storage.write_activity(baz).unwrap();
+        let mut streamed = vec![];
+        storage
+            .for_each_activity(|(id, _a)| *id < 2, |a| streamed.push(a))
+            .unwrap();
+        assert_eq!(streamed, vec![(0, bar), (1, foo)]);
+    }
+
+    #[test]
+    fn test_for_each_activity_legacy() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let storage = build_storage(&test_dir);
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        // written by an older version, without id
+        let content = format!(
+            "{}\n{}\n",
+            serde_json::to_string(&bar).unwrap(),
+            serde_json::to_string(&foo).unwrap()
+        );
+        std::fs::write(test_dir.path().join(".rtwh.jsonl"), content).unwrap();
+        let mut streamed = vec![];
+        storage
+            .for_each_activity(|_a| true, |a| streamed.push(a))
+            .unwrap();
+        assert_eq!(streamed, storage.get_finished_activities().unwrap());
+        // ids displayed by older versions: 0 is the latest
+        assert_eq!(streamed, vec![(1, foo), (0, bar)]);
+    }
+
+    #[test]
+    fn test_deleted_highest_id_not_reused() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        assert_eq!(storage.write_activity(foo).unwrap(), 0);
+        assert_eq!(storage.write_activity(bar.clone()).unwrap(), 1);
+        assert_eq!(storage.delete_activity(1).unwrap(), Some(bar.clone()));
+        assert_eq!(storage.write_activity(bar).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(test_dir.path().join(".rtwh.jsonl.next-id")).unwrap(),
+            "3"
+        );
+    }
+
+    #[test]
+    fn test_count_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = build_storage(&test_dir);
+        assert_eq!(storage.count_activities().unwrap(), 0);
+        for hour in 9..12 {
+            let start = format!("2020-12-25T{:02}:00:00", hour);
+            let stop = format!("2020-12-25T{:02}:30:00", hour);
+            storage
+                .write_activity(activity(&start, &stop, &["foo"]))
+                .unwrap();
+        }
+        assert_eq!(storage.count_activities().unwrap(), 3);
+        storage.delete_activity(1).unwrap();
+        assert_eq!(storage.count_activities().unwrap(), 2);
+    }
+}
@@ -14,3 +14,28 @@
 //! * An `ActivityService` provides the logic above a storage.
 //! * `rtw_cli::run` translates CLI args to actions (`RTWAction`).
 //! * `rtw_cli::run_action` performs actions `RTWAction` by calling the service.
+//!
+//! ## Embedding
+//!
+//! The storages and the service are also available as a library, see `Service::builder`.
+
+#[macro_use]
+extern crate clap;
+
+pub mod builder;
+pub mod chrono_clock;
+pub mod json_storage;
+pub mod jsonl_storage;
+pub mod range;
+pub mod rtw_core;
+pub mod service;
+pub mod sqlite_storage;
+
+pub use crate::builder::{OpenStorage, ServiceBuilder};
+pub use crate::json_storage::JsonStorage;
+pub use crate::jsonl_storage::JsonlStorage;
+pub use crate::rtw_core::clock::Clock;
+pub use crate::rtw_core::service::ActivityService;
+pub use crate::rtw_core::storage::Storage;
+pub use crate::service::Service;
+pub use crate::sqlite_storage::SqliteStorage;
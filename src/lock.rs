@@ -0,0 +1,93 @@
+//! Advisory lock serializing mutating commands.
+//!
+//! Concurrent `rtw` processes would otherwise read and write storage files at the same time,
+//! losing activities. The lock is released when dropped (or when the process dies).
+use anyhow::anyhow;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Lock file name inside the storage directory
+pub const LOCK_FILE_NAME: &str = ".rtw.lock";
+/// How long to wait for another rtw process
+pub const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Exclusive lock on the storage directory, held until dropped
+pub struct StorageLock {
+    _file: File,
+}
+
+impl StorageLock {
+    /// Lock `storage_dir`, waiting at most `timeout` for another process to release it
+    ///
+    /// Fails with "another rtw process is running" on timeout.
+    pub fn acquire(storage_dir: &Path, timeout: Duration) -> anyhow::Result<StorageLock> {
+        std::fs::create_dir_all(storage_dir)?;
+        let lock_path = storage_dir.join(LOCK_FILE_NAME);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(&lock_path)
+            .map_err(|e| anyhow!("could not open lock {}: {}", lock_path.display(), e))?;
+        let deadline = Instant::now() + timeout;
+        while !try_lock(&file)? {
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "another rtw process is running (lock {} held for more than {}s)",
+                    lock_path.display(),
+                    timeout.as_secs()
+                ));
+            }
+            thread::sleep(RETRY_DELAY);
+        }
+        Ok(StorageLock { _file: file })
+    }
+}
+
+// true if locked, false if held by another process
+#[cfg(unix)]
+fn try_lock(file: &File) -> std::io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+    let result = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if result == 0 {
+        Ok(true)
+    } else {
+        let error = std::io::Error::last_os_error();
+        if error.kind() == std::io::ErrorKind::WouldBlock {
+            Ok(false)
+        } else {
+            Err(error)
+        }
+    }
+}
+
+// advisory locking is only implemented on unix
+#[cfg(not(unix))]
+fn try_lock(_file: &File) -> std::io::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lock::StorageLock;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_lock_contention_times_out() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let lock = StorageLock::acquire(test_dir.path(), Duration::from_millis(50)).unwrap();
+        let second = StorageLock::acquire(test_dir.path(), Duration::from_millis(50));
+        assert!(second
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("another rtw process is running"));
+        drop(lock);
+        assert!(StorageLock::acquire(test_dir.path(), Duration::from_millis(50)).is_ok());
+    }
+}
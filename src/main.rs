@@ -2,34 +2,139 @@
 extern crate clap;
 
 use crate::chrono_clock::ChronoClock;
-use crate::cli_helper::get_app;
+use crate::cli_helper::{get_app, unsigned_stop_duration};
 use crate::json_storage::JsonStorage;
+use crate::jsonl_storage::JsonlStorage;
+use crate::lock::{StorageLock, LOCK_TIMEOUT};
 use crate::rtw_cli::{dry_run_action, run, run_mutation};
-use crate::rtw_config::{load_config, RTWConfig};
+use crate::rtw_config::{load_config, load_config_from_file, RTWConfig, StorageBackend, Verbosity};
+use crate::rtw_core::clock::Clock;
+use crate::rtw_core::datetimew;
+use crate::rtw_core::error::RtwError;
+use crate::rtw_core::storage::Storage;
 use crate::service::Service;
-use std::path::PathBuf;
+use crate::sqlite_storage::SqliteStorage;
+use crate::time_tools::TimeTools;
+use clap::ArgMatches;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 mod chrono_clock;
 mod cli_helper;
+mod color;
+mod doctor;
+mod editor;
+mod export;
+mod goals;
+mod hooks;
 mod ical_export;
+mod interop;
 mod json_storage;
+mod jsonl_storage;
+mod lock;
+mod range;
+mod report;
 mod rtw_cli;
 mod rtw_config;
 mod rtw_core;
 mod service;
+mod sqlite_storage;
+mod tags_cache;
+mod text;
 mod time_tools;
 mod timeline;
+mod undo;
 
-fn main() -> anyhow::Result<()> {
-    let clock = ChronoClock {};
+fn run_service<S: Storage>(
+    matches: &ArgMatches,
+    mut service: Service<S>,
+    clock: &ChronoClock,
+    config: &RTWConfig,
+) -> anyhow::Result<()> {
+    let action = run(matches, clock, config)?;
+    // held from reading to writing storage so that concurrent commands do not lose activities
+    let _lock = if action.is_read_only() {
+        None
+    } else {
+        Some(StorageLock::acquire(
+            &config.storage_dir_path,
+            LOCK_TIMEOUT,
+        )?)
+    };
+    let mutation = dry_run_action(action, &service, clock, config)?;
+    if matches.is_present("dry-run") {
+        if !config.is_quiet() {
+            println!("(dry-run) nothing done");
+        }
+        Ok(())
+    } else {
+        run_mutation(mutation, &mut service, config)
+    }
+}
+
+/// Environment variable overriding the configured storage directory
+const RTW_DIR_ENV: &str = "RTW_DIR";
+
+fn log_storage_files(config: &RTWConfig, paths: &[&Path]) {
+    if config.is_verbose() {
+        for path in paths {
+            eprintln!("Storage file {}", path.display());
+        }
+    }
+}
+
+/// Exit code of a failed command: 2 when an activity is not found,
+/// 3 on a state conflict e.g. a refused overlap, 1 otherwise (clap also exits 1 on usage errors)
+fn exit_code(error: &anyhow::Error) -> i32 {
+    match error.downcast_ref::<RtwError>() {
+        Some(RtwError::NotFound(_)) => 2,
+        Some(RtwError::Overlap(_, _)) | Some(RtwError::MultipleOngoing) => 3,
+        _ => 1,
+    }
+}
+
+fn main() {
+    if let Err(e) = run_main() {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(exit_code(&e));
+    }
+}
+
+fn run_main() -> anyhow::Result<()> {
     let app = get_app();
-    let matches = app.get_matches();
-    let config = load_config()?;
+    let matches = app.get_matches_from(unsigned_stop_duration(std::env::args_os()));
+    // --now freezes the clock, relative times resolve against it
+    let clock = match matches.value_of("now") {
+        None => ChronoClock::default(),
+        Some(now) => {
+            let real_clock = ChronoClock::default();
+            let now = real_clock.date_time(TimeTools::time_from_str(now, &real_clock)?);
+            ChronoClock::fixed(now)
+        }
+    };
     let config = if matches.is_present("default") {
         RTWConfig::default()
     } else {
-        config
+        match matches.value_of("config") {
+            None => load_config()?,
+            Some(config_path) => {
+                load_config_from_file(Path::new(config_path), RTWConfig::default())?
+            }
+        }
+    };
+    if let Some(date_format) = &config.date_format {
+        datetimew::check_display_format(date_format)?;
+    }
+    let config = config
+        .painter(color::Painter::new(color::should_enable(
+            matches.is_present("no-color"),
+        )))
+        .display_utc(matches.is_present("utc"))
+        .duration_format(cli_helper::parse_duration_format_args(&matches)?)
+        .duration_precision(cli_helper::parse_precision_args(&matches)?);
+    let config = match cli_helper::parse_week_start_args(&matches)? {
+        None => config,
+        Some(week_start) => config.week_start(week_start),
     };
     let config = if matches.is_present("overlap") {
         config.deny_overlapping(false)
@@ -41,23 +146,64 @@ fn main() -> anyhow::Result<()> {
     } else {
         config
     };
-    let storage_dir = match matches.value_of("directory") {
-        None => config.storage_dir_path.clone(),
-        Some(dir_str) => PathBuf::from_str(dir_str).expect("invalid directory"),
-    };
-    let current_activity_path = storage_dir.join(".rtw.json");
-    let finished_activity_path = storage_dir.join(".rtwh.json");
-    let mut service = Service::new(JsonStorage::new(
-        current_activity_path,
-        finished_activity_path,
-    ));
-
-    let action = run(&matches, &clock)?;
-    let mutation = dry_run_action(action, &service, &clock, &config)?;
-    if matches.is_present("dry-run") {
-        println!("(dry-run) nothing done");
-        Ok(())
+    let config = if matches.is_present("quiet") {
+        config.verbosity(Verbosity::Quiet)
+    } else if matches.is_present("verbose") {
+        config.verbosity(Verbosity::Verbose)
     } else {
-        run_mutation(mutation, &mut service, &config)
+        config
+    };
+    let config = match matches.value_of("backend") {
+        Some("json") => config.storage_backend(StorageBackend::Json),
+        Some("sqlite") => config.storage_backend(StorageBackend::Sqlite),
+        Some("jsonl") => config.storage_backend(StorageBackend::Jsonl),
+        _ => config,
+    };
+    // storage directory: -d, then $RTW_DIR, then config, then home directory
+    let config = match matches.value_of("directory") {
+        Some(dir_str) => {
+            config.storage_dir_path(PathBuf::from_str(dir_str).expect("invalid directory"))
+        }
+        None => match std::env::var_os(RTW_DIR_ENV) {
+            Some(dir) if !dir.is_empty() => config.storage_dir_path(PathBuf::from(dir)),
+            _ => config,
+        },
+    };
+    let config = match matches.value_of("profile") {
+        Some(profile) => config.profile(Some(profile.to_string())),
+        None => config,
+    };
+    if let Some(profile) = &config.profile {
+        if profile.is_empty() || profile.contains(std::path::is_separator) {
+            anyhow::bail!("invalid profile '{}'", profile);
+        }
+    }
+    match config.storage_backend {
+        StorageBackend::Json => {
+            let current_activity_path = config.data_file_path(json_storage::CURRENT_FILE_NAME);
+            let finished_activity_path = config.json_finished_file_path();
+            log_storage_files(&config, &[&current_activity_path, &finished_activity_path]);
+            let service = Service::new(JsonStorage::new(
+                current_activity_path,
+                finished_activity_path,
+            ));
+            run_service(&matches, service, &clock, &config)
+        }
+        StorageBackend::Sqlite => {
+            let database_path = config.data_file_path(sqlite_storage::DATABASE_FILE_NAME);
+            log_storage_files(&config, &[&database_path]);
+            let service = Service::new(SqliteStorage::new(&database_path)?);
+            run_service(&matches, service, &clock, &config)
+        }
+        StorageBackend::Jsonl => {
+            let current_activity_path = config.data_file_path(json_storage::CURRENT_FILE_NAME);
+            let finished_activity_path = config.data_file_path(jsonl_storage::FINISHED_FILE_NAME);
+            log_storage_files(&config, &[&current_activity_path, &finished_activity_path]);
+            let service = Service::new(JsonlStorage::new(
+                current_activity_path,
+                finished_activity_path,
+            ));
+            run_service(&matches, service, &clock, &config)
+        }
     }
 }
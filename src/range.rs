@@ -0,0 +1,312 @@
+//! Named time ranges: `:today`, `:yesterday`, `:week`, `:lastweek`, `:month`,
+//! and calendar ranges: `2019-12` (month), `2019-W52` (ISO week).
+use crate::rtw_core::clock::Clock;
+use crate::rtw_core::datetimew::DateTimeW;
+use anyhow::anyhow;
+use chrono::{Date, DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Weekday};
+
+/// Range tokens start with this prefix e.g. `:week`
+pub const RANGE_TOKEN_PREFIX: char = ':';
+
+/// Return true if `token` looks like a range token e.g. `:week`, `2019-12` or `2019-W52`
+pub fn is_range_token(token: &str) -> bool {
+    token.starts_with(RANGE_TOKEN_PREFIX) || calendar_token(token).is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CalendarToken {
+    // year, month (not checked)
+    Month(i32, u32),
+    // ISO year, ISO week (not checked)
+    IsoWeek(i32, u32),
+}
+
+// `YYYY-MM` or `YYYY-Www` shape, values are checked by `calendar_range`
+fn calendar_token(token: &str) -> Option<CalendarToken> {
+    let is_number = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    let (year, rest) = (token.get(..4)?, token.get(4..)?);
+    if !is_number(year, 4) {
+        return None;
+    }
+    let year: i32 = year.parse().ok()?;
+    if let Some(week) = rest.strip_prefix("-W") {
+        if is_number(week, 2) {
+            return Some(CalendarToken::IsoWeek(year, week.parse().ok()?));
+        }
+    } else if let Some(month) = rest.strip_prefix('-') {
+        if is_number(month, 2) {
+            return Some(CalendarToken::Month(year, month.parse().ok()?));
+        }
+    }
+    None
+}
+
+fn local_date(date: NaiveDate) -> anyhow::Result<Date<Local>> {
+    Local
+        .from_local_date(&date)
+        .earliest()
+        .ok_or_else(|| anyhow!("invalid local date {}", date))
+}
+
+// whole month or ISO week: first day 00:00:00 - last day 23:59:59
+fn calendar_range(token: &str, calendar: CalendarToken) -> anyhow::Result<(DateTimeW, DateTimeW)> {
+    let (first_day, last_day) = match calendar {
+        CalendarToken::Month(year, month) => {
+            let first_day = NaiveDate::from_ymd_opt(year, month, 1)
+                .ok_or_else(|| anyhow!("invalid month in {}, expected 01 to 12", token))?;
+            let next_month_first_day = if month == 12 {
+                NaiveDate::from_ymd(year + 1, 1, 1)
+            } else {
+                NaiveDate::from_ymd(year, month + 1, 1)
+            };
+            (first_day, next_month_first_day - Duration::days(1))
+        }
+        CalendarToken::IsoWeek(year, week) => {
+            // december 28th is always in the last ISO week of its year
+            let weeks = NaiveDate::from_ymd(year, 12, 28).iso_week().week();
+            let first_day =
+                NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).ok_or_else(|| {
+                    anyhow!("invalid week in {}, expected W01 to W{:02}", token, weeks)
+                })?;
+            (first_day, first_day + Duration::days(6))
+        }
+    };
+    Ok(days_range(local_date(first_day)?, local_date(last_day)?))
+}
+
+/// Range of whole days: `day_start` 00:00:00 - `day_end` 23:59:59
+///
+/// Bounds skipped by a daylight saving time change are moved forward, see `DateTimeW::from_local`.
+pub fn days_range(day_start: Date<Local>, day_end: Date<Local>) -> (DateTimeW, DateTimeW) {
+    (
+        DateTimeW::from_local(day_start.naive_local().and_hms(0, 0, 0)).0,
+        DateTimeW::from_local(day_end.naive_local().and_hms(23, 59, 59)).0,
+    )
+}
+
+/// Start of open-ended ranges e.g. `summary --until`: 1970-01-01T00:00:00Z
+pub fn beginning_of_time() -> DateTimeW {
+    Local.timestamp(0, 0).into()
+}
+
+/// Range of the day containing `datetime`: 00:00:00 - 23:59:59
+pub fn day_range(datetime: DateTimeW) -> (DateTimeW, DateTimeW) {
+    let datetime: DateTime<Local> = datetime.into();
+    days_range(datetime.date(), datetime.date())
+}
+
+/// Number of days from `week_start` to `weekday` (0 - 6) e.g. 5 from monday to saturday
+pub fn days_since_week_start(weekday: Weekday, week_start: Weekday) -> i64 {
+    ((7 + weekday.num_days_from_monday() - week_start.num_days_from_monday()) % 7) as i64
+}
+
+// first day of the week containing `day`
+fn week_start_day(day: Date<Local>, week_start: Weekday) -> Date<Local> {
+    day - Duration::days(days_since_week_start(day.weekday(), week_start))
+}
+
+/// Convert a range token to a concrete range, relative to `clock` current time
+///
+/// * `:today`: today 00:00:00 - 23:59:59
+/// * `:yesterday`: yesterday 00:00:00 - 23:59:59
+/// * `:week`: first day of the week 00:00:00 - now
+/// * `:lastweek`: first day of last week 00:00:00 - last day of last week 23:59:59
+/// * `:month`: first day of the month 00:00:00 - now
+/// * `2019-12`: 2019-12-01 00:00:00 - 2019-12-31 23:59:59
+/// * `2019-W52`: ISO week, monday 2019-12-23 00:00:00 - sunday 2019-12-29 23:59:59
+///
+/// Weeks start on `week_start`, except ISO weeks.
+pub fn range_from_token(
+    token: &str,
+    clock: &dyn Clock,
+    week_start: Weekday,
+) -> anyhow::Result<(DateTimeW, DateTimeW)> {
+    if let Some(calendar) = calendar_token(token) {
+        return calendar_range(token, calendar);
+    }
+    let now = clock.get_time();
+    let now_local: DateTime<Local> = now.into();
+    let today = now_local.date();
+    match token {
+        ":today" => Ok(days_range(today, today)),
+        ":yesterday" => {
+            let yesterday = today - Duration::days(1);
+            Ok(days_range(yesterday, yesterday))
+        }
+        ":week" => {
+            let (week_start, _) = days_range(week_start_day(today, week_start), today);
+            Ok((week_start, now))
+        }
+        ":lastweek" => {
+            let this_week_start = week_start_day(today, week_start);
+            Ok(days_range(
+                this_week_start - Duration::days(7),
+                this_week_start - Duration::days(1),
+            ))
+        }
+        ":month" => {
+            let (month_start, _) = days_range(today.with_day(1).unwrap(), today);
+            Ok((month_start, now))
+        }
+        _ => Err(anyhow!(
+            "invalid range {}, expected one of :today :yesterday :week :lastweek :month, YYYY-MM or YYYY-Www",
+            token
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::range::{is_range_token, range_from_token};
+    use crate::rtw_core::test_utils::{datetimew, FixedClock};
+    use chrono::Weekday;
+
+    #[test]
+    fn test_is_range_token() {
+        assert!(is_range_token(":week"));
+        assert!(!is_range_token("week"));
+        assert!(!is_range_token("09:00"));
+        assert!(is_range_token("2019-12"));
+        assert!(is_range_token("2019-W52"));
+        // checked by `range_from_token`
+        assert!(is_range_token("2019-13"));
+        assert!(!is_range_token("2019-12-25"));
+        assert!(!is_range_token("2019-1"));
+        assert!(!is_range_token("2019-W5"));
+        assert!(!is_range_token("foo"));
+    }
+
+    #[test]
+    fn test_calendar_ranges() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        let table = vec![
+            ("2019-12", "2019-12-01T00:00:00", "2019-12-31T23:59:59"),
+            ("2019-11", "2019-11-01T00:00:00", "2019-11-30T23:59:59"),
+            // leap year
+            ("2020-02", "2020-02-01T00:00:00", "2020-02-29T23:59:59"),
+            ("2019-02", "2019-02-01T00:00:00", "2019-02-28T23:59:59"),
+            ("2000-02", "2000-02-01T00:00:00", "2000-02-29T23:59:59"),
+            ("1900-02", "1900-02-01T00:00:00", "1900-02-28T23:59:59"),
+            ("2019-W52", "2019-12-23T00:00:00", "2019-12-29T23:59:59"),
+            // ISO year differs from calendar year
+            ("2020-W01", "2019-12-30T00:00:00", "2020-01-05T23:59:59"),
+            ("2020-W53", "2020-12-28T00:00:00", "2021-01-03T23:59:59"),
+        ];
+        for (token, expected_start, expected_end) in table {
+            // ISO weeks ignore the configured week start
+            let range = range_from_token(token, &clock, Weekday::Sun).unwrap();
+            assert_eq!(
+                range,
+                (datetimew(expected_start), datetimew(expected_end)),
+                "{}",
+                token
+            );
+        }
+    }
+
+    #[test]
+    fn test_calendar_ranges_invalid() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        for token in &["2019-13", "2019-00", "2019-W54", "2019-W53", "2019-W00"] {
+            let error = range_from_token(token, &clock, Weekday::Mon).unwrap_err();
+            assert!(error.to_string().starts_with("invalid"), "{}", token);
+        }
+        let error = range_from_token("2019-13", &clock, Weekday::Mon).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid month in 2019-13, expected 01 to 12"
+        );
+        let error = range_from_token("2019-W54", &clock, Weekday::Mon).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid week in 2019-W54, expected W01 to W52"
+        );
+    }
+
+    #[test]
+    fn test_range_from_token() {
+        // 2020-12-23 is a wednesday
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        let table = vec![
+            (
+                ":today",
+                Weekday::Mon,
+                "2020-12-23T00:00:00",
+                "2020-12-23T23:59:59",
+            ),
+            (
+                ":yesterday",
+                Weekday::Mon,
+                "2020-12-22T00:00:00",
+                "2020-12-22T23:59:59",
+            ),
+            (
+                ":week",
+                Weekday::Mon,
+                "2020-12-21T00:00:00",
+                "2020-12-23T15:30:00",
+            ),
+            (
+                ":week",
+                Weekday::Sun,
+                "2020-12-20T00:00:00",
+                "2020-12-23T15:30:00",
+            ),
+            (
+                ":week",
+                Weekday::Wed,
+                "2020-12-23T00:00:00",
+                "2020-12-23T15:30:00",
+            ),
+            (
+                ":week",
+                Weekday::Thu,
+                "2020-12-17T00:00:00",
+                "2020-12-23T15:30:00",
+            ),
+            (
+                ":lastweek",
+                Weekday::Mon,
+                "2020-12-14T00:00:00",
+                "2020-12-20T23:59:59",
+            ),
+            (
+                ":lastweek",
+                Weekday::Sun,
+                "2020-12-13T00:00:00",
+                "2020-12-19T23:59:59",
+            ),
+            (
+                ":month",
+                Weekday::Mon,
+                "2020-12-01T00:00:00",
+                "2020-12-23T15:30:00",
+            ),
+        ];
+        for (token, week_start, expected_start, expected_end) in table {
+            let range = range_from_token(token, &clock, week_start).unwrap();
+            assert_eq!(
+                range,
+                (datetimew(expected_start), datetimew(expected_end)),
+                "{} (week start: {:?})",
+                token,
+                week_start
+            );
+        }
+    }
+
+    #[test]
+    fn test_range_from_token_invalid() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        assert!(range_from_token(":fortnight", &clock, Weekday::Mon).is_err());
+        assert!(range_from_token("week", &clock, Weekday::Mon).is_err());
+    }
+}
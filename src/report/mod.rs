@@ -0,0 +1,563 @@
+//! Aggregations over finished activities.
+pub mod period;
+pub mod stats;
+pub mod timeline;
+pub mod week_grid;
+
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::durationw::DurationW;
+use crate::rtw_core::{tag_key_value, ActivityId, Tag};
+use chrono::Duration;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+/// Tag statistics: number of activities and total duration
+pub type TagStats = (Tag, usize, Duration);
+
+/// Compute per-tag activity count and total duration
+///
+/// An activity counts fully toward each of its tags.
+///
+/// Returns tags sorted by total duration (descending), then by tag.
+pub fn tag_stats(activities: &[(ActivityId, Activity)]) -> Vec<TagStats> {
+    let mut stats: HashMap<Tag, (usize, Duration)> = HashMap::new();
+    for (_id, activity) in activities {
+        let duration: Duration = activity.get_duration().into();
+        for tag in activity.get_tags() {
+            let entry = stats.entry(tag).or_insert((0, Duration::seconds(0)));
+            entry.0 += 1;
+            entry.1 = entry.1 + duration;
+        }
+    }
+    sort_tag_stats(
+        stats
+            .into_iter()
+            .map(|(tag, (count, total))| (tag, count, total))
+            .collect(),
+    )
+}
+
+/// Sort tags by total duration (descending), then by tag
+pub fn sort_tag_stats(mut stats: Vec<TagStats>) -> Vec<TagStats> {
+    stats.sort_by(|(tag_a, _, total_a), (tag_b, _, total_b)| {
+        total_b.cmp(total_a).then_with(|| tag_a.cmp(tag_b))
+    });
+    stats
+}
+
+/// Compute per-tag total duration
+///
+/// An activity counts fully toward each of its tags:
+/// the sum of tag durations exceeds the tracked time when activities have several tags.
+pub fn tag_durations(activities: &[(ActivityId, Activity)]) -> HashMap<Tag, DurationW> {
+    let mut durations: HashMap<Tag, Duration> = HashMap::new();
+    for (_id, activity) in activities {
+        let duration: Duration = activity.get_duration().into();
+        for tag in activity.get_tags() {
+            let total = durations.entry(tag).or_insert_with(|| Duration::seconds(0));
+            *total = *total + duration;
+        }
+    }
+    durations
+        .into_iter()
+        .map(|(tag, total)| (tag, DurationW::from(total)))
+        .collect()
+}
+
+/// Group of activities without the grouping key in `key_durations`
+pub const NO_VALUE: &str = "(none)";
+
+/// Compute per-value total duration of `key:value` tags with the given `key`
+///
+/// An activity counts fully toward each of its values, activities without `key` count toward `NO_VALUE`.
+pub fn key_durations(activities: &[(ActivityId, Activity)], key: &str) -> HashMap<Tag, DurationW> {
+    let mut durations: HashMap<Tag, Duration> = HashMap::new();
+    for (_id, activity) in activities {
+        let duration: Duration = activity.get_duration().into();
+        let mut values: Vec<Tag> = activity
+            .get_tags()
+            .iter()
+            .filter_map(|tag| tag_key_value(tag))
+            .filter(|(tag_key, _value)| *tag_key == key)
+            .map(|(_key, value)| value.to_string())
+            .collect();
+        if values.is_empty() {
+            values.push(NO_VALUE.to_string());
+        }
+        for value in values {
+            let total = durations
+                .entry(value)
+                .or_insert_with(|| Duration::seconds(0));
+            *total = *total + duration;
+        }
+    }
+    durations
+        .into_iter()
+        .map(|(value, total)| (value, DurationW::from(total)))
+        .collect()
+}
+
+/// Compute wall-clock tracked duration
+///
+/// Time shared by overlapping activities is counted once.
+pub fn tracked_duration(activities: &[(ActivityId, Activity)]) -> DurationW {
+    let mut activities: Vec<&Activity> = activities.iter().map(|(_id, a)| a).collect();
+    activities.sort();
+    let mut total = Duration::seconds(0);
+    let mut cursor: Option<DateTimeW> = None;
+    for activity in activities {
+        let start = match cursor {
+            None => activity.get_start_time(),
+            Some(cursor) => cursor.max(activity.get_start_time()),
+        };
+        if activity.get_stop_time() > start {
+            let duration: Duration = (activity.get_stop_time() - start).into();
+            total = total + duration;
+            cursor = Some(activity.get_stop_time());
+        }
+    }
+    DurationW::from(total)
+}
+
+/// Compute untracked intervals within `range`
+///
+/// Gaps between consecutive activities, before the first one and after the last one are returned.
+///
+/// Gaps shorter than `min` are skipped.
+pub fn gaps(
+    activities: &[(ActivityId, Activity)],
+    range: (DateTimeW, DateTimeW),
+    min: Duration,
+) -> Vec<(DateTimeW, DateTimeW)> {
+    let (range_start, range_end) = range;
+    let mut activities: Vec<&Activity> = activities.iter().map(|(_id, a)| a).collect();
+    activities.sort();
+    let mut gaps = vec![];
+    let mut cursor = range_start;
+    for activity in activities {
+        if activity.get_start_time() > cursor {
+            gaps.push((cursor, activity.get_start_time().min(range_end)));
+        }
+        cursor = cursor.max(activity.get_stop_time());
+        if cursor >= range_end {
+            break;
+        }
+    }
+    if cursor < range_end {
+        gaps.push((cursor, range_end));
+    }
+    gaps.into_iter()
+        .filter(|(start, end)| {
+            let duration: Duration = (*end - *start).into();
+            duration >= min
+        })
+        .collect()
+}
+
+/// Overlapping pair of finished activities with the overlapping interval
+pub type Overlap = (
+    (ActivityId, Activity),
+    (ActivityId, Activity),
+    (DateTimeW, DateTimeW),
+);
+
+/// Find every pair of overlapping (double-booked) activities
+///
+/// Returns pairs sorted by start time, the earlier activity first.
+pub fn overlaps(activities: &[(ActivityId, Activity)]) -> Vec<Overlap> {
+    let mut activities: Vec<&(ActivityId, Activity)> = activities.iter().collect();
+    activities.sort_by(|(_, a), (_, b)| a.cmp(b));
+    let mut overlaps = vec![];
+    for (index, (id, activity)) in activities.iter().enumerate() {
+        for (other_id, other) in activities[index + 1..].iter() {
+            // sorted by start time: later activities cannot overlap either
+            if other.get_start_time() >= activity.get_stop_time() {
+                break;
+            }
+            if activity.overlaps(other) {
+                let interval = (
+                    other.get_start_time(),
+                    activity.get_stop_time().min(other.get_stop_time()),
+                );
+                overlaps.push((
+                    (*id, activity.clone()),
+                    (*other_id, other.clone()),
+                    interval,
+                ));
+            }
+        }
+    }
+    overlaps
+}
+
+/// Most recent activity of each distinct tag set (tags order does not matter)
+///
+/// Returns at most `count` activities, most recent (start time) first.
+pub fn recent_tag_sets(
+    activities: &[(ActivityId, Activity)],
+    count: usize,
+) -> Vec<(ActivityId, Activity)> {
+    let mut activities: Vec<&(ActivityId, Activity)> = activities.iter().collect();
+    activities.sort_by_key(|(_id, activity)| Reverse(activity.get_start_time()));
+    let mut seen: HashSet<Vec<Tag>> = HashSet::new();
+    activities
+        .into_iter()
+        .filter(|(_id, activity)| {
+            let mut tags = activity.get_tags();
+            tags.sort();
+            seen.insert(tags)
+        })
+        .take(count)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::{
+        gaps, key_durations, overlaps, recent_tag_sets, tag_durations, tag_stats, tracked_duration,
+        NO_VALUE,
+    };
+    use crate::rtw_core::test_utils::{activity, datetimew};
+    use chrono::Duration;
+    use itertools::Itertools;
+
+    #[test]
+    fn test_tag_stats_empty() {
+        assert!(tag_stats(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_tag_stats() {
+        let activities = vec![
+            (
+                2,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity(
+                    "2020-12-25T10:00:00",
+                    "2020-12-25T10:30:00",
+                    &["foo", "bar"],
+                ),
+            ),
+            (
+                0,
+                activity("2020-12-25T11:00:00", "2020-12-25T13:00:00", &["baz"]),
+            ),
+        ];
+        let stats = tag_stats(activities.as_slice());
+        assert_eq!(
+            stats,
+            vec![
+                (String::from("baz"), 1, Duration::hours(2)),
+                (String::from("foo"), 2, Duration::minutes(90)),
+                (String::from("bar"), 1, Duration::minutes(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_key_durations() {
+        let activities = vec![
+            (
+                0,
+                activity(
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T10:00:00",
+                    &["project:alpha", "review"],
+                ),
+            ),
+            (
+                1,
+                activity(
+                    "2020-12-25T10:00:00",
+                    "2020-12-25T10:30:00",
+                    &["project:beta"],
+                ),
+            ),
+            (
+                2,
+                activity(
+                    "2020-12-25T11:00:00",
+                    "2020-12-25T11:15:00",
+                    &["project:alpha"],
+                ),
+            ),
+            // without the key
+            (
+                3,
+                activity("2020-12-25T12:00:00", "2020-12-25T12:45:00", &["lunch"]),
+            ),
+            (
+                4,
+                activity(
+                    "2020-12-25T13:00:00",
+                    "2020-12-25T13:10:00",
+                    &["client:acme"],
+                ),
+            ),
+        ];
+        let durations: Vec<(String, Duration)> = key_durations(activities.as_slice(), "project")
+            .into_iter()
+            .map(|(value, d)| (value, d.into()))
+            .sorted()
+            .collect();
+        assert_eq!(
+            durations,
+            vec![
+                (String::from(NO_VALUE), Duration::minutes(55)),
+                (String::from("alpha"), Duration::minutes(75)),
+                (String::from("beta"), Duration::minutes(30)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tag_durations_and_tracked_duration() {
+        let activities = vec![
+            (
+                2,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            // overlaps foo
+            (
+                1,
+                activity(
+                    "2020-12-25T09:30:00",
+                    "2020-12-25T10:30:00",
+                    &["foo", "bar"],
+                ),
+            ),
+            (
+                0,
+                activity("2020-12-25T11:00:00", "2020-12-25T11:15:00", &["baz"]),
+            ),
+        ];
+        let durations: Vec<(String, Duration)> = tag_durations(activities.as_slice())
+            .into_iter()
+            .map(|(tag, d)| (tag, d.into()))
+            .sorted()
+            .collect();
+        assert_eq!(
+            durations,
+            vec![
+                (String::from("bar"), Duration::hours(1)),
+                (String::from("baz"), Duration::minutes(15)),
+                (String::from("foo"), Duration::hours(2)),
+            ]
+        );
+        // 09:00 - 10:30 and 11:00 - 11:15, overlapping time counted once
+        let tracked: Duration = tracked_duration(activities.as_slice()).into();
+        assert_eq!(tracked, Duration::minutes(105));
+    }
+
+    #[test]
+    fn test_tracked_duration_contained() {
+        let activities = vec![
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &["foo"]),
+            ),
+            (
+                0,
+                activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]),
+            ),
+        ];
+        let tracked: Duration = tracked_duration(activities.as_slice()).into();
+        assert_eq!(tracked, Duration::hours(3));
+        let tracked: Duration = tracked_duration(&[]).into();
+        assert_eq!(tracked, Duration::seconds(0));
+    }
+
+    #[test]
+    fn test_gaps_empty() {
+        let range = (
+            datetimew("2020-12-25T09:00:00"),
+            datetimew("2020-12-25T12:00:00"),
+        );
+        assert_eq!(gaps(&[], range, Duration::minutes(1)), vec![range]);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let activities = vec![
+            (
+                2,
+                activity("2020-12-25T09:30:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T10:00:30", "2020-12-25T10:30:00", &["bar"]),
+            ),
+            (
+                0,
+                activity("2020-12-25T11:00:00", "2020-12-25T11:30:00", &["baz"]),
+            ),
+        ];
+        let range = (
+            datetimew("2020-12-25T09:00:00"),
+            datetimew("2020-12-25T12:00:00"),
+        );
+        assert_eq!(
+            gaps(activities.as_slice(), range, Duration::minutes(1)),
+            vec![
+                (
+                    datetimew("2020-12-25T09:00:00"),
+                    datetimew("2020-12-25T09:30:00")
+                ),
+                // 30s gap between foo and bar is skipped
+                (
+                    datetimew("2020-12-25T10:30:00"),
+                    datetimew("2020-12-25T11:00:00")
+                ),
+                (
+                    datetimew("2020-12-25T11:30:00"),
+                    datetimew("2020-12-25T12:00:00")
+                ),
+            ]
+        );
+        assert_eq!(
+            gaps(activities.as_slice(), range, Duration::seconds(0)).len(),
+            4
+        );
+    }
+
+    #[test]
+    fn test_gaps_overlapping_and_out_of_range() {
+        let activities = vec![
+            (
+                2,
+                activity("2020-12-25T08:00:00", "2020-12-25T09:30:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T11:00:00", &["bar"]),
+            ),
+            (
+                0,
+                activity("2020-12-25T09:30:00", "2020-12-25T10:00:00", &["baz"]),
+            ),
+        ];
+        let range = (
+            datetimew("2020-12-25T09:00:00"),
+            datetimew("2020-12-25T12:00:00"),
+        );
+        assert_eq!(
+            gaps(activities.as_slice(), range, Duration::minutes(1)),
+            vec![(
+                datetimew("2020-12-25T11:00:00"),
+                datetimew("2020-12-25T12:00:00")
+            )]
+        );
+    }
+
+    #[test]
+    fn test_recent_tag_sets() {
+        let activities = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity(
+                    "2020-12-25T10:00:00",
+                    "2020-12-25T11:00:00",
+                    &["bar", "baz"],
+                ),
+            ),
+            (
+                2,
+                activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["foo"]),
+            ),
+            (
+                3,
+                activity(
+                    "2020-12-25T12:00:00",
+                    "2020-12-25T13:00:00",
+                    &["baz", "bar"],
+                ),
+            ),
+            (
+                4,
+                activity("2020-12-25T08:00:00", "2020-12-25T09:00:00", &["qux"]),
+            ),
+        ];
+        let ids: Vec<usize> = recent_tag_sets(&activities, 10)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![3, 2, 4]);
+        let ids: Vec<usize> = recent_tag_sets(&activities, 2)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(ids, vec![3, 2]);
+        assert!(recent_tag_sets(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_overlaps_nested_and_partial() {
+        let activities = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &["outer"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["nested"]),
+            ),
+            (
+                2,
+                activity("2020-12-25T11:30:00", "2020-12-25T13:00:00", &["partial"]),
+            ),
+            (
+                3,
+                activity("2020-12-25T13:00:00", "2020-12-25T14:00:00", &["adjacent"]),
+            ),
+            (
+                4,
+                activity("2020-12-25T15:00:00", "2020-12-25T16:00:00", &["alone"]),
+            ),
+        ];
+        let pairs: Vec<(usize, usize, String, String)> = overlaps(&activities)
+            .into_iter()
+            .map(|((a, _), (b, _), (start, end))| (a, b, start.to_string(), end.to_string()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (
+                    0,
+                    1,
+                    "2020-12-25T10:00:00".to_string(),
+                    "2020-12-25T11:00:00".to_string()
+                ),
+                (
+                    0,
+                    2,
+                    "2020-12-25T11:30:00".to_string(),
+                    "2020-12-25T12:00:00".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlaps_none() {
+        let activities = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]),
+            ),
+        ];
+        assert!(overlaps(&activities).is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+//! Group finished activities by day or week.
+use crate::range::days_since_week_start;
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::ActivityId;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+/// Grouping period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    /// local day
+    Day,
+    /// week starting on the given day, ISO 8601 week when monday
+    Week(Weekday),
+}
+
+/// Number of activities and total duration within a period
+pub type PeriodStats = (usize, Duration);
+
+// first day of the period containing `day`
+fn period_start(day: NaiveDate, period: Period) -> NaiveDate {
+    match period {
+        Period::Day => day,
+        Period::Week(week_start) => {
+            day - Duration::days(days_since_week_start(day.weekday(), week_start))
+        }
+    }
+}
+
+/// Label of the period starting on `start` e.g. `2020-12-25` (day) or `2020-W52` (ISO week)
+///
+/// Weeks not starting on monday are labelled by their first day.
+pub fn period_label(start: NaiveDate, period: Period) -> String {
+    match period {
+        Period::Week(Weekday::Mon) => {
+            let week = start.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        Period::Day | Period::Week(_) => start.format("%Y-%m-%d").to_string(),
+    }
+}
+
+/// Group activities by period of their (local) start time
+///
+/// An activity counts fully toward the period it starts in,
+/// unless `clip` is given: only the activity part within the clip range counts then.
+///
+/// Returns stats keyed by period first day, in chronological order.
+pub fn period_stats(
+    activities: &[(ActivityId, Activity)],
+    period: Period,
+    clip: Option<(DateTimeW, DateTimeW)>,
+) -> BTreeMap<NaiveDate, PeriodStats> {
+    let mut stats: BTreeMap<NaiveDate, PeriodStats> = BTreeMap::new();
+    for (_id, activity) in activities {
+        let (start_time, duration) = match clip {
+            None => (activity.get_start_time(), activity.get_duration()),
+            Some((range_start, range_end)) => (
+                activity.get_start_time().max(range_start),
+                activity.clipped_duration(range_start, range_end),
+            ),
+        };
+        let start_time: DateTime<Local> = start_time.into();
+        let key = period_start(start_time.date().naive_local(), period);
+        let duration: Duration = duration.into();
+        let entry = stats.entry(key).or_insert((0, Duration::seconds(0)));
+        entry.0 += 1;
+        entry.1 = entry.1 + duration;
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::period::{period_label, period_stats, Period};
+    use crate::rtw_core::test_utils::{activity, datetimew};
+    use chrono::{Duration, NaiveDate, Weekday};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_period_stats_by_day() {
+        let activities = vec![
+            (
+                2,
+                activity("2020-12-24T09:00:00", "2020-12-24T10:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:30:00", &["foo"]),
+            ),
+            // counts toward the day it starts in
+            (
+                0,
+                activity("2020-12-25T23:00:00", "2020-12-26T01:00:00", &["foo"]),
+            ),
+        ];
+        let stats: Vec<_> = period_stats(&activities, Period::Day, None)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                (date("2020-12-24"), (1, Duration::hours(1))),
+                (date("2020-12-25"), (2, Duration::minutes(210))),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_period_stats_by_week() {
+        let activities = vec![
+            // sunday
+            (
+                2,
+                activity("2020-12-20T09:00:00", "2020-12-20T10:00:00", &["foo"]),
+            ),
+            // monday, tuesday
+            (
+                1,
+                activity("2020-12-21T09:00:00", "2020-12-21T10:00:00", &["foo"]),
+            ),
+            (
+                0,
+                activity("2020-12-22T09:00:00", "2020-12-22T10:00:00", &["foo"]),
+            ),
+        ];
+        let stats: Vec<_> = period_stats(&activities, Period::Week(Weekday::Mon), None)
+            .into_iter()
+            .collect();
+        assert_eq!(
+            stats,
+            vec![
+                (date("2020-12-14"), (1, Duration::hours(1))),
+                (date("2020-12-21"), (2, Duration::hours(2))),
+            ]
+        );
+        // weeks starting on sunday
+        let stats: Vec<_> = period_stats(&activities, Period::Week(Weekday::Sun), None)
+            .into_iter()
+            .collect();
+        assert_eq!(stats, vec![(date("2020-12-20"), (3, Duration::hours(3)))]);
+    }
+
+    #[test]
+    fn test_period_stats_clipped() {
+        let activities = vec![
+            // straddling the clip range start, counts toward the range first day
+            (
+                0,
+                activity("2020-12-24T23:00:00", "2020-12-25T01:00:00", &["foo"]),
+            ),
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+        ];
+        let clip = (
+            datetimew("2020-12-25T00:00:00"),
+            datetimew("2020-12-25T23:59:59"),
+        );
+        let stats: Vec<_> = period_stats(&activities, Period::Day, Some(clip))
+            .into_iter()
+            .collect();
+        assert_eq!(stats, vec![(date("2020-12-25"), (2, Duration::hours(2)))]);
+    }
+
+    #[test]
+    fn test_period_label() {
+        assert_eq!(period_label(date("2020-12-25"), Period::Day), "2020-12-25");
+        let iso_week = Period::Week(Weekday::Mon);
+        assert_eq!(period_label(date("2020-12-21"), iso_week), "2020-W52");
+        // ISO year differs from calendar year
+        assert_eq!(period_label(date("2020-12-28"), iso_week), "2020-W53");
+        assert_eq!(period_label(date("2018-12-31"), iso_week), "2019-W01");
+        assert_eq!(
+            period_label(date("2020-12-20"), Period::Week(Weekday::Sun)),
+            "2020-12-20"
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Activity length statistics.
+use crate::report::period::{period_stats, Period};
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::ActivityId;
+use chrono::{Duration, NaiveDate};
+
+/// Statistics over finished activities
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    /// sum of activity durations
+    pub total: Duration,
+    pub shortest: Duration,
+    pub longest: Duration,
+    /// truncated to whole seconds
+    pub mean: Duration,
+    /// day (of activities start) with the largest total duration, earliest on ties
+    pub busiest_day: (NaiveDate, Duration),
+}
+
+/// Compute statistics, None if there is no activity
+pub fn stats(activities: &[(ActivityId, Activity)]) -> Option<Stats> {
+    let durations: Vec<Duration> = activities
+        .iter()
+        .map(|(_id, a)| a.get_duration().into())
+        .collect();
+    let shortest = *durations.iter().min()?;
+    let longest = *durations.iter().max()?;
+    let total = durations
+        .iter()
+        .fold(Duration::seconds(0), |acc, duration| acc + *duration);
+    let count = durations.len();
+    let mean = Duration::seconds(total.num_seconds() / count as i64);
+    let busiest_day = period_stats(activities, Period::Day, None)
+        .into_iter()
+        .fold(
+            None,
+            |busiest: Option<(NaiveDate, Duration)>, (day, (_, total))| match busiest {
+                Some((_, busiest_total)) if busiest_total >= total => busiest,
+                _ => Some((day, total)),
+            },
+        )?;
+    Some(Stats {
+        count,
+        total,
+        shortest,
+        longest,
+        mean,
+        busiest_day,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::stats::{stats, Stats};
+    use crate::rtw_core::test_utils::activity;
+    use chrono::{Duration, NaiveDate};
+
+    #[test]
+    fn test_stats_empty() {
+        assert_eq!(stats(&[]), None);
+    }
+
+    #[test]
+    fn test_stats() {
+        let activities = vec![
+            (
+                0,
+                activity("2020-12-24T09:00:00", "2020-12-24T10:00:00", &[]),
+            ),
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T09:15:00", &[]),
+            ),
+            (
+                2,
+                activity("2020-12-25T10:00:00", "2020-12-25T12:00:00", &[]),
+            ),
+            (
+                3,
+                activity("2020-12-26T09:00:00", "2020-12-26T09:20:00", &[]),
+            ),
+        ];
+        assert_eq!(
+            stats(&activities),
+            Some(Stats {
+                count: 4,
+                total: Duration::minutes(215),
+                shortest: Duration::minutes(15),
+                longest: Duration::hours(2),
+                mean: Duration::seconds(215 * 60 / 4),
+                busiest_day: (NaiveDate::from_ymd(2020, 12, 25), Duration::minutes(135)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_stats_busiest_day_tie_is_earliest() {
+        let activities = vec![
+            (
+                0,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]),
+            ),
+            (
+                1,
+                activity("2020-12-24T09:00:00", "2020-12-24T10:00:00", &[]),
+            ),
+        ];
+        let busiest_day = stats(&activities).unwrap().busiest_day;
+        assert_eq!(busiest_day.0, NaiveDate::from_ymd(2020, 12, 24));
+    }
+}
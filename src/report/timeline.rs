@@ -0,0 +1,139 @@
+//! ASCII day chart: one row per activity, bars spanning activity hours.
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::ActivityId;
+use chrono::{DateTime, Duration, Local, Timelike};
+
+/// Default chart width (bars only, labels excluded)
+pub const DEFAULT_CHART_WIDTH: usize = 72;
+
+const BAR: char = '#';
+const EMPTY: char = ' ';
+const BORDER: char = '|';
+
+// (earliest start truncated to the hour, latest stop rounded up to the hour)
+fn axis_bounds(activities: &[(ActivityId, Activity)]) -> Option<(DateTimeW, DateTimeW)> {
+    let earliest = activities.iter().map(|(_, a)| a.get_start_time()).min()?;
+    let latest = activities.iter().map(|(_, a)| a.get_stop_time()).max()?;
+    let earliest: DateTime<Local> = earliest.into();
+    let latest: DateTime<Local> = latest.into();
+    let axis_start = earliest.date().and_hms(earliest.hour(), 0, 0);
+    let latest_hour = latest.date().and_hms(latest.hour(), 0, 0);
+    let axis_end = if latest_hour == latest {
+        latest_hour
+    } else {
+        latest_hour + Duration::hours(1)
+    };
+    let axis_end = axis_end.max(axis_start + Duration::hours(1));
+    Some((axis_start.into(), axis_end.into()))
+}
+
+/// Render `activities` as an ASCII chart
+///
+/// The axis spans from the earliest activity start hour to the latest activity stop hour.
+///
+/// `width` is the number of columns used by bars, each hour uses at least 3 columns.
+///
+/// Returns one line for the axis then one line per activity (sorted by start time).
+pub fn render_day_chart(activities: &[(ActivityId, Activity)], width: usize) -> Vec<String> {
+    let (axis_start, axis_end) = match axis_bounds(activities) {
+        None => return vec![],
+        Some(bounds) => bounds,
+    };
+    let axis_start_local: DateTime<Local> = axis_start.into();
+    let span: Duration = (axis_end - axis_start).into();
+    let hours = span.num_hours().max(1) as usize;
+    let columns_per_hour = (width / hours).max(3);
+    let columns = columns_per_hour * hours;
+    let total_seconds = (hours * 3600) as i64;
+    let column = |t: DateTimeW| -> usize {
+        let elapsed: Duration = (t - axis_start).into();
+        let seconds = elapsed.num_seconds().max(0).min(total_seconds);
+        (seconds as usize * columns) / total_seconds as usize
+    };
+    let mut activities: Vec<&(ActivityId, Activity)> = activities.iter().collect();
+    activities.sort_by(|(_, a), (_, b)| a.cmp(b));
+    let labels: Vec<String> = activities
+        .iter()
+        .map(|(id, a)| format!("{:>3} {}", id, a.get_title()))
+        .collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let axis: String = (0..hours)
+        .map(|h| {
+            let hour = (axis_start_local + Duration::hours(h as i64)).hour();
+            format!(
+                "{:<width$}",
+                format!("{:02}", hour),
+                width = columns_per_hour
+            )
+        })
+        .collect();
+    let mut lines = vec![format!("{:width$}  {}", "", axis, width = label_width)];
+    for ((_, activity), label) in activities.iter().zip(labels.iter()) {
+        let bar_start = column(activity.get_start_time()).min(columns - 1);
+        let bar_end = column(activity.get_stop_time()).max(bar_start + 1);
+        let bar: String = (0..columns)
+            .map(|c| {
+                if bar_start <= c && c < bar_end {
+                    BAR
+                } else {
+                    EMPTY
+                }
+            })
+            .collect();
+        lines.push(format!(
+            "{:width$} {}{}{}",
+            label,
+            BORDER,
+            bar,
+            BORDER,
+            width = label_width
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::timeline::render_day_chart;
+    use crate::rtw_core::test_utils::activity;
+
+    #[test]
+    fn test_render_day_chart_empty() {
+        assert!(render_day_chart(&[], 72).is_empty());
+    }
+
+    #[test]
+    fn test_render_day_chart() {
+        let activities = vec![
+            (
+                1,
+                activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]),
+            ),
+            (
+                0,
+                activity("2020-12-25T09:30:00", "2020-12-25T11:00:00", &["bar"]),
+            ),
+        ];
+        let lines = render_day_chart(activities.as_slice(), 8);
+        assert_eq!(
+            lines,
+            vec![
+                "         09  10  ",
+                "  1 foo |####    |",
+                "  0 bar |  ######|",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_day_chart_short_activity() {
+        // a short activity still gets a 1 column bar
+        let activities = vec![(
+            0,
+            activity("2020-12-25T09:00:00", "2020-12-25T09:01:00", &["foo"]),
+        )];
+        let lines = render_day_chart(activities.as_slice(), 4);
+        assert_eq!(lines, vec!["         09  ", "  0 foo |#   |"]);
+    }
+}
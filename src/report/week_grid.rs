@@ -0,0 +1,165 @@
+//! Week by day grid of daily totals, like a contribution graph.
+use crate::report::period::{period_label, Period, PeriodStats};
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+const DAY_NAMES: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+// cell of a day outside the displayed range
+const OUTSIDE: &str = "  ";
+
+/// Intensity of a day cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    /// nothing tracked
+    Blank,
+    Light,
+    Medium,
+    Heavy,
+}
+
+impl Level {
+    fn cell(self) -> &'static str {
+        match self {
+            Level::Blank => "··",
+            Level::Light => "░░",
+            Level::Medium => "▒▒",
+            Level::Heavy => "██",
+        }
+    }
+}
+
+/// Level of a day total given (medium, heavy) thresholds
+///
+/// A day is medium from the medium threshold (included) and heavy from the heavy threshold (included).
+pub fn level(total: Duration, (medium, heavy): (Duration, Duration)) -> Level {
+    if total <= Duration::zero() {
+        Level::Blank
+    } else if total >= heavy {
+        Level::Heavy
+    } else if total >= medium {
+        Level::Medium
+    } else {
+        Level::Light
+    }
+}
+
+// nearest-rank percentile of sorted values
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let rank = ((percent * sorted.len()) as f64 / 100.0).ceil() as usize;
+    sorted[rank.max(1) - 1]
+}
+
+/// Default (medium, heavy) thresholds: median and third quartile of non-zero daily totals
+pub fn default_thresholds(totals: &[Duration]) -> (Duration, Duration) {
+    let mut totals: Vec<Duration> = totals
+        .iter()
+        .copied()
+        .filter(|total| *total > Duration::zero())
+        .collect();
+    if totals.is_empty() {
+        return (Duration::zero(), Duration::zero());
+    }
+    totals.sort();
+    (percentile(&totals, 50), percentile(&totals, 75))
+}
+
+/// Render one row per ISO week from `first_day` to `last_day` (included), one cell per day
+///
+/// `days` are stats keyed by day e.g. `period_stats(activities, Period::Day)`.
+///
+/// Returns a header line then one line per week.
+pub fn render_week_grid(
+    days: &BTreeMap<NaiveDate, PeriodStats>,
+    (first_day, last_day): (NaiveDate, NaiveDate),
+    thresholds: (Duration, Duration),
+) -> Vec<String> {
+    let label_width = period_label(first_day, Period::Week(Weekday::Mon)).len();
+    let mut lines = vec![format!(
+        "{:width$} {}",
+        "",
+        DAY_NAMES.join(" "),
+        width = label_width
+    )];
+    let mut week_start =
+        first_day - Duration::days(first_day.weekday().num_days_from_monday() as i64);
+    while week_start <= last_day {
+        let cells: Vec<&str> = (0..7)
+            .map(|offset| {
+                let day = week_start + Duration::days(offset);
+                if day < first_day || day > last_day {
+                    OUTSIDE
+                } else {
+                    let total = days
+                        .get(&day)
+                        .map(|(_count, total)| *total)
+                        .unwrap_or_else(Duration::zero);
+                    level(total, thresholds).cell()
+                }
+            })
+            .collect();
+        let line = format!(
+            "{} {}",
+            period_label(week_start, Period::Week(Weekday::Mon)),
+            cells.join(" ")
+        );
+        lines.push(line.trim_end().to_string());
+        week_start += Duration::weeks(1);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::report::week_grid::{default_thresholds, level, render_week_grid, Level};
+    use chrono::{Duration, NaiveDate};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_level() {
+        let thresholds = (Duration::hours(2), Duration::hours(6));
+        assert_eq!(level(Duration::zero(), thresholds), Level::Blank);
+        assert_eq!(level(Duration::minutes(30), thresholds), Level::Light);
+        assert_eq!(level(Duration::hours(2), thresholds), Level::Medium);
+        assert_eq!(level(Duration::hours(5), thresholds), Level::Medium);
+        assert_eq!(level(Duration::hours(6), thresholds), Level::Heavy);
+    }
+
+    #[test]
+    fn test_default_thresholds() {
+        let totals: Vec<Duration> = vec![0, 1, 2, 3, 4]
+            .into_iter()
+            .map(Duration::hours)
+            .collect();
+        // zero totals are ignored: median of 1, 2, 3, 4 is 2, third quartile is 3
+        assert_eq!(
+            default_thresholds(&totals),
+            (Duration::hours(2), Duration::hours(3))
+        );
+        assert_eq!(
+            default_thresholds(&[]),
+            (Duration::zero(), Duration::zero())
+        );
+    }
+
+    #[test]
+    fn test_render_week_grid() {
+        let day = |d: u32| NaiveDate::from_ymd(2020, 12, d);
+        let mut days = BTreeMap::new();
+        days.insert(day(23), (1, Duration::hours(1)));
+        days.insert(day(24), (1, Duration::hours(4)));
+        days.insert(day(25), (2, Duration::hours(8)));
+        let lines = render_week_grid(
+            &days,
+            (day(23), day(29)),
+            (Duration::hours(4), Duration::hours(8)),
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "         Mo Tu We Th Fr Sa Su",
+                "2020-W52       ░░ ▒▒ ██ ·· ··",
+                "2020-W53 ·· ··",
+            ]
+        );
+    }
+}
@@ -1,66 +1,420 @@
 //! Translate CLI args to calls to activity Service.
 use crate::cli_helper;
+use crate::doctor::{check_activities, Finding};
+use crate::editor::{edit_activities, edited_activities, EditedActivities, EDIT_FILE_NAME};
+use crate::export::{
+    export_activities, export_ongoing_activities_to_json, export_summary_to_json,
+    export_summary_to_markdown, import_activities_from_json, ExportFormat, ExportedActivity,
+    ImportFormat, OutputFormat,
+};
+use crate::goals::{format_delta, goal_statuses, load_goals, save_goals, Goals, GOALS_FILE_NAME};
+use crate::hooks::{run_hook, ON_START, ON_STOP};
 use crate::ical_export::export_activities_to_ical;
+use crate::interop::timewarrior;
+use crate::report::period::{period_label, period_stats, Period};
+use crate::report::stats::{stats, Stats};
+use crate::report::timeline::{render_day_chart, DEFAULT_CHART_WIDTH};
+use crate::report::week_grid::{default_thresholds, render_week_grid};
+use crate::report::{
+    gaps, key_durations, overlaps, recent_tag_sets, tag_durations, tag_stats, tracked_duration,
+    TagStats,
+};
 use crate::rtw_cli::OptionalOrAmbiguousOrNotFound::Optional;
 use crate::rtw_config::RTWConfig;
-use crate::rtw_core::activity::{Activity, OngoingActivity};
-use crate::rtw_core::clock::Clock;
+use crate::rtw_core::activity::{Activity, ActivityOrder, OngoingActivity};
+use crate::rtw_core::clock::{Clock, Time};
 use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::durationw::{DurationFormat, DurationPrecision, DurationW, RoundMethod};
+use crate::rtw_core::error::RtwError;
 use crate::rtw_core::service::ActivityService;
 use crate::rtw_core::storage::Storage;
 use crate::rtw_core::ActivityId;
-use crate::rtw_core::{Description, Tags};
+use crate::rtw_core::{Description, Tag, Tags};
 use crate::service::Service;
+use crate::tags_cache::{load_tags_cache, save_tags_cache, TagsCache, TAGS_CACHE_FILE_NAME};
+use crate::text::fit_tags;
 use crate::timeline::render_days;
+use crate::undo::{UndoEntry, UndoJournal, UNDO_FILE_NAME};
+use chrono::{DateTime, Duration, Local};
 use clap::ArgMatches;
+use itertools::Itertools;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
 
 type ActivityWithId = (ActivityId, Activity);
 
+// print a confirmation (not data), nothing when quiet
+macro_rules! info {
+    ($config:expr, $($arg:tt)*) => {
+        if !$config.is_quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+// log to stderr when verbose
+macro_rules! verbose {
+    ($config:expr, $($arg:tt)*) => {
+        if $config.is_verbose() {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
 /// Describe the action to be made
 ///
 /// see `run`
 pub enum RTWAction {
     Cancel(Option<ActivityId>),
-    Start(DateTimeW, Tags, Option<Description>),
-    Track((DateTimeW, DateTimeW), Tags, Option<Description>),
-    Stop(DateTimeW, Option<ActivityId>),
-    Summary((DateTimeW, DateTimeW), bool, bool),
+    // start time, tags, description, parallel, allow future, restart
+    Start(DateTimeW, Tags, Option<Description>, bool, bool, bool),
+    // like Start, start time is the stop time of the last finished activity,
+    // add default tags (once the start time is known)
+    StartFromLast(Tags, Option<Description>, bool, bool, bool, bool),
+    // range, tags, description, allow overlap, allow future, if not exists
+    Track(
+        (DateTimeW, DateTimeW),
+        Tags,
+        Option<Description>,
+        bool,
+        bool,
+        bool,
+    ),
+    // stop time, stopped id, tags of the stopped activity, coalesce, description
+    Stop(
+        DateTimeW,
+        Option<ActivityId>,
+        Tags,
+        bool,
+        Option<Description>,
+    ),
+    StopAll(DateTimeW),
+    Summary(
+        (DateTimeW, DateTimeW),
+        bool,
+        bool,
+        Option<(u32, RoundMethod)>,
+        OutputFormat,
+        (Tags, bool),
+        // longer than, shorter than
+        (Option<Duration>, Option<Duration>),
+        // sort key, reverse
+        (ActivityOrder, bool),
+        // max line width, tags are truncated to fit
+        Option<usize>,
+        // display ongoing activities until now
+        bool,
+        // totals per value of this key instead of per tag
+        Option<Tag>,
+    ),
+    WeekGrid((DateTimeW, DateTimeW), (Tags, bool)),
+    // range, (tags, any), (longer than, shorter than), (sort key, reverse)
+    TagBreakdown(
+        (DateTimeW, DateTimeW),
+        (Tags, bool),
+        (Option<Duration>, Option<Duration>),
+        (ActivityOrder, bool),
+    ),
+    // range, (tags, any), (longer than, shorter than), rounding, display ongoing activities, human
+    SummaryTotal(
+        (DateTimeW, DateTimeW),
+        (Tags, bool),
+        (Option<Duration>, Option<Duration>),
+        Option<(u32, RoundMethod)>,
+        bool,
+        bool,
+    ),
+    Tags(Option<(DateTimeW, DateTimeW)>),
+    TagNames,
+    Total(Tag, Option<(DateTimeW, DateTimeW)>),
+    GoalAdd(Tag, Duration),
+    GoalRemove(Tag),
+    GoalStatus((DateTimeW, DateTimeW)),
+    // range, period, clip activities to range
+    Report(Option<(DateTimeW, DateTimeW)>, Period, bool),
+    Gaps((DateTimeW, DateTimeW), Duration),
+    Overlaps((DateTimeW, DateTimeW)),
+    Stats(Option<(DateTimeW, DateTimeW)>),
+    Count(Option<(DateTimeW, DateTimeW)>),
+    // number of activities
+    Last(usize),
     DumpICal((DateTimeW, DateTimeW)),
-    Continue,
+    Export(ExportFormat, Option<(DateTimeW, DateTimeW)>),
+    Import(PathBuf, ImportFormat),
+    Continue(Option<ActivityId>, DateTimeW, bool),
     Delete(ActivityId),
+    // tags (all required), range, skip confirmation
+    DeleteMatching(Tags, Option<(DateTimeW, DateTimeW)>, bool),
+    Annotate(ActivityId, Description),
+    Modify(
+        ActivityId,
+        Option<DateTimeW>,
+        Option<DateTimeW>,
+        Option<Tags>,
+    ),
+    // delta, allow overlap, allow future
+    Move(ActivityId, Duration, bool, bool),
+    Split(ActivityId, DateTimeW, Option<Tags>),
+    Merge(ActivityId, ActivityId, bool),
+    Rename(Tag, Tag, bool),
+    // range, tags filter (all required), tags to add, tags to remove, dry run
+    Retag(Option<(DateTimeW, DateTimeW)>, Tags, Tags, Tags, bool),
     DisplayCurrent,
+    Current(OutputFormat),
+    CurrentTags,
     Timeline((DateTimeW, DateTimeW)),
+    DayChart((DateTimeW, DateTimeW)),
     Completion(clap::Shell),
+    Edit,
+    Undo,
+    // fix
+    Doctor(bool),
+}
+
+impl RTWAction {
+    // read-only actions do not need the storage lock
+    // (`tags` without range and `tag-names` are not: they may rebuild the tags cache)
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            RTWAction::Summary(..)
+                | RTWAction::WeekGrid(..)
+                | RTWAction::TagBreakdown(..)
+                | RTWAction::SummaryTotal(..)
+                | RTWAction::Tags(Some(_))
+                | RTWAction::Total(..)
+                | RTWAction::GoalStatus(_)
+                | RTWAction::Report(..)
+                | RTWAction::Gaps(..)
+                | RTWAction::Overlaps(_)
+                | RTWAction::Stats(_)
+                | RTWAction::Count(_)
+                | RTWAction::Last(_)
+                | RTWAction::DumpICal(_)
+                | RTWAction::Export(..)
+                | RTWAction::DisplayCurrent
+                | RTWAction::Current(_)
+                | RTWAction::CurrentTags
+                | RTWAction::Timeline(_)
+                | RTWAction::DayChart(_)
+                | RTWAction::Completion(_)
+                | RTWAction::Doctor(false)
+        )
+    }
 }
 
 pub enum RTWMutation {
-    Start(OngoingActivity),
-    Track(Activity),
-    Stop(DateTimeW, ActivityId),
+    Start(OngoingActivity, bool),
+    Track(Activity, bool),
+    Import(Vec<Activity>, Option<OngoingActivity>),
+    // stop time, stopped id, description
+    Stop(DateTimeW, ActivityId, Option<Description>),
+    // stopped id (ongoing), previous id (replaced by merged activity), merged activity
+    StopCoalesce(ActivityId, ActivityId, Activity),
+    StopAll(DateTimeW),
     Delete(ActivityId),
+    DeleteMatching(Vec<ActivityId>),
+    Modify(ActivityId, Activity),
+    Split(ActivityId, Activity, Activity),
+    // older id (replaced by merged activity), newer id (deleted), merged activity
+    Merge(ActivityId, ActivityId, Activity),
+    Rename(Tag, Tag),
+    Retag(Vec<(ActivityId, Activity)>),
     Cancel(ActivityId),
+    Edit(EditedActivities),
+    Goals(Goals),
+    // fixed findings, count of findings left
+    Repair(Vec<Finding>, usize),
+    Undo,
     Pure,
 }
 
+impl RTWMutation {
+    // command name recorded in the undo journal
+    fn command(&self) -> &'static str {
+        match self {
+            RTWMutation::Start(_, _) => "start",
+            RTWMutation::Track(_, _) => "track",
+            RTWMutation::Import(_, _) => "import",
+            RTWMutation::Edit(_) => "edit",
+            RTWMutation::Stop(_, _, _)
+            | RTWMutation::StopCoalesce(_, _, _)
+            | RTWMutation::StopAll(_) => "stop",
+            RTWMutation::Delete(_) | RTWMutation::DeleteMatching(_) => "delete",
+            RTWMutation::Modify(_, _) => "modify",
+            RTWMutation::Split(_, _, _) => "split",
+            RTWMutation::Merge(_, _, _) => "merge",
+            RTWMutation::Rename(_, _) => "rename",
+            RTWMutation::Retag(_) => "retag",
+            RTWMutation::Cancel(_) => "cancel",
+            RTWMutation::Goals(_) => "goal",
+            RTWMutation::Repair(_, _) => "doctor",
+            RTWMutation::Undo => "undo",
+            RTWMutation::Pure => "",
+        }
+    }
+}
+
+// run hook if a hooks directory is configured, print a warning on failure unless configured to abort
+fn run_configured_hook<T: Serialize>(
+    config: &RTWConfig,
+    event: &str,
+    activity: &T,
+) -> anyhow::Result<()> {
+    match &config.hooks_dir {
+        None => Ok(()),
+        Some(hooks_dir) => match run_hook(hooks_dir, event, activity) {
+            Err(e) if !config.hooks_abort_on_failure => {
+                eprintln!("Warning: {}", e);
+                Ok(())
+            }
+            result => result,
+        },
+    }
+}
+
+fn undo_journal(config: &RTWConfig) -> UndoJournal {
+    let path = config.data_file_path(UNDO_FILE_NAME);
+    verbose!(config, "Undo journal {}", path.display());
+    UndoJournal::new(path)
+}
+
+fn tags_cache_path(config: &RTWConfig) -> PathBuf {
+    config.data_file_path(TAGS_CACHE_FILE_NAME)
+}
+
+fn load_fresh_tags_cache(config: &RTWConfig) -> Option<TagsCache> {
+    load_tags_cache(&tags_cache_path(config), &config.storage_file_paths())
+}
+
+fn ongoing_tags<S: Storage>(service: &Service<S>) -> anyhow::Result<Vec<Tag>> {
+    Ok(service
+        .get_ongoing_activities()?
+        .into_iter()
+        .flat_map(|(_id, ongoing)| ongoing.tags)
+        .collect())
+}
+
+// tags of finished and ongoing activities, the cache is updated on a best-effort basis
+fn rebuild_tags_cache<S: Storage>(
+    service: &Service<S>,
+    config: &RTWConfig,
+) -> anyhow::Result<TagsCache> {
+    let mut tags = TagsCache::new();
+    service.for_each_activity(|_| true, |(_id, a)| tags.add_finished(&a))?;
+    tags.set_ongoing(ongoing_tags(service)?);
+    let saved = save_tags_cache(
+        &tags_cache_path(config),
+        &tags,
+        &config.storage_file_paths(),
+    );
+    if let Err(e) = saved {
+        verbose!(config, "Could not update tags cache: {}", e);
+    }
+    Ok(tags)
+}
+
+// best-effort: a cache that was stale before the command is rebuilt by the next reader
+fn update_tags_cache<S: Storage>(
+    cached: Option<TagsCache>,
+    finished: &[&Activity],
+    service: &Service<S>,
+    config: &RTWConfig,
+) {
+    if let Some(mut cached) = cached {
+        let updated = ongoing_tags(service).and_then(|ongoing| {
+            for activity in finished {
+                cached.add_finished(activity);
+            }
+            cached.set_ongoing(ongoing);
+            save_tags_cache(
+                &tags_cache_path(config),
+                &cached,
+                &config.storage_file_paths(),
+            )
+        });
+        if let Err(e) = updated {
+            verbose!(config, "Could not update tags cache: {}", e);
+        }
+    }
+}
+
+// tag, count and total aligned in columns
+fn print_tag_stats(stats: &[TagStats], config: &RTWConfig) {
+    let longest_tag = stats
+        .iter()
+        .map(|(tag, _count, _total)| tag.len())
+        .max()
+        .unwrap_or_default();
+    let longest_count = stats
+        .iter()
+        .map(|(_tag, count, _total)| count.to_string().len())
+        .max()
+        .unwrap_or_default();
+    for (tag, count, total) in stats {
+        println!(
+            "{:tag_width$} {:>count_width$} {}",
+            tag,
+            count,
+            config.format_duration(*total),
+            tag_width = longest_tag,
+            count_width = longest_count
+        );
+    }
+}
+
+fn print_stats(stats: &Stats, config: &RTWConfig) {
+    let (busiest_day, busiest_total) = stats.busiest_day;
+    println!("Count    {}", stats.count);
+    println!("Total    {}", config.format_duration(stats.total));
+    println!("Shortest {}", config.format_duration(stats.shortest));
+    println!("Longest  {}", config.format_duration(stats.longest));
+    println!("Mean     {}", config.format_duration(stats.mean));
+    println!(
+        "Busiest  {} {}",
+        busiest_day.format("%Y-%m-%d"),
+        config.format_duration(busiest_total)
+    );
+}
+
+fn goals_path(config: &RTWConfig) -> PathBuf {
+    config.data_file_path(GOALS_FILE_NAME)
+}
+
 enum OptionalOrAmbiguousOrNotFound {
     Optional(Option<(ActivityId, OngoingActivity)>),
     Ambiguous,
     NotFound(ActivityId),
+    // no ongoing activity with these tags
+    NoTagMatch(Tags),
 }
 
+// ongoing activity with id, or the only ongoing activity with `tags` (any ongoing activity if empty)
 fn get_ongoing_activity<S: Storage>(
     id_maybe: Option<ActivityId>,
+    tags: &[Tag],
     service: &Service<S>,
 ) -> anyhow::Result<OptionalOrAmbiguousOrNotFound> {
     match id_maybe {
-        None => match service.get_ongoing_activities()?.as_slice() {
-            [] => Ok(OptionalOrAmbiguousOrNotFound::Optional(None)),
-            [(cancelled_id, cancelled)] => Ok(OptionalOrAmbiguousOrNotFound::Optional(Some((
-                *cancelled_id,
-                cancelled.clone(),
-            )))),
-            _ => Ok(OptionalOrAmbiguousOrNotFound::Ambiguous),
-        },
+        None => {
+            let matching: Vec<(ActivityId, OngoingActivity)> = service
+                .get_ongoing_activities()?
+                .into_iter()
+                .filter(|(_id, a)| a.has_tags(tags))
+                .collect();
+            match matching.as_slice() {
+                [] if tags.is_empty() => Ok(OptionalOrAmbiguousOrNotFound::Optional(None)),
+                [] => Ok(OptionalOrAmbiguousOrNotFound::NoTagMatch(tags.to_vec())),
+                [(cancelled_id, cancelled)] => Ok(OptionalOrAmbiguousOrNotFound::Optional(Some((
+                    *cancelled_id,
+                    cancelled.clone(),
+                )))),
+                _ => Ok(OptionalOrAmbiguousOrNotFound::Ambiguous),
+            }
+        }
         Some(cancelled_id) => match service.get_ongoing_activity(cancelled_id)? {
             None => Ok(OptionalOrAmbiguousOrNotFound::NotFound(cancelled_id)),
             Some(cancelled) => Ok(OptionalOrAmbiguousOrNotFound::Optional(Some((
@@ -71,56 +425,478 @@ fn get_ongoing_activity<S: Storage>(
     }
 }
 
+// true if activity starts within range or if there is no range
+fn in_optional_range(activity: &Activity, range_maybe: Option<(DateTimeW, DateTimeW)>) -> bool {
+    match range_maybe {
+        None => true,
+        Some((range_start, range_end)) => {
+            range_start <= activity.get_start_time() && activity.get_start_time() <= range_end
+        }
+    }
+}
+
+// only activities within the range are read when there is one
+fn filter_in_optional_range<S: Storage, P>(
+    service: &Service<S>,
+    range_maybe: Option<(DateTimeW, DateTimeW)>,
+    p: P,
+) -> anyhow::Result<Vec<ActivityWithId>>
+where
+    P: Fn(&(ActivityId, Activity)) -> bool,
+{
+    let activities = match range_maybe {
+        None => service.filter_activities(|activity| p(activity))?,
+        Some(range) => service.filter_activities_within(range, |activity| {
+            in_optional_range(&activity.1, range_maybe) && p(activity)
+        })?,
+    };
+    Ok(activities)
+}
+
+// narrowest tags column when truncating summary lines e.g. `foo …`
+const MIN_TITLE_WIDTH: usize = 5;
+
+// appended to summary lines of ongoing activities
+const ONGOING_MARK: &str = " (ongoing)";
+
+// tolerate small differences between the time clue and the clock e.g. `rtw start 09:00` at 08:59:30
+const FUTURE_TOLERANCE_SECONDS: i64 = 60;
+
+fn check_not_in_future(time: DateTimeW, clock: &dyn Clock) -> anyhow::Result<()> {
+    let ahead: Duration = (time - clock.get_time()).into();
+    if ahead > Duration::seconds(FUTURE_TOLERANCE_SECONDS) {
+        Err(anyhow::anyhow!(
+            "{} is in the future, use --allow-future to record it anyway",
+            time
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+// ask for confirmation on a TTY, refuse otherwise
+fn confirm(prompt: &str) -> anyhow::Result<bool> {
+    if !atty::is(atty::Stream::Stdin) {
+        return Ok(false);
+    }
+    print!("{} [y/N] ", prompt);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+// list the most recent tag sets numbered from 1, read the chosen number (blank: none)
+fn pick_activity(
+    activities: &[ActivityWithId],
+    count: usize,
+) -> anyhow::Result<Option<ActivityWithId>> {
+    let candidates = recent_tag_sets(activities, count);
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    let width = candidates.len().to_string().len();
+    for (i, (_id, activity)) in candidates.iter().enumerate() {
+        println!("{:>width$} {}", i + 1, activity.get_title(), width = width);
+    }
+    print!("Continue which activity? [1-{}] ", candidates.len());
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(None);
+    }
+    match answer.parse::<usize>() {
+        Ok(choice) if (1..=candidates.len()).contains(&choice) => {
+            Ok(candidates.into_iter().nth(choice - 1))
+        }
+        _ => Err(anyhow::anyhow!(
+            "invalid choice '{}', expected a number between 1 and {}",
+            answer,
+            candidates.len()
+        )),
+    }
+}
+
+// duration as displayed by summary: whole seconds (or minutes with `--precision minute`),
+// rounded if requested, so that totals add up with the rows
+fn summary_duration(
+    activity: &Activity,
+    rounding: Option<(u32, RoundMethod)>,
+    precision: DurationPrecision,
+) -> Duration {
+    match (rounding, precision) {
+        (Some((minutes, method)), _) => activity.get_duration().round_to(minutes, method).into(),
+        (None, DurationPrecision::Minute) => activity
+            .get_duration()
+            .round_to(1, RoundMethod::Nearest)
+            .into(),
+        (None, DurationPrecision::Second) => {
+            let duration: Duration = activity.get_duration().into();
+            Duration::seconds(duration.num_seconds())
+        }
+    }
+}
+
+// ongoing activities started within the range as if stopped `now`, filtered like finished ones by summary
+fn ongoing_within<S: Storage>(
+    service: &Service<S>,
+    now: DateTimeW,
+    (range_start, range_end): (DateTimeW, DateTimeW),
+    (tags, any): (&[Tag], bool),
+    duration_filter: (Option<Duration>, Option<Duration>),
+) -> anyhow::Result<Vec<ActivityWithId>> {
+    Ok(service
+        .get_ongoing_activities()?
+        .into_iter()
+        .filter(|(_i, a)| range_start <= a.start_time && a.start_time <= range_end)
+        .filter_map(|(i, a)| a.into_activity(now).ok().map(|a| (i, a)))
+        .filter(|(_i, a)| a.has_tags(tags, any) && in_duration_bounds(a, duration_filter))
+        .collect())
+}
+
+// true if activity is strictly longer than `longer_than` and strictly shorter than `shorter_than`
+fn in_duration_bounds(
+    activity: &Activity,
+    (longer_than, shorter_than): (Option<Duration>, Option<Duration>),
+) -> bool {
+    let duration: Duration = activity.get_duration().into();
+    let longer = match longer_than {
+        None => true,
+        Some(min) => duration > min,
+    };
+    let shorter = match shorter_than {
+        None => true,
+        Some(max) => duration < max,
+    };
+    longer && shorter
+}
+
+// stable sort: activities with equal keys keep their (start time) order
+fn sort_activities(
+    activities: &mut [(ActivityId, Activity)],
+    (order, reverse): (ActivityOrder, bool),
+) {
+    activities.sort_by(|(_, a), (_, b)| {
+        let ordering = order.compare(a, b);
+        if reverse {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
 /// Translate CLI args to actions (side-effect free)
 ///
 /// It may fetch data from underlying activity storage but it should not write anything.
-pub fn run<Cl>(matches: &ArgMatches, clock: &Cl) -> anyhow::Result<RTWAction>
+pub fn run<Cl>(matches: &ArgMatches, clock: &Cl, config: &RTWConfig) -> anyhow::Result<RTWAction>
 where
     Cl: Clock,
 {
     match matches.subcommand() {
         ("start", Some(sub_m)) => {
-            let (start_time, tags, description) = cli_helper::parse_start_args(sub_m, clock)?;
+            let (start_time, tags, description, parallel, allow_future, restart) =
+                cli_helper::parse_start_args(sub_m, clock)?;
+            let default_tags = !sub_m.is_present("no-defaults");
+            if sub_m.is_present("from-last") {
+                if start_time != Time::Now {
+                    return Err(anyhow::anyhow!(
+                        "--from-last cannot be combined with a time clue"
+                    ));
+                }
+                return Ok(RTWAction::StartFromLast(
+                    tags,
+                    description,
+                    parallel,
+                    allow_future,
+                    restart,
+                    default_tags,
+                ));
+            }
             let abs_start_time = clock.date_time(start_time);
-            Ok(RTWAction::Start(abs_start_time, tags, description))
+            let tags = if default_tags {
+                config.with_default_tags(tags, abs_start_time)
+            } else {
+                tags
+            };
+            verbose!(
+                config,
+                "Resolved start time {}",
+                config.format_datetime(abs_start_time)
+            );
+            Ok(RTWAction::Start(
+                abs_start_time,
+                tags,
+                description,
+                parallel,
+                allow_future,
+                restart,
+            ))
         }
         ("stop", Some(sub_m)) => {
-            let (stop_time, stopped_id_maybe) = cli_helper::parse_stop_args(sub_m, clock)?;
+            let (stop_time, stopped_id_maybe, stop_all, tags, description) =
+                cli_helper::parse_stop_args(sub_m, clock)?;
             let abs_stop_time = clock.date_time(stop_time);
-            Ok(RTWAction::Stop(abs_stop_time, stopped_id_maybe))
+            verbose!(
+                config,
+                "Resolved stop time {}",
+                config.format_datetime(abs_stop_time)
+            );
+            if stop_all {
+                Ok(RTWAction::StopAll(abs_stop_time))
+            } else {
+                let coalesce = sub_m.is_present("coalesce") || config.coalesce;
+                Ok(RTWAction::Stop(
+                    abs_stop_time,
+                    stopped_id_maybe,
+                    tags,
+                    coalesce,
+                    description,
+                ))
+            }
         }
         ("summary", Some(sub_m)) => {
-            let ((range_start, range_end), display_id, display_description) =
-                cli_helper::parse_summary_args(sub_m, clock)?;
+            let ((range_start, range_end), display_id, display_description, tag_filter) =
+                cli_helper::parse_summary_args(sub_m, clock, config.week_start)?;
+            if sub_m.is_present("week-grid") {
+                return Ok(RTWAction::WeekGrid((range_start, range_end), tag_filter));
+            }
+            if sub_m.value_of("by") == Some("tag") {
+                return Ok(RTWAction::TagBreakdown(
+                    (range_start, range_end),
+                    tag_filter,
+                    cli_helper::parse_duration_filter_args(sub_m)?,
+                    cli_helper::parse_tag_sort_args(sub_m)?,
+                ));
+            }
+            let (round_minutes, round_method) = cli_helper::parse_round_args(sub_m)?;
+            let rounding = round_minutes
+                .or(config.round_minutes)
+                .map(|minutes| (minutes, round_method.unwrap_or(config.round_method)));
+            let duration_filter = cli_helper::parse_duration_filter_args(sub_m)?;
+            let with_current = cli_helper::parse_with_current_args(sub_m)
+                .unwrap_or_else(|| (range_start, range_end) == clock.today_range());
+            if sub_m.is_present("total-only") {
+                return Ok(RTWAction::SummaryTotal(
+                    (range_start, range_end),
+                    tag_filter,
+                    duration_filter,
+                    rounding,
+                    with_current,
+                    sub_m.is_present("human"),
+                ));
+            }
+            let format = cli_helper::parse_output_format_args(sub_m)?;
+            let sort = cli_helper::parse_sort_args(sub_m)?;
+            let max_width = cli_helper::parse_max_width_args(sub_m)?;
+            let group_by = sub_m.value_of("group-by").map(|key| key.trim().to_string());
+            // --format has a default value, clap conflicts would always fail
+            if group_by.is_some() && format != OutputFormat::Text {
+                return Err(anyhow::anyhow!(
+                    "--group-by cannot be combined with --format json or markdown"
+                ));
+            }
             Ok(RTWAction::Summary(
                 (range_start, range_end),
                 display_id,
                 display_description,
+                rounding,
+                format,
+                tag_filter,
+                duration_filter,
+                sort,
+                max_width,
+                with_current,
+                group_by,
             ))
         }
+        ("report", Some(sub_m)) => {
+            let (range_maybe, period) =
+                cli_helper::parse_report_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Report(
+                range_maybe,
+                period,
+                sub_m.is_present("clip"),
+            ))
+        }
+        ("tags", Some(sub_m)) if sub_m.is_present("names") => Ok(RTWAction::TagNames),
+        ("tags", Some(sub_m)) => {
+            let range_maybe =
+                cli_helper::parse_optional_range_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Tags(range_maybe))
+        }
+        ("total", Some(sub_m)) => {
+            let (tag, range_maybe) = cli_helper::parse_total_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Total(tag, range_maybe))
+        }
+        ("goal", Some(sub_m)) => match sub_m.subcommand() {
+            ("add", Some(add_m)) => {
+                let (tag, target) = cli_helper::parse_goal_add_args(add_m)?;
+                Ok(RTWAction::GoalAdd(tag, target))
+            }
+            ("remove", Some(remove_m)) => Ok(RTWAction::GoalRemove(
+                cli_helper::parse_goal_remove_args(remove_m),
+            )),
+            (_, status_m) => {
+                let range = cli_helper::parse_goal_status_args(status_m, clock, config.week_start)?;
+                Ok(RTWAction::GoalStatus(range))
+            }
+        },
+        ("stats", Some(sub_m)) => {
+            let range_maybe =
+                cli_helper::parse_optional_range_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Stats(range_maybe))
+        }
+        ("count", Some(sub_m)) => {
+            let range_maybe =
+                cli_helper::parse_optional_range_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Count(range_maybe))
+        }
+        ("last", Some(sub_m)) => {
+            let count = cli_helper::parse_last_args(sub_m)?;
+            Ok(RTWAction::Last(count))
+        }
+        ("overlaps", Some(sub_m)) => {
+            let range = cli_helper::parse_overlaps_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Overlaps(range))
+        }
+        ("gaps", Some(sub_m)) => {
+            let (range, min_duration) =
+                cli_helper::parse_gaps_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Gaps(range, min_duration))
+        }
         ("timeline", Some(sub_m)) => {
             let ((range_start, range_end), _display_id) =
-                cli_helper::parse_timeline_args(sub_m, clock)?;
+                cli_helper::parse_timeline_args(sub_m, clock, config.week_start)?;
             Ok(RTWAction::Timeline((range_start, range_end)))
         }
-        ("continue", Some(_sub_m)) => Ok(RTWAction::Continue),
+        ("continue", Some(sub_m)) => {
+            let (continued_id_maybe, start_time, pick) =
+                cli_helper::parse_continue_args(sub_m, clock)?;
+            let abs_start_time = clock.date_time(start_time);
+            verbose!(
+                config,
+                "Resolved start time {}",
+                config.format_datetime(abs_start_time)
+            );
+            Ok(RTWAction::Continue(
+                continued_id_maybe,
+                abs_start_time,
+                pick,
+            ))
+        }
         ("delete", Some(sub_m)) => {
-            let id = cli_helper::parse_delete_args(sub_m)?;
-            Ok(RTWAction::Delete(id))
+            if sub_m.is_present("id") {
+                let id = cli_helper::parse_delete_args(sub_m)?;
+                Ok(RTWAction::Delete(id))
+            } else {
+                let (tags, range_maybe, yes) =
+                    cli_helper::parse_delete_filter_args(sub_m, clock, config.week_start)?;
+                Ok(RTWAction::DeleteMatching(tags, range_maybe, yes))
+            }
+        }
+        ("annotate", Some(sub_m)) => {
+            let (id, description) = cli_helper::parse_annotate_args(sub_m)?;
+            Ok(RTWAction::Annotate(id, description))
+        }
+        ("modify", Some(sub_m)) => {
+            let (id, start_time_maybe, stop_time_maybe, tags_maybe) =
+                cli_helper::parse_modify_args(sub_m, clock)?;
+            let start_time_maybe = start_time_maybe.map(|t| clock.date_time(t));
+            let stop_time_maybe = stop_time_maybe.map(|t| clock.date_time(t));
+            if let Some(start_time) = start_time_maybe {
+                verbose!(
+                    config,
+                    "Resolved start time {}",
+                    config.format_datetime(start_time)
+                );
+            }
+            if let Some(stop_time) = stop_time_maybe {
+                verbose!(
+                    config,
+                    "Resolved stop time {}",
+                    config.format_datetime(stop_time)
+                );
+            }
+            Ok(RTWAction::Modify(
+                id,
+                start_time_maybe,
+                stop_time_maybe,
+                tags_maybe,
+            ))
+        }
+        ("move", Some(sub_m)) => {
+            let (id, delta, allow_overlap, allow_future) = cli_helper::parse_move_args(sub_m)?;
+            Ok(RTWAction::Move(id, delta, allow_overlap, allow_future))
+        }
+        ("split", Some(sub_m)) => {
+            let (id, time, tags_maybe) = cli_helper::parse_split_args(sub_m, clock)?;
+            let split_time = clock.date_time(time);
+            verbose!(
+                config,
+                "Resolved split time {}",
+                config.format_datetime(split_time)
+            );
+            Ok(RTWAction::Split(id, split_time, tags_maybe))
+        }
+        ("merge", Some(sub_m)) => {
+            let (first, second, force) = cli_helper::parse_merge_args(sub_m)?;
+            Ok(RTWAction::Merge(first, second, force))
+        }
+        ("rename", Some(sub_m)) => {
+            let (old, new, dry_run) = cli_helper::parse_rename_args(sub_m)?;
+            Ok(RTWAction::Rename(old, new, dry_run))
+        }
+        ("retag", Some(sub_m)) => {
+            let (range_maybe, tags, add, remove, dry_run) =
+                cli_helper::parse_retag_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Retag(range_maybe, tags, add, remove, dry_run))
         }
         ("track", Some(sub_m)) => {
-            let (start_time, stop_time, tags, description) =
-                cli_helper::parse_track_args(sub_m, clock)?;
+            let (
+                start_time,
+                stop_time,
+                tags,
+                description,
+                allow_overlap,
+                allow_future,
+                if_not_exists,
+            ) = cli_helper::parse_track_args(sub_m, clock)?;
             let start_time = clock.date_time(start_time);
             let stop_time = clock.date_time(stop_time);
-            Ok(RTWAction::Track((start_time, stop_time), tags, description))
+            let tags = if sub_m.is_present("no-defaults") {
+                tags
+            } else {
+                config.with_default_tags(tags, start_time)
+            };
+            verbose!(
+                config,
+                "Resolved start time {}",
+                config.format_datetime(start_time)
+            );
+            verbose!(
+                config,
+                "Resolved stop time {}",
+                config.format_datetime(stop_time)
+            );
+            Ok(RTWAction::Track(
+                (start_time, stop_time),
+                tags,
+                description,
+                allow_overlap,
+                allow_future,
+                if_not_exists,
+            ))
         }
-        ("day", Some(_sub_m)) => {
-            let (range_start, range_end) = clock.today_range();
-            Ok(RTWAction::Timeline((range_start, range_end)))
+        ("day", Some(sub_m)) => {
+            let (range_start, range_end) =
+                cli_helper::parse_day_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::DayChart((range_start, range_end)))
         }
         ("week", Some(_sub_m)) => {
-            let (range_start, range_end) = clock.this_week_range();
+            let (range_start, range_end) = clock.this_week_range(config.week_start);
             Ok(RTWAction::Timeline((range_start, range_end)))
         }
         ("cancel", Some(sub_m)) => {
@@ -128,14 +904,33 @@ where
             Ok(RTWAction::Cancel(cancelled_id_maybe))
         }
         ("dump", Some(sub_m)) => {
-            let ((range_start, range_end), _display_id, _description) =
-                cli_helper::parse_summary_args(sub_m, clock)?;
+            let ((range_start, range_end), _display_id, _description, _tag_filter) =
+                cli_helper::parse_summary_args(sub_m, clock, config.week_start)?;
             Ok(RTWAction::DumpICal((range_start, range_end)))
         }
+        ("export", Some(sub_m)) => {
+            let (format, range_maybe) =
+                cli_helper::parse_export_args(sub_m, clock, config.week_start)?;
+            Ok(RTWAction::Export(format, range_maybe))
+        }
+        ("import", Some(sub_m)) => {
+            let (path, format) = cli_helper::parse_import_args(sub_m)?;
+            Ok(RTWAction::Import(path, format))
+        }
+        ("current", Some(sub_m)) => {
+            if sub_m.is_present("tags-only") {
+                return Ok(RTWAction::CurrentTags);
+            }
+            let format = cli_helper::parse_output_format_args(sub_m)?;
+            Ok(RTWAction::Current(format))
+        }
         ("completion", Some(sub_m)) => {
             let shell = cli_helper::parse_completion_args(sub_m)?;
             Ok(RTWAction::Completion(shell))
         }
+        ("edit", Some(_sub_m)) => Ok(RTWAction::Edit),
+        ("undo", Some(_sub_m)) => Ok(RTWAction::Undo),
+        ("doctor", Some(sub_m)) => Ok(RTWAction::Doctor(sub_m.is_present("fix"))),
         // default case: display current activity
         _ => Ok(RTWAction::DisplayCurrent),
     }
@@ -153,72 +948,377 @@ where
     Cl: Clock,
 {
     match action {
-        RTWAction::Start(start_time, tags, description) => {
+        RTWAction::Start(start_time, tags, description, parallel, allow_future, restart) => {
+            if !allow_future {
+                check_not_in_future(start_time, clock)?;
+            }
             let started = OngoingActivity::new(start_time, tags, description);
-            println!("Tracking {}", started.get_title());
-            println!("Started  {}", started.get_start_time());
-            Ok(RTWMutation::Start(started))
+            if !restart {
+                let tolerance = Duration::seconds(config.duplicate_start_tolerance_seconds as i64);
+                let duplicate_maybe =
+                    service.find_duplicate_ongoing(&started, clock.get_time(), tolerance)?;
+                if let Some((_id, ongoing)) = duplicate_maybe {
+                    info!(config, "Already tracking {}.", ongoing.get_title());
+                    return Ok(RTWMutation::Pure);
+                }
+            }
+            info!(config, "Tracking {}", started.get_title());
+            info!(
+                config,
+                "Started  {}",
+                config.format_datetime(started.get_start_time())
+            );
+            Ok(RTWMutation::Start(started, parallel))
+        }
+        RTWAction::StartFromLast(
+            tags,
+            description,
+            parallel,
+            allow_future,
+            restart,
+            default_tags,
+        ) => {
+            let start_time = match service.get_last_stop_time()? {
+                Some(last_stop_time) => last_stop_time,
+                None => {
+                    eprintln!("Warning: no finished activity, starting now");
+                    clock.get_time()
+                }
+            };
+            verbose!(
+                config,
+                "Resolved start time {}",
+                config.format_datetime(start_time)
+            );
+            let tags = if default_tags {
+                config.with_default_tags(tags, start_time)
+            } else {
+                tags
+            };
+            let start = RTWAction::Start(
+                start_time,
+                tags,
+                description,
+                parallel,
+                allow_future,
+                restart,
+            );
+            dry_run_action(start, service, clock, config)
         }
-        RTWAction::Track((start_time, stop_time), tags, description) => {
+        RTWAction::Track(
+            (start_time, stop_time),
+            tags,
+            description,
+            allow_overlap,
+            allow_future,
+            if_not_exists,
+        ) => {
+            if !allow_future {
+                check_not_in_future(stop_time, clock)?;
+            }
             let tracked =
                 OngoingActivity::new(start_time, tags, description).into_activity(stop_time)?;
-            println!("Recorded {}", tracked.get_title());
-            println!("Started {:>20}", tracked.get_start_time());
-            println!("Ended   {:>20}", tracked.get_stop_time());
-            println!("Total   {:>20}", tracked.get_duration());
-            Ok(RTWMutation::Track(tracked))
-        }
-        RTWAction::Stop(stop_time, activity_id) => {
-            match get_ongoing_activity(activity_id, &service)? {
+            if if_not_exists {
+                if let Some(existing_id) = service.find_duplicate_finished(&tracked)? {
+                    info!(
+                        config,
+                        "Skipped {}, already tracked with id {}",
+                        tracked.get_title(),
+                        existing_id
+                    );
+                    return Ok(RTWMutation::Pure);
+                }
+            }
+            info!(config, "Recorded {}", tracked.get_title());
+            info!(
+                config,
+                "Started {}",
+                config.format_datetime(tracked.get_start_time())
+            );
+            info!(
+                config,
+                "Ended   {}",
+                config.format_datetime(tracked.get_stop_time())
+            );
+            info!(
+                config,
+                "Total   {}",
+                config.format_duration(tracked.get_duration())
+            );
+            Ok(RTWMutation::Track(tracked, allow_overlap))
+        }
+        RTWAction::Stop(stop_time, activity_id, tags, coalesce, description) => {
+            match get_ongoing_activity(activity_id, &tags, service)? {
                 Optional(None) => {
-                    println!("There is no active time tracking.");
+                    info!(config, "There is no active time tracking.");
                     Ok(RTWMutation::Pure)
                 }
                 Optional(Some((stopped_id, stopped))) => {
-                    println!("Recorded {}", stopped.get_title());
-                    println!("Started {:>20}", stopped.get_start_time());
-                    println!("Ended   {:>20}", stop_time);
-                    println!("Total   {:>20}", stop_time - stopped.get_start_time());
-                    Ok(RTWMutation::Stop(stop_time, stopped_id))
-                }
-                OptionalOrAmbiguousOrNotFound::Ambiguous => {
-                    println!("Multiple ongoing activities, please provide an id.");
-                    Ok(RTWMutation::Pure)
+                    // fails before anything is printed, the activity stays ongoing
+                    let stopped = stopped.into_activity(stop_time)?;
+                    let stopped = match &description {
+                        None => stopped,
+                        Some(_) => stopped.annotate(description.clone()),
+                    };
+                    info!(config, "Recorded {}", stopped.get_title());
+                    info!(
+                        config,
+                        "Started {}",
+                        config.format_datetime(stopped.get_start_time())
+                    );
+                    info!(
+                        config,
+                        "Ended   {}",
+                        config.format_datetime(stopped.get_stop_time())
+                    );
+                    info!(
+                        config,
+                        "Total   {}",
+                        config.format_duration(stopped.get_duration())
+                    );
+                    if let Some(description) = &description {
+                        info!(config, "{}", description);
+                    }
+                    if coalesce {
+                        let tolerance = Duration::seconds(config.coalesce_tolerance_seconds as i64);
+                        if let Some((previous_id, previous)) =
+                            service.find_coalescable(&stopped, tolerance)?
+                        {
+                            // the gap is absorbed, unless it would overlap another activity
+                            let merged = previous.merge(&stopped, true)?;
+                            // the previous description comes first, unless one is given
+                            let merged = match &description {
+                                None => merged,
+                                Some(_) => merged.annotate(description.clone()),
+                            };
+                            let overlapping = service.filter_activities_within(
+                                (merged.get_start_time(), merged.get_stop_time()),
+                                |(i, a)| *i != previous_id && a.overlaps(&merged),
+                            )?;
+                            if !config.deny_overlapping || overlapping.is_empty() {
+                                info!(config, "Coalesced with {}", previous_id);
+                                info!(
+                                    config,
+                                    "Total   {}",
+                                    config.format_duration(merged.get_duration())
+                                );
+                                return Ok(RTWMutation::StopCoalesce(
+                                    stopped_id,
+                                    previous_id,
+                                    merged,
+                                ));
+                            }
+                        }
+                    }
+                    Ok(RTWMutation::Stop(stop_time, stopped_id, description))
                 }
+                OptionalOrAmbiguousOrNotFound::Ambiguous => Err(anyhow::anyhow!(
+                    "multiple ongoing activities, please provide an id or tags"
+                )),
                 OptionalOrAmbiguousOrNotFound::NotFound(stopped_id) => {
-                    println!("No ongoing activity with id {}.", stopped_id);
+                    info!(config, "No ongoing activity with id {}.", stopped_id);
+                    Ok(RTWMutation::Pure)
+                }
+                OptionalOrAmbiguousOrNotFound::NoTagMatch(tags) => {
+                    info!(config, "No ongoing activity with tag {}.", tags.join(" "));
                     Ok(RTWMutation::Pure)
                 }
             }
         }
-        RTWAction::Summary((range_start, range_end), display_id, display_description) => {
-            let activities = service.get_finished_activities()?;
-            let activities: Vec<(ActivityId, Activity)> = activities
+        RTWAction::StopAll(stop_time) => {
+            let ongoing_activities = service.get_ongoing_activities()?;
+            if ongoing_activities.len() <= 1 {
+                dry_run_action(
+                    RTWAction::Stop(stop_time, None, vec![], false, None),
+                    service,
+                    clock,
+                    config,
+                )
+            } else {
+                for (_id, stopped) in ongoing_activities.iter().rev() {
+                    info!(
+                        config,
+                        "Recorded {} {} {} {}",
+                        stopped.get_title(),
+                        config.format_datetime(stopped.get_start_time()),
+                        config.format_datetime(stop_time),
+                        config.format_duration(stop_time - stopped.get_start_time())
+                    );
+                }
+                Ok(RTWMutation::StopAll(stop_time))
+            }
+        }
+        RTWAction::Summary(
+            (range_start, range_end),
+            display_id,
+            display_description,
+            rounding,
+            format @ (OutputFormat::Json | OutputFormat::Markdown),
+            (tags, any),
+            duration_filter,
+            sort,
+            _max_width,
+            with_current,
+            _group_by,
+        ) => {
+            let mut activities =
+                service.filter_activities_within((range_start, range_end), |(_i, a)| {
+                    range_start <= a.get_start_time()
+                        && a.get_start_time() <= range_end
+                        && a.has_tags(&tags, any)
+                        && in_duration_bounds(a, duration_filter)
+                })?;
+            sort_activities(&mut activities, sort);
+            // ongoing activities are exported last, as if stopped now, without id
+            let ongoing: Vec<ActivityWithId> = if with_current {
+                ongoing_within(
+                    service,
+                    clock.get_time(),
+                    (range_start, range_end),
+                    (&tags, any),
+                    duration_filter,
+                )?
+            } else {
+                vec![]
+            };
+            let exported: Vec<ExportedActivity> = activities
                 .iter()
-                .filter(|(_i, a)| {
-                    range_start <= a.get_start_time() && a.get_start_time() <= range_end
+                .map(|(id, finished)| (ExportedActivity::new(*id, finished), finished))
+                .chain(
+                    ongoing
+                        .iter()
+                        .map(|(id, a)| (ExportedActivity::new(*id, a).ongoing(), a)),
+                )
+                .map(|(exported, finished)| {
+                    let exported = match rounding {
+                        None => exported,
+                        Some((minutes, method)) => exported
+                            .duration(finished.get_duration().round_to(minutes, method).into()),
+                    };
+                    let exported = if display_id {
+                        exported
+                    } else {
+                        exported.without_id()
+                    };
+                    if display_description {
+                        exported
+                    } else {
+                        exported.without_description()
+                    }
                 })
-                .cloned()
                 .collect();
+            if format == OutputFormat::Markdown {
+                println!("{}", export_summary_to_markdown(&exported, config));
+            } else {
+                println!("{}", export_summary_to_json(exported)?);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Summary(
+            (range_start, range_end),
+            display_id,
+            display_description,
+            rounding,
+            OutputFormat::Text,
+            (tags, any),
+            duration_filter,
+            sort,
+            max_width,
+            with_current,
+            group_by,
+        ) => {
+            let mut activities =
+                service.filter_activities_within((range_start, range_end), |(_i, a)| {
+                    range_start <= a.get_start_time()
+                        && a.get_start_time() <= range_end
+                        && a.has_tags(&tags, any)
+                        && in_duration_bounds(a, duration_filter)
+                })?;
+            sort_activities(&mut activities, sort);
+            // ongoing activities are displayed last, as if stopped now
+            let ongoing: Vec<ActivityWithId> = if with_current {
+                ongoing_within(
+                    service,
+                    clock.get_time(),
+                    (range_start, range_end),
+                    (&tags, any),
+                    duration_filter,
+                )?
+            } else {
+                vec![]
+            };
             let longest_title = activities
                 .iter()
+                .chain(ongoing.iter())
                 .map(|(_id, a)| a.get_title().len())
                 .max()
                 .unwrap_or_default();
-            if activities.is_empty() {
+            // ids are right aligned in a column as wide as the widest id
+            let id_width = activities
+                .iter()
+                .map(|(id, _a)| id.to_string().len())
+                .max()
+                .unwrap_or_default();
+            if activities.is_empty() && ongoing.is_empty() {
                 println!("No filtered data found.");
             } else {
-                for (id, finished) in activities {
+                let displayed_duration = |activity: &Activity| {
+                    summary_duration(activity, rounding, config.duration_precision)
+                };
+                let title_width = match max_width {
+                    None => longest_title,
+                    Some(max_width) => {
+                        // other columns keep their full width
+                        let id_column_width = if display_id { id_width + 1 } else { 0 };
+                        let other_width = activities
+                            .iter()
+                            .map(|(_id, a)| (a, ""))
+                            .chain(ongoing.iter().map(|(_id, a)| (a, ONGOING_MARK)))
+                            .map(|(a, mark)| {
+                                format!(
+                                    " {} {} {}{}",
+                                    config.format_datetime(a.get_start_time()),
+                                    config.format_datetime(a.get_stop_time()),
+                                    config.format_duration(displayed_duration(a)),
+                                    mark
+                                )
+                                .chars()
+                                .count()
+                            })
+                            .max()
+                            .unwrap_or_default()
+                            + id_column_width;
+                        longest_title
+                            .min(max_width.saturating_sub(other_width).max(MIN_TITLE_WIDTH))
+                    }
+                };
+                let mut total = Duration::seconds(0);
+                let rows = activities
+                    .iter()
+                    .map(|(id, a)| (id.to_string(), a, ""))
+                    .chain(
+                        ongoing
+                            .iter()
+                            .map(|(_id, a)| (String::new(), a, ONGOING_MARK)),
+                    );
+                for (id, finished, mark) in rows {
+                    let duration = displayed_duration(finished);
+                    total = total + duration;
                     let output = format!(
-                        "{:width$} {} {} {}",
-                        finished.get_title(),
-                        finished.get_start_time(),
-                        finished.get_stop_time(),
-                        finished.get_duration(),
-                        width = longest_title
+                        "{} {} {} {}{}",
+                        config.painter.tag(&format!(
+                            "{:width$}",
+                            fit_tags(&finished.get_tags(), title_width),
+                            width = title_width
+                        )),
+                        config.format_datetime(finished.get_start_time()),
+                        config.format_datetime(finished.get_stop_time()),
+                        config.format_duration(duration),
+                        mark
                     );
+                    // ongoing activities have no finished id
                     let output = if display_id {
-                        format!("{:>1} {}", id, output)
+                        format!("{:>id_width$} {}", id, output, id_width = id_width)
                     } else {
                         output
                     };
@@ -229,61 +1329,736 @@ where
                     };
                     println!("{}", output)
                 }
+                // tags and total use displayed durations so that they add up with the rows,
+                // activities displayed as nothing are skipped
+                let displayed: Vec<ActivityWithId> = activities
+                    .iter()
+                    .chain(ongoing.iter())
+                    .filter_map(|(id, a)| {
+                        let start: DateTime<Local> = a.get_start_time().into();
+                        a.modify(None, Some((start + displayed_duration(a)).into()), None)
+                            .ok()
+                            .map(|a| (*id, a))
+                    })
+                    .collect();
+                // an activity counts toward each of its tags (or values of the grouping key),
+                // total does not double count
+                let tag_durations = match &group_by {
+                    None => tag_durations(displayed.as_slice()),
+                    Some(key) => key_durations(displayed.as_slice(), key),
+                };
+                // total is the sum of the rows, time shared by overlapping activities
+                // is counted once by the wall clock line (displayed only when they differ)
+                let wall_clock: Duration = tracked_duration(displayed.as_slice()).into();
+                let overlapping = wall_clock != total;
+                let longest_tag = tag_durations
+                    .keys()
+                    .map(|t| t.len())
+                    .chain(std::iter::once("Total".len()))
+                    .chain(Some("Wall clock".len()).filter(|_| overlapping))
+                    .max()
+                    .unwrap_or_default();
+                println!();
+                for (tag, duration) in tag_durations
+                    .iter()
+                    .sorted_by(|(tag_a, _), (tag_b, _)| tag_a.cmp(tag_b))
+                {
+                    println!(
+                        "{} {}",
+                        config
+                            .painter
+                            .tag(&format!("{:width$}", tag, width = longest_tag)),
+                        config.format_duration(*duration)
+                    );
+                }
+                println!(
+                    "{}",
+                    config.painter.bold(&format!(
+                        "{:width$} {}",
+                        "Total",
+                        config.format_duration(total),
+                        width = longest_tag
+                    ))
+                );
+                if overlapping {
+                    println!(
+                        "{:width$} {}",
+                        "Wall clock",
+                        config.format_duration(wall_clock),
+                        width = longest_tag
+                    );
+                }
             }
             Ok(RTWMutation::Pure)
         }
-        RTWAction::Continue => {
-            let activities = service.get_finished_activities()?;
-            let last_activity_maybe = activities.last();
-            match last_activity_maybe {
+        RTWAction::Gaps((range_start, range_end), min) => {
+            // do not report the future as untracked
+            let range_end = range_end.min(clock.get_time());
+            let activities = service
+                .filter_activities_within((range_start, range_end), |(_i, a)| {
+                    a.get_start_time() < range_end && range_start < a.get_stop_time()
+                })?;
+            let gaps = gaps(activities.as_slice(), (range_start, range_end), min);
+            if gaps.is_empty() {
+                println!("No gaps found.");
+            } else {
+                for (gap_start, gap_end) in gaps {
+                    println!(
+                        "{} {} {}",
+                        config.format_datetime(gap_start),
+                        config.format_datetime(gap_end),
+                        config.format_duration(gap_end - gap_start)
+                    );
+                }
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Stats(range_maybe) => {
+            let activities = filter_in_optional_range(service, range_maybe, |_| true)?;
+            match stats(activities.as_slice()) {
+                None => println!("No data."),
+                Some(stats) => print_stats(&stats, config),
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Count(range_maybe) => {
+            let count = match range_maybe {
+                // without reading activities, depending on the storage
+                None => service.count_activities()?,
+                Some(_) => filter_in_optional_range(service, range_maybe, |_| true)?.len(),
+            };
+            println!("{}", count);
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Last(count) => {
+            let activities = service.get_last_activities(count)?;
+            if activities.is_empty() {
+                println!("No finished activities.");
+            }
+            let longest_title = activities
+                .iter()
+                .map(|(_id, a)| a.get_title().len())
+                .max()
+                .unwrap_or_default();
+            let id_width = activities
+                .iter()
+                .map(|(id, _a)| id.to_string().len())
+                .max()
+                .unwrap_or_default();
+            for (id, activity) in activities {
+                println!(
+                    "{:>id_width$} {} {} {} {}",
+                    id,
+                    config.painter.tag(&format!(
+                        "{:width$}",
+                        activity.get_title(),
+                        width = longest_title
+                    )),
+                    config.format_datetime(activity.get_start_time()),
+                    config.format_datetime(activity.get_stop_time()),
+                    config.format_duration(activity.get_duration()),
+                    id_width = id_width
+                );
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Overlaps((range_start, range_end)) => {
+            let activities = service
+                .filter_activities_within((range_start, range_end), |(_i, a)| {
+                    a.get_start_time() < range_end && range_start < a.get_stop_time()
+                })?;
+            let overlaps = overlaps(activities.as_slice());
+            if overlaps.is_empty() {
+                println!("No overlaps found.");
+            } else {
+                for ((id, activity), (other_id, other), (start, end)) in overlaps {
+                    println!(
+                        "{} {} overlaps {} {}",
+                        id,
+                        activity.get_title(),
+                        other_id,
+                        other.get_title()
+                    );
+                    println!(
+                        "  {} {} {}",
+                        config.format_datetime(start),
+                        config.format_datetime(end),
+                        config.format_duration(end - start)
+                    );
+                }
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::TagNames => {
+            let tags = match load_fresh_tags_cache(config) {
+                Some(tags) => tags,
+                None => rebuild_tags_cache(service, config)?,
+            };
+            for tag in tags.names() {
+                println!("{}", tag);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Tags(range_maybe) => {
+            let stats = match range_maybe {
+                None => match load_fresh_tags_cache(config) {
+                    Some(tags) => tags.stats(),
+                    None => rebuild_tags_cache(service, config)?.stats(),
+                },
+                Some(range) => tag_stats(&service.filter_activities_within(range, |_| true)?),
+            };
+            if stats.is_empty() {
+                println!("No tags found.");
+            } else {
+                print_tag_stats(&stats, config);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::TagBreakdown(
+            (range_start, range_end),
+            (tags, any),
+            duration_filter,
+            (order, reverse),
+        ) => {
+            let activities =
+                service.filter_activities_within((range_start, range_end), |(_i, a)| {
+                    range_start <= a.get_start_time()
+                        && a.get_start_time() <= range_end
+                        && a.has_tags(&tags, any)
+                        && in_duration_bounds(a, duration_filter)
+                })?;
+            // by total (descending) then tag
+            let mut stats = tag_stats(&activities);
+            if order == ActivityOrder::Tags {
+                stats.sort_by(|(tag_a, _, _), (tag_b, _, _)| tag_a.cmp(tag_b));
+            }
+            if reverse {
+                stats.reverse();
+            }
+            if stats.is_empty() {
+                println!("No filtered data found.");
+            } else {
+                print_tag_stats(&stats, config);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::SummaryTotal(
+            (range_start, range_end),
+            (tags, any),
+            duration_filter,
+            rounding,
+            with_current,
+            human,
+        ) => {
+            let mut total = Duration::seconds(0);
+            service.for_each_activity(
+                |(_i, a)| {
+                    range_start <= a.get_start_time()
+                        && a.get_start_time() <= range_end
+                        && a.has_tags(&tags, any)
+                        && in_duration_bounds(a, duration_filter)
+                },
+                |(_id, activity)| {
+                    total = total + summary_duration(&activity, rounding, config.duration_precision)
+                },
+            )?;
+            // ongoing activities count as if stopped now, like in the summary
+            if with_current {
+                let ongoing = ongoing_within(
+                    service,
+                    clock.get_time(),
+                    (range_start, range_end),
+                    (&tags, any),
+                    duration_filter,
+                )?;
+                for (_id, a) in ongoing {
+                    total = total + summary_duration(&a, rounding, config.duration_precision);
+                }
+            }
+            if human {
+                println!(
+                    "{}",
+                    DurationW::from(total)
+                        .display_with_precision(DurationFormat::Human, config.duration_precision)
+                );
+            } else {
+                println!("{}", total.num_seconds());
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Total(tag, range_maybe) => {
+            let tags = vec![tag.clone()];
+            let mut total = Duration::seconds(0);
+            let mut count = 0;
+            service.for_each_activity(
+                |(_i, a)| a.has_tags(&tags, false) && in_optional_range(a, range_maybe),
+                |(_id, activity)| {
+                    let duration: Duration = activity.get_duration().into();
+                    total = total + duration;
+                    count += 1;
+                },
+            )?;
+            println!("{}", config.format_duration(total));
+            if count == 0 {
+                info!(config, "No activity tagged with {}.", tag);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::GoalAdd(tag, target) => {
+            let mut goals = load_goals(&goals_path(config))?;
+            info!(config, "Goal {} {}", tag, config.format_duration(target));
+            goals.insert(tag, target);
+            Ok(RTWMutation::Goals(goals))
+        }
+        RTWAction::GoalRemove(tag) => {
+            let mut goals = load_goals(&goals_path(config))?;
+            if goals.remove(&tag).is_some() {
+                info!(config, "Removed goal {}", tag);
+                Ok(RTWMutation::Goals(goals))
+            } else {
+                info!(config, "No goal for {}.", tag);
+                Ok(RTWMutation::Pure)
+            }
+        }
+        RTWAction::GoalStatus((range_start, range_end)) => {
+            let goals = load_goals(&goals_path(config))?;
+            if goals.is_empty() {
+                println!("No goals found.");
+                return Ok(RTWMutation::Pure);
+            }
+            let activities = service
+                .filter_activities_within((range_start, range_end), |(_i, a)| {
+                    range_start <= a.get_start_time() && a.get_start_time() <= range_end
+                })?;
+            let statuses = goal_statuses(&goals, &tag_durations(activities.as_slice()));
+            let longest_tag = statuses
+                .iter()
+                .map(|(tag, _, _, _)| tag.len())
+                .max()
+                .unwrap_or_default();
+            for (tag, tracked, target, delta) in statuses {
+                println!(
+                    "{} {} / {} {}",
+                    config
+                        .painter
+                        .tag(&format!("{:width$}", tag, width = longest_tag)),
+                    config.format_duration(tracked),
+                    config.format_duration(target),
+                    format_delta(delta, config)
+                );
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::WeekGrid((range_start, range_end), (tags, any)) => {
+            let activities =
+                service.filter_activities_within((range_start, range_end), |(_i, a)| {
+                    range_start <= a.get_start_time()
+                        && a.get_start_time() <= range_end
+                        && a.has_tags(&tags, any)
+                })?;
+            let days = period_stats(activities.as_slice(), Period::Day, None);
+            let thresholds = match config.week_grid_thresholds_minutes {
+                Some((medium, heavy)) => (
+                    Duration::minutes(medium as i64),
+                    Duration::minutes(heavy as i64),
+                ),
                 None => {
-                    println!("No activity to continue from.");
+                    let totals: Vec<Duration> =
+                        days.values().map(|(_count, total)| *total).collect();
+                    default_thresholds(&totals)
+                }
+            };
+            let range_start: DateTime<Local> = range_start.into();
+            let range_end: DateTime<Local> = range_end.into();
+            let range = (
+                range_start.date().naive_local(),
+                range_end.date().naive_local(),
+            );
+            for line in render_week_grid(&days, range, thresholds) {
+                println!("{}", line);
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Report(range_maybe, period, clip) => {
+            // clipped: activities partially within the range count for their part within the range
+            let clip_range = range_maybe.filter(|_| clip);
+            let activities = match clip_range {
+                Some((range_start, range_end)) => service
+                    .filter_activities_within((range_start, range_end), |(_i, a)| {
+                        a.get_start_time() < range_end && range_start < a.get_stop_time()
+                    })?,
+                None => filter_in_optional_range(service, range_maybe, |_| true)?,
+            };
+            let stats = period_stats(activities.as_slice(), period, clip_range);
+            if stats.is_empty() {
+                println!("No filtered data found.");
+            } else {
+                let total_label = "Total";
+                let total_count: usize = stats.values().map(|(count, _total)| count).sum();
+                let total = stats
+                    .values()
+                    .fold(Duration::seconds(0), |acc, (_count, total)| acc + *total);
+                let rows: Vec<(String, usize, Duration)> = stats
+                    .into_iter()
+                    .map(|(start, (count, total))| (period_label(start, period), count, total))
+                    .collect();
+                let label_width = rows
+                    .iter()
+                    .map(|(label, _count, _total)| label.len())
+                    .max()
+                    .unwrap_or_default()
+                    .max(total_label.len());
+                let count_width = total_count.to_string().len();
+                for (label, count, total) in rows {
+                    println!(
+                        "{:label_width$} {:>count_width$} {}",
+                        label,
+                        count,
+                        config.format_duration(total),
+                        label_width = label_width,
+                        count_width = count_width
+                    );
+                }
+                println!(
+                    "{:label_width$} {:>count_width$} {}",
+                    total_label,
+                    total_count,
+                    config.format_duration(total),
+                    label_width = label_width,
+                    count_width = count_width
+                );
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Continue(continued_id_maybe, start_time, pick) => {
+            let activities = service.get_finished_activities()?;
+            let continued_maybe = if pick {
+                pick_activity(&activities, config.continue_pick_count)?
+            } else {
+                match continued_id_maybe {
+                    None => activities.last().cloned(),
+                    Some(continued_id) => activities
+                        .iter()
+                        .find(|(id, _)| *id == continued_id)
+                        .cloned(),
+                }
+            };
+            match (continued_maybe.as_ref(), continued_id_maybe) {
+                (None, None) => {
+                    info!(config, "No activity to continue from.");
                     Ok(RTWMutation::Pure)
                 }
-                Some((_id, finished)) => {
-                    println!("Tracking {}", finished.get_title());
+                (None, Some(continued_id)) => Err(RtwError::NotFound(continued_id).into()),
+                (Some((_id, finished)), _) => {
+                    info!(config, "Tracking {}", finished.get_title());
                     let new_current = OngoingActivity::new(
-                        clock.get_time(),
+                        start_time,
                         finished.get_tags(),
                         finished.get_description(),
                     );
-                    Ok(RTWMutation::Start(new_current))
+                    Ok(RTWMutation::Start(new_current, false))
                 }
             }
         }
         RTWAction::Delete(activity_id) => {
-            let deleted = service.filter_activities(|(i, _)| *i == activity_id)?;
-            let deleted_maybe = deleted.first();
+            let deleted = service.get_activity_by_id(activity_id)?;
+            let deleted_maybe = deleted.as_ref();
             match deleted_maybe {
-                None => {
-                    println!("No activity found for id {}.", activity_id);
-                    Ok(RTWMutation::Pure)
-                }
+                None => Err(RtwError::NotFound(activity_id).into()),
                 Some((deleted_id, deleted)) => {
-                    println!("Deleted {}", deleted.get_title());
-                    println!("Started {:>20}", deleted.get_start_time());
-                    println!("Ended   {:>20}", deleted.get_stop_time());
-                    println!("Total   {:>20}", deleted.get_duration());
+                    info!(config, "Deleted {}", deleted.get_title());
+                    info!(
+                        config,
+                        "Started {}",
+                        config.format_datetime(deleted.get_start_time())
+                    );
+                    info!(
+                        config,
+                        "Ended   {}",
+                        config.format_datetime(deleted.get_stop_time())
+                    );
+                    info!(
+                        config,
+                        "Total   {}",
+                        config.format_duration(deleted.get_duration())
+                    );
                     Ok(RTWMutation::Delete(*deleted_id))
                 }
             }
         }
+        RTWAction::DeleteMatching(tags, range_maybe, yes) => {
+            let deleted =
+                filter_in_optional_range(service, range_maybe, |(_i, a)| a.has_tags(&tags, false))?;
+            let deleted_ids: Vec<ActivityId> = deleted.iter().map(|(id, _)| *id).collect();
+            match deleted_ids.len() {
+                0 => {
+                    info!(config, "No activity found.");
+                    Ok(RTWMutation::Pure)
+                }
+                count => {
+                    let prompt = format!("Delete {} activities?", count);
+                    if yes || confirm(&prompt)? {
+                        info!(config, "Deleted {} activities.", count);
+                        Ok(RTWMutation::DeleteMatching(deleted_ids))
+                    } else {
+                        Err(anyhow::anyhow!(
+                            "{} activities match, use --yes to delete them",
+                            count
+                        ))
+                    }
+                }
+            }
+        }
+        RTWAction::Annotate(activity_id, description) => {
+            let found = service.get_activity_by_id(activity_id)?;
+            match found.as_ref() {
+                None => Err(RtwError::NotFound(activity_id).into()),
+                Some((_id, original)) => {
+                    let annotated = original.annotate(Some(description));
+                    info!(config, "Annotated {}", annotated.get_title());
+                    if let Some(description) = annotated.get_description() {
+                        info!(config, "{}", description);
+                    }
+                    Ok(RTWMutation::Modify(activity_id, annotated))
+                }
+            }
+        }
+        RTWAction::Modify(activity_id, start_time_maybe, stop_time_maybe, tags_maybe) => {
+            let found = service.get_activity_by_id(activity_id)?;
+            match found.as_ref() {
+                None => Err(RtwError::NotFound(activity_id).into()),
+                Some((_id, original)) => {
+                    let modified =
+                        original.modify(start_time_maybe, stop_time_maybe, tags_maybe)?;
+                    info!(config, "Modified {}", modified.get_title());
+                    info!(
+                        config,
+                        "Started {}",
+                        config.format_datetime(modified.get_start_time())
+                    );
+                    info!(
+                        config,
+                        "Ended   {}",
+                        config.format_datetime(modified.get_stop_time())
+                    );
+                    info!(
+                        config,
+                        "Total   {}",
+                        config.format_duration(modified.get_duration())
+                    );
+                    Ok(RTWMutation::Modify(activity_id, modified))
+                }
+            }
+        }
+        RTWAction::Move(activity_id, delta, allow_overlap, allow_future) => {
+            let found = service.get_activity_by_id(activity_id)?;
+            match found.as_ref() {
+                None => Err(RtwError::NotFound(activity_id).into()),
+                Some((_id, original)) => {
+                    let moved = original.shift(delta);
+                    if !allow_future {
+                        check_not_in_future(moved.get_stop_time(), clock)?;
+                    }
+                    if config.deny_overlapping && !allow_overlap {
+                        let overlapping_ids: Vec<ActivityId> = service
+                            .filter_activities_within(
+                                (moved.get_start_time(), moved.get_stop_time()),
+                                |(i, a)| *i != activity_id && a.overlaps(&moved),
+                            )?
+                            .into_iter()
+                            .map(|(i, _a)| i)
+                            .collect();
+                        if !overlapping_ids.is_empty() {
+                            return Err(
+                                RtwError::Overlap(moved.get_title(), overlapping_ids).into()
+                            );
+                        }
+                    }
+                    info!(config, "Moved {}", moved.get_title());
+                    info!(
+                        config,
+                        "Started {}",
+                        config.format_datetime(moved.get_start_time())
+                    );
+                    info!(
+                        config,
+                        "Ended   {}",
+                        config.format_datetime(moved.get_stop_time())
+                    );
+                    Ok(RTWMutation::Modify(activity_id, moved))
+                }
+            }
+        }
+        RTWAction::Split(activity_id, time, tags_maybe) => {
+            let found = service.get_activity_by_id(activity_id)?;
+            match found.as_ref() {
+                None => Err(RtwError::NotFound(activity_id).into()),
+                Some((_id, original)) => {
+                    let (first, second) = original.split(time, tags_maybe)?;
+                    info!(config, "Split {}", original.get_title());
+                    for split in &[&first, &second] {
+                        info!(
+                            config,
+                            "{} {} {} {}",
+                            split.get_title(),
+                            config.format_datetime(split.get_start_time()),
+                            config.format_datetime(split.get_stop_time()),
+                            config.format_duration(split.get_duration())
+                        );
+                    }
+                    Ok(RTWMutation::Split(activity_id, first, second))
+                }
+            }
+        }
+        RTWAction::Merge(first_id, second_id, force) => {
+            let first = service.get_activity_by_id(first_id)?;
+            let second = service.get_activity_by_id(second_id)?;
+            match (first, second) {
+                (None, _) => Err(RtwError::NotFound(first_id).into()),
+                (_, None) => Err(RtwError::NotFound(second_id).into()),
+                (Some(first), Some(second)) => {
+                    let ((older_id, older), (newer_id, newer)) = if first.1 <= second.1 {
+                        (first, second)
+                    } else {
+                        (second, first)
+                    };
+                    let merged = older.merge(&newer, force)?;
+                    info!(config, "Merged {}", merged.get_title());
+                    info!(
+                        config,
+                        "Started {}",
+                        config.format_datetime(merged.get_start_time())
+                    );
+                    info!(
+                        config,
+                        "Ended   {}",
+                        config.format_datetime(merged.get_stop_time())
+                    );
+                    info!(
+                        config,
+                        "Total   {}",
+                        config.format_duration(merged.get_duration())
+                    );
+                    Ok(RTWMutation::Merge(older_id, newer_id, merged))
+                }
+            }
+        }
+        RTWAction::Rename(old, new, dry_run) => {
+            let finished_ids: Vec<ActivityId> = service
+                .filter_activities(|(_i, a)| a.rename_tag(&old, &new).is_some())?
+                .iter()
+                .map(|(i, _a)| *i)
+                .collect();
+            let ongoing_ids: Vec<ActivityId> = service
+                .get_ongoing_activities()?
+                .iter()
+                .filter(|(_i, a)| a.rename_tag(&old, &new).is_some())
+                .map(|(i, _a)| *i)
+                .collect();
+            if finished_ids.is_empty() && ongoing_ids.is_empty() {
+                info!(config, "No activity tagged with {}.", old);
+                return Ok(RTWMutation::Pure);
+            }
+            info!(config, "Renamed  {} to {}", old, new);
+            info!(config, "Finished {}", finished_ids.len());
+            info!(config, "Ongoing  {}", ongoing_ids.len());
+            if dry_run {
+                info!(config, "Finished ids {}", finished_ids.iter().join(" "));
+                info!(config, "Ongoing ids  {}", ongoing_ids.iter().join(" "));
+                Ok(RTWMutation::Pure)
+            } else {
+                Ok(RTWMutation::Rename(old, new))
+            }
+        }
+        RTWAction::Retag(range_maybe, tags, add, remove, dry_run) => {
+            let retagged: Vec<(ActivityId, Activity)> =
+                filter_in_optional_range(service, range_maybe, |(_i, a)| {
+                    a.has_tags(&tags, false) && a.retag(&add, &remove).is_some()
+                })?
+                .into_iter()
+                .filter_map(|(i, a)| a.retag(&add, &remove).map(|retagged| (i, retagged)))
+                .collect();
+            if retagged.is_empty() {
+                info!(config, "No activity changed.");
+                return Ok(RTWMutation::Pure);
+            }
+            info!(config, "Retagged {} activities.", retagged.len());
+            if dry_run {
+                info!(config, "Ids {}", retagged.iter().map(|(i, _a)| i).join(" "));
+                Ok(RTWMutation::Pure)
+            } else {
+                Ok(RTWMutation::Retag(retagged))
+            }
+        }
         RTWAction::DisplayCurrent => {
             let ongoing_activities = service.get_ongoing_activities()?;
             if ongoing_activities.is_empty() {
                 println!("There is no active time tracking.");
             } else {
                 for (id, ongoing_activity) in ongoing_activities {
-                    println!("Tracking {}", ongoing_activity.get_title());
                     println!(
-                        "Total    {}",
-                        clock.get_time() - ongoing_activity.get_start_time()
+                        "Tracking {}",
+                        config.painter.tag(&ongoing_activity.get_title())
+                    );
+                    println!(
+                        "{}",
+                        config.painter.bold(&format!(
+                            "Total    {}",
+                            config.format_duration(ongoing_activity.elapsed(clock.get_time()))
+                        ))
                     );
                     println!("Id       {}", id);
                 }
             }
             Ok(RTWMutation::Pure)
         }
+        RTWAction::Current(format) => {
+            let ongoing_activities = service.get_ongoing_activities()?;
+            let now = clock.get_time();
+            match format {
+                OutputFormat::Json => {
+                    println!(
+                        "{}",
+                        export_ongoing_activities_to_json(ongoing_activities.as_slice(), now)?
+                    );
+                }
+                OutputFormat::Text | OutputFormat::Markdown => {
+                    if ongoing_activities.is_empty() {
+                        println!("There is no active time tracking.");
+                    }
+                    for (id, ongoing_activity) in ongoing_activities {
+                        println!(
+                            "Tracking {}",
+                            config.painter.tag(&ongoing_activity.get_title())
+                        );
+                        println!(
+                            "Started  {}",
+                            config.format_datetime(ongoing_activity.get_start_time())
+                        );
+                        println!(
+                            "{}",
+                            config.painter.bold(&format!(
+                                "Elapsed  {}",
+                                config.format_duration(ongoing_activity.elapsed(now))
+                            ))
+                        );
+                        println!("Id       {}", id);
+                    }
+                }
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::CurrentTags => {
+            let ongoing_activities = service.get_ongoing_activities()?;
+            if ongoing_activities.is_empty() {
+                // nothing printed, status bars hide the segment on failure
+                std::process::exit(1);
+            }
+            for (_id, ongoing_activity) in ongoing_activities {
+                println!("{}", ongoing_activity.get_title());
+            }
+            Ok(RTWMutation::Pure)
+        }
         RTWAction::Timeline((range_start, range_end)) => {
             let activities = service.get_finished_activities()?;
             let activities: Vec<ActivityWithId> = activities
@@ -310,32 +2085,81 @@ where
                 .cloned()
                 .chain(ongoing_activities.iter().cloned())
                 .collect();
-            let rendered = render_days(timeline_activities.as_slice(), &config.timeline_colors)?;
+            let rendered = render_days(
+                timeline_activities.as_slice(),
+                &config.timeline_colors,
+                config.duration_format,
+                config.duration_precision,
+            )?;
             for line in rendered {
                 println!("{}", line);
             }
             Ok(RTWMutation::Pure)
         }
-        RTWAction::Cancel(id_maybe) => match get_ongoing_activity(id_maybe, service)? {
-            Optional(None) => {
-                println!("Nothing to cancel: there is no active time tracking.");
+        RTWAction::DayChart((range_start, range_end)) => {
+            let now = clock.get_time();
+            let in_day =
+                |a: &Activity| a.get_start_time() < range_end && range_start < a.get_stop_time();
+            let activities =
+                service.filter_activities_within((range_start, range_end), |(_i, a)| in_day(a))?;
+            let ongoing_activities: Vec<ActivityWithId> = service
+                .get_ongoing_activities()?
+                .iter()
+                .filter_map(|(i, a)| match a.clone().into_activity(now) {
+                    Ok(a) => Some((*i, a)),
+                    _ => None,
+                })
+                .filter(|(_i, a)| in_day(a))
+                .collect();
+            // clamp activities to the day
+            let chart_activities: Vec<ActivityWithId> = activities
+                .iter()
+                .chain(ongoing_activities.iter())
+                .filter_map(|(i, a)| {
+                    a.modify(
+                        Some(a.get_start_time().max(range_start)),
+                        Some(a.get_stop_time().min(range_end)),
+                        None,
+                    )
+                    .ok()
+                    .map(|a| (*i, a))
+                })
+                .collect();
+            if chart_activities.is_empty() {
+                println!("No filtered data found.");
+            } else {
+                for line in render_day_chart(chart_activities.as_slice(), DEFAULT_CHART_WIDTH) {
+                    println!("{}", line);
+                }
+            }
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Cancel(id_maybe) => match get_ongoing_activity(id_maybe, &[], service)? {
+            // no tags given: there is no tag match to report
+            Optional(None) | OptionalOrAmbiguousOrNotFound::NoTagMatch(_) => {
+                info!(config, "Nothing to cancel.");
                 Ok(RTWMutation::Pure)
             }
             Optional(Some((cancelled_id, cancelled))) => {
-                println!("Cancelled {}", cancelled.get_title());
-                println!("Started   {:>20}", cancelled.get_start_time());
-                println!(
-                    "Total     {:>20}",
-                    clock.get_time() - cancelled.get_start_time()
+                info!(config, "Cancelled tracking of {}.", cancelled.get_title());
+                info!(
+                    config,
+                    "Started   {}",
+                    config.format_datetime(cancelled.get_start_time())
+                );
+                info!(
+                    config,
+                    "Total     {}",
+                    config.format_duration(cancelled.elapsed(clock.get_time()))
                 );
                 Ok(RTWMutation::Cancel(cancelled_id))
             }
             OptionalOrAmbiguousOrNotFound::Ambiguous => {
-                println!("Multiple ongoing activities, please provide an id.");
+                info!(config, "Multiple ongoing activities, please provide an id.");
                 Ok(RTWMutation::Pure)
             }
             OptionalOrAmbiguousOrNotFound::NotFound(cancelled_id) => {
-                println!("No ongoing activity with id {}.", cancelled_id);
+                info!(config, "No ongoing activity with id {}.", cancelled_id);
                 Ok(RTWMutation::Pure)
             }
         },
@@ -351,15 +2175,140 @@ where
             println!("{}", calendar);
             Ok(RTWMutation::Pure)
         }
+        RTWAction::Export(format, range_maybe) => {
+            let activities = filter_in_optional_range(service, range_maybe, |_| true)?;
+            println!(
+                "{}",
+                export_activities(activities.as_slice(), format, config)?
+            );
+            Ok(RTWMutation::Pure)
+        }
+        RTWAction::Import(path, format) => {
+            let file = File::open(&path)?;
+            let (imported, imported_ongoing) = match format {
+                ImportFormat::Rtw => (import_activities_from_json(file)?, vec![]),
+                ImportFormat::Timewarrior => {
+                    let imported = timewarrior::import_intervals(file)?;
+                    (imported.finished, imported.ongoing)
+                }
+            };
+            let mut ongoing_maybe: Option<OngoingActivity> = None;
+            let no_ongoing = service.get_ongoing_activities()?.is_empty();
+            for ongoing in imported_ongoing {
+                if no_ongoing && ongoing_maybe.is_none() {
+                    ongoing_maybe = Some(ongoing);
+                } else {
+                    eprintln!(
+                        "Warning: skipped ongoing activity {} started {}",
+                        ongoing.get_title(),
+                        config.format_datetime(ongoing.get_start_time())
+                    );
+                }
+            }
+            let finished = service.get_finished_activities()?;
+            let imported_len = imported.len();
+            let mut kept: Vec<Activity> = vec![];
+            for activity in imported {
+                let duplicate = finished
+                    .iter()
+                    .map(|(_id, a)| a)
+                    .chain(kept.iter())
+                    .any(|a| {
+                        a.get_start_time() == activity.get_start_time()
+                            && a.get_stop_time() == activity.get_stop_time()
+                    });
+                if !duplicate {
+                    kept.push(activity);
+                }
+            }
+            info!(config, "Imported {}", kept.len());
+            info!(
+                config,
+                "Skipped  {} (duplicates)",
+                imported_len - kept.len()
+            );
+            if let Some(ongoing) = &ongoing_maybe {
+                info!(config, "Tracking {}", ongoing.get_title());
+                info!(
+                    config,
+                    "Started  {}",
+                    config.format_datetime(ongoing.get_start_time())
+                );
+            }
+            Ok(RTWMutation::Import(kept, ongoing_maybe))
+        }
         RTWAction::Completion(shell) => {
             let mut app = cli_helper::get_app();
             app.gen_completions_to(crate_name!(), shell, &mut std::io::stdout());
             Ok(RTWMutation::Pure)
         }
+        RTWAction::Edit => {
+            let activities = service.get_finished_activities()?;
+            let path = config.storage_dir_path.join(EDIT_FILE_NAME);
+            verbose!(config, "Edit file {}", path.display());
+            let edited = edit_activities(&path, activities.as_slice())?;
+            let changes = edited_activities(activities.as_slice(), edited)?;
+            if changes.is_empty() {
+                info!(config, "No change.");
+                Ok(RTWMutation::Pure)
+            } else {
+                info!(config, "Modified {}", changes.modified.len());
+                info!(config, "Deleted  {}", changes.deleted.len());
+                info!(config, "Added    {}", changes.added.len());
+                Ok(RTWMutation::Edit(changes))
+            }
+        }
+        RTWAction::Undo => match undo_journal(config).last()? {
+            None => {
+                info!(config, "Nothing to undo.");
+                Ok(RTWMutation::Pure)
+            }
+            Some(entry) => {
+                info!(config, "Undo {}", entry.command);
+                for (_id, removed) in entry.removed.iter() {
+                    info!(config, "Restored {}", removed.get_title());
+                }
+                for (_id, added) in entry.added.iter() {
+                    info!(config, "Deleted  {}", added.get_title());
+                }
+                Ok(RTWMutation::Undo)
+            }
+        },
+        RTWAction::Doctor(fix) => {
+            let findings = match (
+                service.get_ongoing_activities(),
+                service.get_finished_activities(),
+            ) {
+                (Ok(ongoing), Ok(finished)) => {
+                    check_activities(&ongoing, &finished, clock.get_time())
+                }
+                (Err(e), _) | (_, Err(e)) => vec![Finding::Unreadable(e.to_string())],
+            };
+            if findings.is_empty() {
+                println!("All good.");
+                return Ok(RTWMutation::Pure);
+            }
+            let (fixed, left): (Vec<Finding>, Vec<Finding>) = findings
+                .into_iter()
+                .partition(|finding| fix && finding.is_fixable());
+            for finding in fixed.iter() {
+                println!("{} (fixed)", finding);
+            }
+            for finding in left.iter() {
+                println!("{}", finding);
+            }
+            if fixed.is_empty() {
+                Err(anyhow::anyhow!("{} problems found", left.len()))
+            } else {
+                Ok(RTWMutation::Repair(fixed, left.len()))
+            }
+        }
     }
 }
 
 /// Side effect
+///
+/// Mutations changing activities are recorded in the undo journal.
 pub fn run_mutation<S>(
     action: RTWMutation,
     service: &mut Service<S>,
@@ -369,28 +2318,227 @@ where
     S: Storage,
 {
     match action {
-        RTWMutation::Start(activity) => {
-            let _started = service.start_activity(activity, config.deny_overlapping)?;
-            Ok(())
+        RTWMutation::Pure => Ok(()),
+        RTWMutation::Undo => match undo_journal(config).pop()? {
+            None => Ok(()),
+            Some(entry) => entry.revert(service),
+        },
+        _ => {
+            let mut entry = UndoEntry::new(action.command());
+            // some mutations partially succeed (e.g. stop --all), record them anyway
+            let result = apply_mutation(action, service, config, &mut entry);
+            if entry.has_changes(service)? {
+                undo_journal(config).push(&entry)?;
+            }
+            result
         }
-        RTWMutation::Track(activity) => {
-            let _tracked = service.track_activity(activity, config.deny_overlapping)?;
+    }
+}
+
+// replace finished activities with the same id, recording them in `entry`
+fn replace_activities<S: Storage>(
+    service: &mut Service<S>,
+    replacements: Vec<(ActivityId, Activity)>,
+    entry: &mut UndoEntry,
+) -> anyhow::Result<()> {
+    let replacements: HashMap<ActivityId, Activity> = replacements.into_iter().collect();
+    let replaced = service.filter_activities(|(i, _a)| replacements.contains_key(i))?;
+    let _updated = service.update_activities(|(i, _a)| replacements.get(i).cloned())?;
+    for (id, previous) in replaced {
+        entry.added.push((id, replacements[&id].clone()));
+        entry.removed.push((id, previous));
+    }
+    Ok(())
+}
+
+// activities touched by the mutation are recorded in `entry`, as soon as they are written
+fn apply_mutation<S>(
+    action: RTWMutation,
+    service: &mut Service<S>,
+    config: &RTWConfig,
+    entry: &mut UndoEntry,
+) -> anyhow::Result<()>
+where
+    S: Storage,
+{
+    match action {
+        RTWMutation::Start(activity, parallel) => {
+            let cached = load_fresh_tags_cache(config);
+            entry.record_ongoing(service)?;
+            let (started, stopped_maybe) =
+                service.start_activity(activity, config.deny_overlapping, parallel)?;
+            entry.added.extend(stopped_maybe.clone());
+            let stopped: Vec<&Activity> = stopped_maybe.iter().map(|(_id, a)| a).collect();
+            update_tags_cache(cached, &stopped, service, config);
+            if let Some((_id, stopped)) = stopped_maybe {
+                run_configured_hook(config, ON_STOP, &stopped)?;
+            }
+            run_configured_hook(config, ON_START, &started)
+        }
+        RTWMutation::Track(activity, allow_overlap) => {
+            let deny_overlapping = config.deny_overlapping && !allow_overlap;
+            let cached = load_fresh_tags_cache(config);
+            let (id, tracked) = service.track_activity(activity, deny_overlapping)?;
+            update_tags_cache(cached, &[&tracked], service, config);
+            entry.added.push((id, tracked));
             Ok(())
         }
-        RTWMutation::Stop(stop_time, activity_id) => {
-            let _stopped =
-                service.stop_ongoing_activity(stop_time, activity_id, config.deny_overlapping)?;
+        RTWMutation::Import(activities, ongoing_maybe) => {
+            let imported = service.track_activities(activities, false)?;
+            entry.added.extend(imported);
+            if let Some(ongoing) = ongoing_maybe {
+                entry.record_ongoing(service)?;
+                let _started = service.start_activity(ongoing, false, true)?;
+            }
             Ok(())
         }
+        RTWMutation::Stop(stop_time, activity_id, description) => {
+            entry.record_ongoing(service)?;
+            let stopped_maybe = service.stop_ongoing_activity(
+                stop_time,
+                activity_id,
+                config.deny_overlapping,
+                description,
+            )?;
+            match stopped_maybe {
+                None => Ok(()),
+                Some((id, stopped)) => {
+                    entry.added.push((id, stopped.clone()));
+                    run_configured_hook(config, ON_STOP, &stopped)
+                }
+            }
+        }
+        RTWMutation::StopCoalesce(stopped_id, previous_id, merged) => {
+            // the ongoing activity is now part of the previous activity, which keeps its id
+            entry.record_ongoing(service)?;
+            let _cancelled = service.cancel_ongoing_activity(stopped_id)?;
+            replace_activities(service, vec![(previous_id, merged.clone())], entry)?;
+            run_configured_hook(config, ON_STOP, &merged)
+        }
+        RTWMutation::StopAll(stop_time) => {
+            entry.record_ongoing(service)?;
+            let stopped = service.stop_all_activities(stop_time, config.deny_overlapping)?;
+            for (_id, result) in stopped.iter() {
+                if let Ok(stopped) = result {
+                    entry.added.push(stopped.clone());
+                }
+            }
+            for (_id, result) in stopped.iter() {
+                if let Ok((_stopped_id, stopped)) = result {
+                    run_configured_hook(config, ON_STOP, stopped)?;
+                }
+            }
+            let failures: Vec<(ActivityId, RtwError)> = stopped
+                .into_iter()
+                .filter_map(|(id, result)| result.err().map(|e| (id, e)))
+                .collect();
+            for (id, e) in failures.iter() {
+                eprintln!("Failed to stop activity {}: {}", id, e);
+            }
+            if failures.is_empty() {
+                Ok(())
+            } else {
+                Err(anyhow::anyhow!(
+                    "{} ongoing activities could not be stopped",
+                    failures.len()
+                ))
+            }
+        }
         RTWMutation::Delete(activity_id) => {
-            let _deleted = service.delete_activity(activity_id)?;
+            let deleted = service.delete_activity(activity_id)?;
+            entry.removed.push((activity_id, deleted));
             Ok(())
         }
+        RTWMutation::DeleteMatching(activity_ids) => {
+            let deleted = service.delete_activities(|(i, _a)| activity_ids.contains(i))?;
+            entry.removed.extend(deleted);
+            Ok(())
+        }
+        RTWMutation::Modify(activity_id, modified) => {
+            replace_activities(service, vec![(activity_id, modified)], entry)?;
+            // tags and durations may have changed
+            if let Err(e) = rebuild_tags_cache(service, config) {
+                verbose!(config, "Could not update tags cache: {}", e);
+            }
+            Ok(())
+        }
+        RTWMutation::Split(activity_id, first, second) => {
+            replace_activities(service, vec![(activity_id, first)], entry)?;
+            let tracked = service.track_activity(second, false)?;
+            entry.added.push(tracked);
+            Ok(())
+        }
+        RTWMutation::Merge(older_id, newer_id, merged) => {
+            // merged activity starts with the older activity and keeps its id
+            replace_activities(service, vec![(older_id, merged)], entry)?;
+            let deleted = service.delete_activity(newer_id)?;
+            entry.removed.push((newer_id, deleted));
+            Ok(())
+        }
+        RTWMutation::Rename(old, new) => {
+            let renamed: Vec<(ActivityId, Activity)> = service
+                .filter_activities(|(_i, a)| a.rename_tag(&old, &new).is_some())?
+                .into_iter()
+                .filter_map(|(i, a)| a.rename_tag(&old, &new).map(|renamed| (i, renamed)))
+                .collect();
+            replace_activities(service, renamed, entry)?;
+            entry.record_ongoing(service)?;
+            let _renamed = service.update_ongoing_activities(|(_i, a)| a.rename_tag(&old, &new))?;
+            Ok(())
+        }
+        RTWMutation::Retag(retagged) => {
+            replace_activities(service, retagged, entry)?;
+            if let Err(e) = rebuild_tags_cache(service, config) {
+                verbose!(config, "Could not update tags cache: {}", e);
+            }
+            Ok(())
+        }
+        RTWMutation::Repair(fixed, left) => {
+            let zero_length: Vec<ActivityId> = fixed
+                .iter()
+                .filter_map(|finding| match finding {
+                    Finding::ZeroLength(id) => Some(*id),
+                    _ => None,
+                })
+                .collect();
+            let deleted = service.delete_activities(|(i, _a)| zero_length.contains(i))?;
+            entry.removed.extend(deleted);
+            let finished_ongoing: Vec<ActivityId> = fixed
+                .iter()
+                .filter_map(|finding| match finding {
+                    Finding::OngoingAlsoFinished(ongoing_id, _finished_id) => Some(*ongoing_id),
+                    _ => None,
+                })
+                .sorted()
+                .dedup()
+                .collect();
+            entry.record_ongoing(service)?;
+            // remove from the highest id: removing an activity shifts the ids of later ones
+            for ongoing_id in finished_ongoing.into_iter().rev() {
+                let _removed = service.cancel_ongoing_activity(ongoing_id)?;
+            }
+            if left > 0 {
+                Err(anyhow::anyhow!("{} problems left", left))
+            } else {
+                Ok(())
+            }
+        }
         RTWMutation::Cancel(activity_id) => {
+            entry.record_ongoing(service)?;
             let _cancelled = service.cancel_ongoing_activity(activity_id)?;
             Ok(())
         }
-        RTWMutation::Pure => {
+        RTWMutation::Edit(changes) => {
+            let deleted = service.delete_activities(|(i, _a)| changes.deleted.contains(i))?;
+            entry.removed.extend(deleted);
+            replace_activities(service, changes.modified, entry)?;
+            let added = service.track_activities(changes.added, false)?;
+            entry.added.extend(added);
+            Ok(())
+        }
+        // goals are not activities, they are not recorded in the undo journal
+        RTWMutation::Goals(goals) => save_goals(&goals_path(config), &goals),
+        RTWMutation::Undo | RTWMutation::Pure => {
             // pure nothing to do
             Ok(())
         }
@@ -2,62 +2,409 @@
 extern crate config;
 
 use self::config::FileFormat;
+use crate::cli_helper::normalize_tags;
+use crate::color::Painter;
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::durationw::{DurationFormat, DurationPrecision, DurationW, RoundMethod};
+use crate::rtw_core::Tag;
+use crate::{json_storage, jsonl_storage, sqlite_storage};
+use chrono::{DateTime, Local, NaiveTime, Weekday};
 use serde::Deserialize;
 use serde::Serialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const DEFAULT_CONFIG: &str = r#"
     {
         "timeline_colors": [[183,28,28], [26,35,126], [0,77,64], [38,50,56]],
-        "deny_overlapping": true
+        "deny_overlapping": true,
+        "storage_backend": "json",
+        "week_start": "monday"
     }
 "#;
 
 type RGB = (u8, u8, u8);
 
+/// Where activities are stored
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// `.rtw.json` and `.rtwh.json` files
+    #[default]
+    Json,
+    /// `.rtw.sqlite` database
+    Sqlite,
+    /// `.rtw.json` and `.rtwh.jsonl` (one finished activity per line) files
+    Jsonl,
+}
+
+/// How much is printed besides data and errors
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    /// No confirmations e.g. the started activity
+    Quiet,
+    #[default]
+    Normal,
+    /// Also log files read/written and resolved times (to stderr)
+    Verbose,
+}
+
+/// Default tags of activities starting within a daily time window
+///
+/// e.g. `{"tags": ["work"], "from": "09:00", "to": "18:00"}`,
+/// `from` is included, `to` is excluded, the window spans midnight when `to` is before `from`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WindowDefaultTags {
+    pub tags: Vec<Tag>,
+    #[serde(with = "clock_time")]
+    pub from: NaiveTime,
+    #[serde(with = "clock_time")]
+    pub to: NaiveTime,
+}
+
+impl WindowDefaultTags {
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.to {
+            self.from <= time && time < self.to
+        } else {
+            self.from <= time || time < self.to
+        }
+    }
+}
+
+// HH:MM or HH:MM:SS
+mod clock_time {
+    use chrono::NaiveTime;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&time.format("%H:%M:%S").to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(&s, "%H:%M:%S")
+            .or_else(|_| NaiveTime::parse_from_str(&s, "%H:%M"))
+            .map_err(|_| de::Error::custom(format!("invalid time '{}', expected HH:MM", s)))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct RTWConfig {
     pub storage_dir_path: PathBuf,
     pub timeline_colors: Vec<RGB>,
     pub deny_overlapping: bool,
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
+    /// First day of the week for `:week` and `:lastweek` ranges
+    #[serde(default = "default_week_start")]
+    pub week_start: Weekday,
+    /// Round summary durations to a multiple of N minutes
+    #[serde(default)]
+    pub round_minutes: Option<u32>,
+    #[serde(default = "default_round_method")]
+    pub round_method: RoundMethod,
+    /// Display dates using this strftime format e.g. `%d/%m/%Y %H:%M`
+    #[serde(default)]
+    pub date_format: Option<String>,
+    /// Directory containing `on-start` and `on-stop` hooks, no hooks when omitted
+    #[serde(default)]
+    pub hooks_dir: Option<PathBuf>,
+    /// Fail the command when a hook fails instead of printing a warning
+    #[serde(default)]
+    pub hooks_abort_on_failure: bool,
+    /// `start` is a no-op when an ongoing activity has the same tags
+    /// and the start time is within N seconds of now (0: start time is now)
+    #[serde(default)]
+    pub duplicate_start_tolerance_seconds: u64,
+    /// `stop` merges the stopped activity into the previous finished activity
+    /// when it has the same tags and stopped within `coalesce_tolerance_seconds` of the start
+    #[serde(default)]
+    pub coalesce: bool,
+    #[serde(default = "default_coalesce_tolerance_seconds")]
+    pub coalesce_tolerance_seconds: u64,
+    /// Number of recent tag sets listed by `continue --pick`
+    #[serde(default = "default_continue_pick_count")]
+    pub continue_pick_count: usize,
+    /// (medium, heavy) minutes of a day in `summary --week-grid`,
+    /// median and third quartile of displayed daily totals when omitted
+    #[serde(default)]
+    pub week_grid_thresholds_minutes: Option<(u64, u64)>,
+    #[serde(default)]
+    pub verbosity: Verbosity,
+    /// Prefix of data file names e.g. `work` for `work.rtw.json`, none by default
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Json backend: store finished activities gzip-compressed in `.rtwh.json.gz`
+    #[serde(default)]
+    pub compress: bool,
+    /// Tags added to every activity created by `start` and `track`, unless `--no-defaults`
+    #[serde(default)]
+    pub default_tags: Vec<Tag>,
+    /// Tags added to activities created by `start` and `track` starting within a time window
+    #[serde(default)]
+    pub window_default_tags: Vec<WindowDefaultTags>,
+    /// Display dates in UTC, set by `--utc`
+    #[serde(skip)]
+    pub display_utc: bool,
+    /// How durations are displayed, set by `--duration-format`
+    #[serde(skip)]
+    pub duration_format: DurationFormat,
+    /// Smallest duration unit displayed, set by `--precision`
+    #[serde(skip)]
+    pub duration_precision: DurationPrecision,
+    /// Colored output, disabled by `--no-color`, `$NO_COLOR` or when stdout is not a terminal
+    #[serde(skip)]
+    pub painter: Painter,
+}
+
+fn default_round_method() -> RoundMethod {
+    RoundMethod::Up
+}
+
+fn default_coalesce_tolerance_seconds() -> u64 {
+    60
+}
+
+fn default_continue_pick_count() -> usize {
+    10
+}
+
+fn default_week_start() -> Weekday {
+    Weekday::Mon
+}
+
+/// Default storage directory, `rtw` in the platform data directory e.g. `$XDG_DATA_HOME/rtw`
+///
+/// The home directory is kept when it holds activities stored there by earlier versions.
+fn default_storage_dir_path() -> PathBuf {
+    let home_dir = dirs::home_dir().expect("could not find home dir");
+    let legacy = [
+        json_storage::CURRENT_FILE_NAME,
+        json_storage::FINISHED_FILE_NAME,
+        jsonl_storage::FINISHED_FILE_NAME,
+        sqlite_storage::DATABASE_FILE_NAME,
+    ]
+    .iter()
+    .any(|file_name| home_dir.join(file_name).exists());
+    match dirs::data_dir() {
+        Some(data_dir) if !legacy => data_dir.join("rtw"),
+        _ => home_dir,
+    }
 }
 
 impl RTWConfig {
     pub fn default() -> Self {
-        let home_dir = dirs::home_dir().expect("could not find home dir");
         RTWConfig {
-            storage_dir_path: home_dir, // stores finished activities
+            storage_dir_path: default_storage_dir_path(), // stores finished activities
             timeline_colors: vec![(183, 28, 28), (26, 35, 126), (0, 77, 64), (38, 50, 56)],
             deny_overlapping: true,
+            storage_backend: StorageBackend::Json,
+            week_start: default_week_start(),
+            round_minutes: None,
+            round_method: default_round_method(),
+            date_format: None,
+            hooks_dir: None,
+            hooks_abort_on_failure: false,
+            duplicate_start_tolerance_seconds: 0,
+            coalesce: false,
+            coalesce_tolerance_seconds: default_coalesce_tolerance_seconds(),
+            continue_pick_count: default_continue_pick_count(),
+            week_grid_thresholds_minutes: None,
+            verbosity: Verbosity::Normal,
+            profile: None,
+            compress: false,
+            default_tags: vec![],
+            window_default_tags: vec![],
+            display_utc: false,
+            duration_format: DurationFormat::Clock,
+            duration_precision: DurationPrecision::Second,
+            painter: Painter::default(),
         }
     }
 
     pub fn deny_overlapping(self, deny: bool) -> Self {
         RTWConfig {
-            storage_dir_path: self.storage_dir_path,
-            timeline_colors: self.timeline_colors,
             deny_overlapping: deny,
+            ..self
+        }
+    }
+
+    pub fn storage_dir_path(self, storage_dir_path: PathBuf) -> Self {
+        RTWConfig {
+            storage_dir_path,
+            ..self
+        }
+    }
+
+    pub fn verbosity(self, verbosity: Verbosity) -> Self {
+        RTWConfig { verbosity, ..self }
+    }
+
+    pub fn is_quiet(&self) -> bool {
+        self.verbosity == Verbosity::Quiet
+    }
+
+    pub fn is_verbose(&self) -> bool {
+        self.verbosity == Verbosity::Verbose
+    }
+
+    pub fn storage_backend(self, storage_backend: StorageBackend) -> Self {
+        RTWConfig {
+            storage_backend,
+            ..self
+        }
+    }
+
+    pub fn profile(self, profile: Option<String>) -> Self {
+        RTWConfig { profile, ..self }
+    }
+
+    pub fn display_utc(self, display_utc: bool) -> Self {
+        RTWConfig {
+            display_utc,
+            ..self
+        }
+    }
+
+    pub fn duration_format(self, duration_format: DurationFormat) -> Self {
+        RTWConfig {
+            duration_format,
+            ..self
+        }
+    }
+
+    pub fn duration_precision(self, duration_precision: DurationPrecision) -> Self {
+        RTWConfig {
+            duration_precision,
+            ..self
+        }
+    }
+
+    pub fn painter(self, painter: Painter) -> Self {
+        RTWConfig { painter, ..self }
+    }
+
+    pub fn week_start(self, week_start: Weekday) -> Self {
+        RTWConfig { week_start, ..self }
+    }
+
+    /// Date as displayed: in UTC or using `date_format`
+    pub fn format_datetime(&self, datetimew: DateTimeW) -> String {
+        datetimew.display_with(self.display_utc, self.date_format.as_deref())
+    }
+
+    /// Duration as displayed using `duration_format` and `duration_precision`
+    pub fn format_duration<D: Into<DurationW>>(&self, duration: D) -> String {
+        duration
+            .into()
+            .display_with_precision(self.duration_format, self.duration_precision)
+    }
+
+    /// `tags` followed by the default tags of an activity starting at `start_time`, without duplicates
+    pub fn with_default_tags(&self, tags: Vec<Tag>, start_time: DateTimeW) -> Vec<Tag> {
+        let start_time: DateTime<Local> = start_time.into();
+        let time = start_time.time();
+        let defaults = self.default_tags.iter().chain(
+            self.window_default_tags
+                .iter()
+                .filter(|window| window.contains(time))
+                .flat_map(|window| window.tags.iter()),
+        );
+        let mut merged = tags;
+        for tag in defaults {
+            if !merged.contains(tag) {
+                merged.push(tag.clone());
+            }
+        }
+        merged
+    }
+
+    /// Path of data file `file_name` in the storage directory, prefixed by the profile if any
+    pub fn data_file_path(&self, file_name: &str) -> PathBuf {
+        match &self.profile {
+            None => self.storage_dir_path.join(file_name),
+            Some(profile) => self
+                .storage_dir_path
+                .join(format!("{}{}", profile, file_name)),
+        }
+    }
+
+    /// Path of the finished activities file of the json backend, compressed if configured
+    pub fn json_finished_file_path(&self) -> PathBuf {
+        if self.compress {
+            self.data_file_path(json_storage::COMPRESSED_FINISHED_FILE_NAME)
+        } else {
+            self.data_file_path(json_storage::FINISHED_FILE_NAME)
+        }
+    }
+
+    /// Paths of the files storing activities with the configured backend
+    pub fn storage_file_paths(&self) -> Vec<PathBuf> {
+        match self.storage_backend {
+            StorageBackend::Json => vec![
+                self.data_file_path(json_storage::CURRENT_FILE_NAME),
+                self.json_finished_file_path(),
+            ],
+            StorageBackend::Sqlite => vec![self.data_file_path(sqlite_storage::DATABASE_FILE_NAME)],
+            StorageBackend::Jsonl => vec![
+                self.data_file_path(json_storage::CURRENT_FILE_NAME),
+                self.data_file_path(jsonl_storage::FINISHED_FILE_NAME),
+            ],
         }
     }
 }
 
+// `data_dir` is an alias for `storage_dir_path`,
+// default tags are normalized like tags given on the command line
+fn into_rtw_config(mut settings: config::Config) -> anyhow::Result<RTWConfig> {
+    if let Ok(data_dir) = settings.get_str("data_dir") {
+        settings.set("storage_dir_path", data_dir)?;
+    }
+    let mut rtw_config: RTWConfig = settings.try_into()?;
+    rtw_config.default_tags = normalize_tags(rtw_config.default_tags)
+        .map_err(|e| anyhow::anyhow!("invalid default_tags: {}", e))?;
+    for window in rtw_config.window_default_tags.iter_mut() {
+        window.tags = normalize_tags(std::mem::take(&mut window.tags))
+            .map_err(|e| anyhow::anyhow!("invalid window_default_tags: {}", e))?;
+    }
+    Ok(rtw_config)
+}
+
+fn default_settings(default_config: &RTWConfig) -> anyhow::Result<config::Config> {
+    let mut settings = config::Config::default();
+    settings
+        .set_default(
+            "storage_dir_path",
+            default_config.storage_dir_path.to_str().unwrap(),
+        )?
+        .merge(config::File::from_str(DEFAULT_CONFIG, FileFormat::Json))?;
+    Ok(settings)
+}
+
 fn load_config_from_config_dir(
     config_dir: &PathBuf,
     default_config: RTWConfig,
 ) -> anyhow::Result<RTWConfig> {
-    let mut settings = config::Config::default();
+    let mut settings = default_settings(&default_config)?;
+    let config_path_toml = config_dir.join("rtw").join("config.toml");
     let config_path = config_dir.join("rtw").join("rtw_config.json");
     let config_path_fallback = config_dir.join("rtw_config.json");
     settings
-        .set_default(
-            "storage_dir_path",
-            default_config.storage_dir_path.to_str().unwrap(),
-        )?
-        .merge(config::File::from_str(DEFAULT_CONFIG, FileFormat::Json))?
+        .merge(config::File::with_name(config_path_toml.to_str().unwrap()).required(false))?
         .merge(config::File::with_name(config_path.to_str().unwrap()).required(false))?
         .merge(config::File::with_name(config_path_fallback.to_str().unwrap()).required(false))?;
-    let rtw_config: RTWConfig = settings.try_into()?;
-    Ok(rtw_config)
+    into_rtw_config(settings)
+}
+
+/// Load config from given file (json or toml), the file must exist
+pub fn load_config_from_file(
+    config_path: &Path,
+    default_config: RTWConfig,
+) -> anyhow::Result<RTWConfig> {
+    let mut settings = default_settings(&default_config)?;
+    settings.merge(config::File::from(config_path).required(true))?;
+    into_rtw_config(settings)
 }
 
 pub fn load_config() -> anyhow::Result<RTWConfig> {
@@ -69,7 +416,11 @@ pub fn load_config() -> anyhow::Result<RTWConfig> {
 
 #[cfg(test)]
 mod tests {
-    use crate::rtw_config::{load_config_from_config_dir, RTWConfig};
+    use crate::rtw_config::{
+        load_config_from_config_dir, load_config_from_file, RTWConfig, Verbosity,
+    };
+    use crate::rtw_core::test_utils::datetimew;
+    use chrono::Weekday;
     use std::fs;
     use std::fs::File;
     use std::io::Write;
@@ -86,6 +437,28 @@ mod tests {
         assert!(config.is_ok())
     }
 
+    #[test]
+    // make sure the toml config file in `example` folder is valid
+    fn example_toml_config_valid() {
+        let example_config = PathBuf::from_str("example/config.toml").unwrap();
+        let config = load_config_from_file(&example_config, RTWConfig::default());
+        assert!(config.is_ok())
+    }
+
+    #[test]
+    fn test_data_file_path_profile() {
+        let config = RTWConfig::default().storage_dir_path(PathBuf::from("/data"));
+        assert_eq!(
+            config.data_file_path(".rtw.json"),
+            PathBuf::from("/data/.rtw.json")
+        );
+        let config = config.profile(Some("work".to_string()));
+        assert_eq!(
+            config.data_file_path(".rtw.json"),
+            PathBuf::from("/data/work.rtw.json")
+        );
+    }
+
     #[test]
     fn test_config_not_found_in_config_dir() {
         let test_config_dir = tempdir().expect("could not create temp directory");
@@ -125,4 +498,120 @@ mod tests {
         assert_eq!(config.unwrap().storage_dir_path, expected);
         Ok(())
     }
+
+    #[test]
+    fn test_config_week_start() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let mut tmp_config = File::create(test_config_dir.path().join("rtw_config.json"))?;
+        writeln!(tmp_config, "{{\n\"week_start\": \"sunday\"\n}}")?;
+        let config = load_config_from_config_dir(
+            &test_config_dir.path().to_path_buf(),
+            RTWConfig::default(),
+        );
+        assert_eq!(config.unwrap().week_start, Weekday::Sun);
+        Ok(())
+    }
+
+    #[test]
+    // .config/rtw/config.toml
+    fn test_toml_config_found_in_sub_config_dir() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let test_config_sub_dir = test_config_dir.path().join("rtw");
+        fs::create_dir(test_config_sub_dir.clone()).expect("could not create temp/rtw directory");
+        let mut tmp_config = File::create(test_config_sub_dir.join("config.toml"))?;
+        writeln!(
+            tmp_config,
+            "data_dir = \"/expected\"\ndate_format = \"%d/%m/%Y %H:%M\"\nround_minutes = 15\nweek_start = \"sunday\""
+        )?;
+        let config = load_config_from_config_dir(
+            &test_config_dir.path().to_path_buf(),
+            RTWConfig::default(),
+        )?;
+        assert_eq!(
+            config.storage_dir_path,
+            PathBuf::from_str("/expected").unwrap()
+        );
+        assert_eq!(config.date_format, Some(String::from("%d/%m/%Y %H:%M")));
+        assert_eq!(config.round_minutes, Some(15));
+        assert_eq!(config.week_start, Weekday::Sun);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_from_file() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let config_path = test_config_dir.path().join("custom.toml");
+        assert!(load_config_from_file(&config_path, RTWConfig::default()).is_err());
+        let mut tmp_config = File::create(&config_path)?;
+        writeln!(tmp_config, "week_start = \"sunday\"")?;
+        let config = load_config_from_file(&config_path, RTWConfig::default())?;
+        assert_eq!(config.week_start, Weekday::Sun);
+        assert_eq!(
+            config.storage_dir_path,
+            RTWConfig::default().storage_dir_path
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_verbosity() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let config_path = test_config_dir.path().join("custom.toml");
+        let mut tmp_config = File::create(&config_path)?;
+        writeln!(tmp_config, "verbosity = \"quiet\"")?;
+        let config = load_config_from_file(&config_path, RTWConfig::default())?;
+        assert_eq!(config.verbosity, Verbosity::Quiet);
+        assert!(config.is_quiet());
+        assert_eq!(RTWConfig::default().verbosity, Verbosity::Normal);
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_default_tags() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let config_path = test_config_dir.path().join("custom.toml");
+        let mut tmp_config = File::create(&config_path)?;
+        writeln!(
+            tmp_config,
+            "default_tags = [\"rtw\"]\n\
+             [[window_default_tags]]\ntags = [\"work\"]\nfrom = \"09:00\"\nto = \"18:00\"\n\
+             [[window_default_tags]]\ntags = [\"night\"]\nfrom = \"22:00\"\nto = \"06:00\""
+        )?;
+        let config = load_config_from_file(&config_path, RTWConfig::default())?;
+        let tags = vec!["foo".to_string(), "rtw".to_string()];
+        assert_eq!(
+            config.with_default_tags(tags.clone(), datetimew("2020-12-25T09:00:00")),
+            vec!["foo", "rtw", "work"]
+        );
+        assert_eq!(
+            config.with_default_tags(tags.clone(), datetimew("2020-12-25T18:00:00")),
+            vec!["foo", "rtw"]
+        );
+        assert_eq!(
+            config.with_default_tags(tags, datetimew("2020-12-25T02:00:00")),
+            vec!["foo", "rtw", "night"]
+        );
+        assert_eq!(
+            RTWConfig::default().with_default_tags(vec![], datetimew("2020-12-25T09:00:00")),
+            Vec::<String>::new()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_default_tags_normalized() -> anyhow::Result<()> {
+        let test_config_dir = tempdir().expect("could not create temp directory");
+        let config_path = test_config_dir.path().join("custom.toml");
+        std::fs::write(&config_path, "default_tags = [\"  padded \", \"padded\"]")?;
+        let config = load_config_from_file(&config_path, RTWConfig::default())?;
+        assert_eq!(config.default_tags, vec!["padded"]);
+        std::fs::write(&config_path, "default_tags = [\"x;y\"]")?;
+        assert!(load_config_from_file(&config_path, RTWConfig::default()).is_err());
+        std::fs::write(
+            &config_path,
+            "[[window_default_tags]]\ntags = [\" \"]\nfrom = \"09:00\"\nto = \"18:00\"",
+        )?;
+        assert!(load_config_from_file(&config_path, RTWConfig::default()).is_err());
+        Ok(())
+    }
 }
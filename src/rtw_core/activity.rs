@@ -2,8 +2,10 @@
 
 use crate::rtw_core::datetimew::DateTimeW;
 use crate::rtw_core::durationw::DurationW;
-use crate::rtw_core::{Description, Tags};
+use crate::rtw_core::error::{RtwError, RtwResult};
+use crate::rtw_core::{Description, Tag, Tags};
 use anyhow::anyhow;
+use chrono::{DateTime, Duration, Local};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
@@ -46,12 +48,195 @@ impl Activity {
     pub fn get_description(&self) -> Option<Description> {
         self.description.clone()
     }
+
+    /// Return true if the activity is tagged with all `tags` (or any of `tags` if `any`)
+    ///
+    /// Always true if `tags` is empty.
+    pub fn has_tags(&self, tags: &[Tag], any: bool) -> bool {
+        if tags.is_empty() {
+            true
+        } else if any {
+            tags.iter().any(|t| self.tags.contains(t))
+        } else {
+            tags.iter().all(|t| self.tags.contains(t))
+        }
+    }
+
+    /// Return true if both activities share some time
+    ///
+    /// Touching activities (one stops when the other starts) do not overlap.
+    pub fn overlaps(&self, other: &Activity) -> bool {
+        self.start_time < other.stop_time && other.start_time < self.stop_time
+    }
+
+    /// Return the duration of the activity part within `range_start` - `range_end`
+    ///
+    /// Zero if the activity is outside the range.
+    pub fn clipped_duration(&self, range_start: DateTimeW, range_end: DateTimeW) -> DurationW {
+        let start = self.start_time.max(range_start);
+        let stop = self.stop_time.min(range_end);
+        if start < stop {
+            stop - start
+        } else {
+            chrono::Duration::zero().into()
+        }
+    }
+
+    /// Return a copy of this activity with the given fields replaced
+    ///
+    /// Omitted (None) fields are unchanged.
+    /// Fails if the modified activity would be zero-length or inverted (`stop time <= start time`).
+    pub fn modify(
+        &self,
+        start_time: Option<DateTimeW>,
+        stop_time: Option<DateTimeW>,
+        tags: Option<Tags>,
+    ) -> anyhow::Result<Activity> {
+        let start_time = start_time.unwrap_or(self.start_time);
+        let stop_time = stop_time.unwrap_or(self.stop_time);
+        if start_time < stop_time {
+            Ok(Activity {
+                start_time,
+                stop_time,
+                tags: tags.unwrap_or_else(|| self.tags.clone()),
+                description: self.description.clone(),
+            })
+        } else {
+            Err(anyhow!(
+                "stop time ({}) <= start time ({})",
+                stop_time,
+                start_time
+            ))
+        }
+    }
+
+    /// Shift both start and stop time by `delta` (negative to move earlier), preserving the duration
+    pub fn shift(&self, delta: Duration) -> Activity {
+        let start_time: DateTime<Local> = self.start_time.into();
+        let stop_time: DateTime<Local> = self.stop_time.into();
+        Activity {
+            start_time: (start_time + delta).into(),
+            stop_time: (stop_time + delta).into(),
+            ..self.clone()
+        }
+    }
+
+    /// Split this activity at `time` into two adjacent activities
+    ///
+    /// The second activity is tagged with `tags` if provided, with the original tags otherwise.
+    /// Both activities keep the original description.
+    /// Fails if `time` is not strictly within the activity.
+    pub fn split(
+        &self,
+        time: DateTimeW,
+        tags: Option<Tags>,
+    ) -> anyhow::Result<(Activity, Activity)> {
+        if self.start_time < time && time < self.stop_time {
+            let first = Activity {
+                stop_time: time,
+                ..self.clone()
+            };
+            let second = Activity {
+                start_time: time,
+                tags: tags.unwrap_or_else(|| self.tags.clone()),
+                ..self.clone()
+            };
+            Ok((first, second))
+        } else {
+            Err(anyhow!(
+                "split time ({}) is not strictly within the activity ({} - {})",
+                time,
+                self.start_time,
+                self.stop_time
+            ))
+        }
+    }
+
+    /// Merge this activity with `other`
+    ///
+    /// The merged activity spans from the earliest start to the latest stop, its tags are the union of both tags.
+    /// The first description found (self, then other) is kept.
+    /// Fails if activities neither overlap nor touch, unless `force` (the gap is absorbed).
+    pub fn merge(&self, other: &Activity, force: bool) -> anyhow::Result<Activity> {
+        let adjacent = self.stop_time == other.start_time || other.stop_time == self.start_time;
+        if !force && !adjacent && !self.overlaps(other) {
+            let gap = if self.stop_time < other.start_time {
+                other.start_time - self.stop_time
+            } else {
+                self.start_time - other.stop_time
+            };
+            return Err(anyhow!(
+                "activities are neither adjacent nor overlapping (gap: {}), use --force to merge anyway",
+                gap
+            ));
+        }
+        let mut tags = self.tags.clone();
+        for tag in &other.tags {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        Ok(Activity {
+            start_time: self.start_time.min(other.start_time),
+            stop_time: self.stop_time.max(other.stop_time),
+            tags,
+            description: self
+                .description
+                .clone()
+                .or_else(|| other.description.clone()),
+        })
+    }
+
+    /// Return a copy of this activity with the given description
+    pub fn annotate(&self, description: Option<Description>) -> Activity {
+        Activity {
+            description,
+            ..self.clone()
+        }
+    }
+
+    /// Rename tag `old` to `new`
+    ///
+    /// Returns None if the activity is not tagged with `old`.
+    pub fn rename_tag(&self, old: &str, new: &str) -> Option<Activity> {
+        renamed_tags(&self.tags, old, new).map(|tags| Activity {
+            tags,
+            ..self.clone()
+        })
+    }
+
+    /// Add tags `add` (unless already present) then remove tags `remove`
+    ///
+    /// Returns None if the tags are unchanged.
+    pub fn retag(&self, add: &[Tag], remove: &[Tag]) -> Option<Activity> {
+        let mut tags = self.tags.clone();
+        for tag in add {
+            if !tags.contains(tag) {
+                tags.push(tag.clone());
+            }
+        }
+        tags.retain(|tag| !remove.contains(tag));
+        if tags == self.tags {
+            None
+        } else {
+            Some(Activity {
+                tags,
+                ..self.clone()
+            })
+        }
+    }
 }
 
-/// Activities are sorted by start time
+/// Activities are sorted by start time, then stop time, then tags
+///
+/// Description comes last so that the ordering is consistent with `Eq`.
 impl Ord for Activity {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.get_start_time().cmp(&other.get_start_time())
+        self.start_time
+            .cmp(&other.start_time)
+            .then_with(|| self.stop_time.cmp(&other.stop_time))
+            .then_with(|| self.tags.cmp(&other.tags))
+            .then_with(|| self.description.cmp(&other.description))
     }
 }
 
@@ -61,6 +246,34 @@ impl PartialOrd for Activity {
     }
 }
 
+/// Key used to order activities e.g. in `summary`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityOrder {
+    /// start time
+    Start,
+    /// duration
+    Duration,
+    /// tags (lexicographic)
+    Tags,
+}
+
+impl ActivityOrder {
+    /// Compare two activities on this key only
+    ///
+    /// Activities with equal keys compare equal so a stable sort preserves their order.
+    pub fn compare(self, activity: &Activity, other: &Activity) -> Ordering {
+        match self {
+            ActivityOrder::Start => activity.start_time.cmp(&other.start_time),
+            ActivityOrder::Duration => {
+                let duration: chrono::Duration = activity.get_duration().into();
+                let other_duration: chrono::Duration = other.get_duration().into();
+                duration.cmp(&other_duration)
+            }
+            ActivityOrder::Tags => activity.tags.cmp(&other.tags),
+        }
+    }
+}
+
 /// A started and unfinished activity (no stop time)
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OngoingActivity {
@@ -72,10 +285,13 @@ pub struct OngoingActivity {
     pub description: Option<Description>,
 }
 
-/// OngoingActivities are sorted by start time
+/// OngoingActivities are sorted by start time, then tags (then description, consistent with `Eq`)
 impl Ord for OngoingActivity {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.get_start_time().cmp(&other.get_start_time())
+        self.start_time
+            .cmp(&other.start_time)
+            .then_with(|| self.tags.cmp(&other.tags))
+            .then_with(|| self.description.cmp(&other.description))
     }
 }
 
@@ -102,9 +318,34 @@ impl OngoingActivity {
     pub fn get_title(&self) -> String {
         self.tags.join(" ")
     }
+    /// Return time elapsed since start time
+    ///
+    /// Zero if `now` precedes start time.
+    pub fn elapsed(&self, now: DateTimeW) -> DurationW {
+        if now < self.start_time {
+            chrono::Duration::zero().into()
+        } else {
+            now - self.start_time
+        }
+    }
+    /// True if activity has all `tags`
+    ///
+    /// Always true if `tags` is empty.
+    pub fn has_tags(&self, tags: &[Tag]) -> bool {
+        tags.iter().all(|t| self.tags.contains(t))
+    }
+    /// Rename tag `old` to `new`
+    ///
+    /// Returns None if the activity is not tagged with `old`.
+    pub fn rename_tag(&self, old: &str, new: &str) -> Option<OngoingActivity> {
+        renamed_tags(&self.tags, old, new).map(|tags| OngoingActivity {
+            tags,
+            ..self.clone()
+        })
+    }
     /// Convert active activity to finished activity
     /// `stop_time` should be >= `start_time` otherwise error
-    pub fn into_activity(self, stop_time: DateTimeW) -> anyhow::Result<Activity> {
+    pub fn into_activity(self, stop_time: DateTimeW) -> RtwResult<Activity> {
         if self.start_time <= stop_time {
             Ok(Activity {
                 start_time: self.start_time,
@@ -113,15 +354,31 @@ impl OngoingActivity {
                 description: self.description,
             })
         } else {
-            Err(anyhow!(
-                "stop time ({}) < start_time ({})",
-                stop_time,
-                self.start_time
-            ))
+            Err(RtwError::InvalidTimeRange(format!(
+                "stop time ({}) is before start time ({})",
+                stop_time, self.start_time
+            )))
         }
     }
 }
 
+/// Replace `old` by `new` in `tags`, `new` is not duplicated
+///
+/// Returns None if `old` is not in `tags`.
+fn renamed_tags(tags: &[Tag], old: &str, new: &str) -> Option<Tags> {
+    if !tags.iter().any(|tag| tag == old) {
+        return None;
+    }
+    let mut renamed: Tags = vec![];
+    for tag in tags {
+        let tag = if tag == old { new } else { tag.as_str() };
+        if !renamed.iter().any(|t| t == tag) {
+            renamed.push(tag.to_string());
+        }
+    }
+    Some(renamed)
+}
+
 /// Check intersection between a finished activity and a date
 ///
 /// Returns Some(activity) if it intersects else None.
@@ -137,17 +394,94 @@ pub fn intersect(finished: &Activity, datetimew: &DateTimeW) -> Option<Activity>
 ///
 /// Returns Some(first) if first activity overlaps with the second else None.
 pub fn overlap(finished: &Activity, other: &Activity) -> Option<Activity> {
-    if finished < other {
-        intersect(finished, &other.start_time)
+    if finished.overlaps(other) {
+        Some(finished.clone())
     } else {
-        intersect(other, &finished.start_time).map(|_| finished.clone())
+        None
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::rtw_core::activity::{intersect, overlap, Activity};
-    use chrono::{Local, TimeZone};
+    use crate::rtw_core::activity::{
+        intersect, overlap, renamed_tags, Activity, ActivityOrder, OngoingActivity,
+    };
+    use crate::rtw_core::test_utils::{activity, datetimew};
+    use chrono::{Duration, Local, TimeZone};
+    use std::cmp::Ordering;
+
+    fn activity_at(start: (u32, u32), stop: (u32, u32)) -> Activity {
+        let day = Local.ymd(2020, 12, 25);
+        OngoingActivity::new(day.and_hms(start.0, start.1, 0).into(), vec![], None)
+            .into_activity(day.and_hms(stop.0, stop.1, 0).into())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_clipped_duration() {
+        let day = Local.ymd(2020, 12, 25);
+        let range_start = day.and_hms(10, 0, 0).into();
+        let range_end = day.and_hms(12, 0, 0).into();
+        let clipped = |start, stop| -> chrono::Duration {
+            activity_at(start, stop)
+                .clipped_duration(range_start, range_end)
+                .into()
+        };
+        // fully inside
+        assert_eq!(clipped((10, 30), (11, 0)), chrono::Duration::minutes(30));
+        // straddling range start
+        assert_eq!(clipped((9, 0), (11, 0)), chrono::Duration::hours(1));
+        // straddling range end
+        assert_eq!(clipped((11, 30), (13, 0)), chrono::Duration::minutes(30));
+        // outside
+        assert_eq!(clipped((8, 0), (9, 0)), chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_elapsed() {
+        let day = Local.ymd(2020, 12, 25);
+        let ongoing = OngoingActivity::new(day.and_hms(9, 0, 0).into(), vec![], None);
+        let elapsed: chrono::Duration = ongoing.elapsed(day.and_hms(9, 30, 0).into()).into();
+        assert_eq!(elapsed, chrono::Duration::minutes(30));
+    }
+
+    #[test]
+    fn test_elapsed_future_start_is_zero() {
+        let day = Local.ymd(2020, 12, 25);
+        let ongoing = OngoingActivity::new(day.and_hms(10, 0, 0).into(), vec![], None);
+        let elapsed: chrono::Duration = ongoing.elapsed(day.and_hms(9, 30, 0).into()).into();
+        assert_eq!(elapsed, chrono::Duration::zero());
+    }
+
+    #[test]
+    fn test_activity_order() {
+        let day = Local.ymd(2020, 12, 25);
+        let short = Activity {
+            start_time: day.and_hms(9, 0, 0).into(),
+            stop_time: day.and_hms(9, 30, 0).into(),
+            tags: vec!["foo".to_string()],
+            description: None,
+        };
+        let long = Activity {
+            start_time: day.and_hms(10, 0, 0).into(),
+            stop_time: day.and_hms(12, 0, 0).into(),
+            tags: vec!["bar".to_string()],
+            description: None,
+        };
+        assert_eq!(ActivityOrder::Start.compare(&short, &long), Ordering::Less);
+        assert_eq!(
+            ActivityOrder::Duration.compare(&short, &long),
+            Ordering::Less
+        );
+        assert_eq!(
+            ActivityOrder::Tags.compare(&short, &long),
+            Ordering::Greater
+        );
+        assert_eq!(
+            ActivityOrder::Duration.compare(&long, &long),
+            Ordering::Equal
+        );
+    }
 
     #[test]
     fn test_intersect() {
@@ -255,4 +589,313 @@ mod tests {
         };
         assert!(overlap(&finished, &other).is_none());
     }
+
+    #[test]
+    fn test_overlaps_touching_endpoints() {
+        let first = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        let second = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &[]);
+        assert!(!first.overlaps(&second));
+        assert!(!second.overlaps(&first));
+    }
+
+    #[test]
+    fn test_overlaps_containment() {
+        let outer = activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &[]);
+        let inner = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &[]);
+        assert!(outer.overlaps(&inner));
+        assert!(inner.overlaps(&outer));
+        // same start time
+        let same_start = activity("2020-12-25T09:00:00", "2020-12-25T09:30:00", &[]);
+        assert!(outer.overlaps(&same_start));
+        assert!(same_start.overlaps(&outer));
+        assert!(outer.overlaps(&outer));
+    }
+
+    #[test]
+    fn test_overlaps_partial() {
+        let first = activity("2020-12-25T09:00:00", "2020-12-25T10:30:00", &[]);
+        let second = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &[]);
+        assert!(first.overlaps(&second));
+        assert!(second.overlaps(&first));
+        let disjoint = activity("2020-12-25T11:30:00", "2020-12-25T12:00:00", &[]);
+        assert!(!first.overlaps(&disjoint));
+        assert!(!disjoint.overlaps(&first));
+    }
+
+    #[test]
+    fn test_modify() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        let unchanged = original.modify(None, None, None).unwrap();
+        assert_eq!(unchanged, original);
+        let new_stop = datetimew("2020-12-25T11:00:00");
+        let modified = original
+            .modify(None, Some(new_stop), Some(vec![String::from("foo")]))
+            .unwrap();
+        assert_eq!(modified.get_start_time(), original.get_start_time());
+        assert_eq!(modified.get_stop_time(), new_stop);
+        assert_eq!(modified.get_tags(), vec![String::from("foo")]);
+    }
+
+    #[test]
+    fn test_modify_zero_length_or_inverted() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        assert!(original
+            .modify(Some(original.get_stop_time()), None, None)
+            .is_err());
+        let late_start = datetimew("2020-12-25T10:30:00");
+        assert!(original.modify(Some(late_start), None, None).is_err());
+    }
+
+    #[test]
+    fn test_shift() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        assert_eq!(
+            original.shift(Duration::minutes(15)),
+            activity("2020-12-25T09:15:00", "2020-12-25T10:15:00", &["foo"])
+        );
+        assert_eq!(
+            original.shift(Duration::hours(-10)),
+            activity("2020-12-24T23:00:00", "2020-12-25T00:00:00", &["foo"])
+        );
+    }
+
+    #[test]
+    fn test_has_tags() {
+        let tagged = |tags: &[&str]| activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", tags);
+        let foo_bar = tagged(&["foo", "bar"]);
+        let bar_baz = tagged(&["bar", "baz"]);
+        let foo = vec![String::from("foo")];
+        let bar = vec![String::from("bar")];
+        let foo_baz = vec![String::from("foo"), String::from("baz")];
+        // (filter, any, foo bar matches, bar baz matches)
+        let table = vec![
+            (vec![], false, true, true),
+            (vec![], true, true, true),
+            (foo.clone(), false, true, false),
+            (foo, true, true, false),
+            (bar.clone(), false, true, true),
+            (bar, true, true, true),
+            (foo_baz.clone(), false, false, false),
+            (foo_baz, true, true, true),
+        ];
+        for (tags, any, foo_bar_expected, bar_baz_expected) in table {
+            assert_eq!(
+                foo_bar.has_tags(&tags, any),
+                foo_bar_expected,
+                "{:?} any: {}",
+                tags,
+                any
+            );
+            assert_eq!(
+                bar_baz.has_tags(&tags, any),
+                bar_baz_expected,
+                "{:?} any: {}",
+                tags,
+                any
+            );
+        }
+    }
+
+    #[test]
+    fn test_ongoing_has_tags() {
+        let ongoing = OngoingActivity::new(
+            Local
+                .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+            vec![String::from("foo"), String::from("bar")],
+            None,
+        );
+        assert!(ongoing.has_tags(&[]));
+        assert!(ongoing.has_tags(&[String::from("bar")]));
+        assert!(ongoing.has_tags(&[String::from("bar"), String::from("foo")]));
+        assert!(!ongoing.has_tags(&[String::from("foo"), String::from("baz")]));
+    }
+
+    #[test]
+    fn test_split() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let middle = datetimew("2020-12-25T09:30:00");
+        let (first, second) = original.split(middle, None).unwrap();
+        assert_eq!(first.get_start_time(), original.get_start_time());
+        assert_eq!(first.get_stop_time(), middle);
+        assert_eq!(second.get_start_time(), middle);
+        assert_eq!(second.get_stop_time(), original.get_stop_time());
+        assert_eq!(first.get_tags(), original.get_tags());
+        assert_eq!(second.get_tags(), original.get_tags());
+        let (first, second) = original
+            .split(middle, Some(vec![String::from("bar")]))
+            .unwrap();
+        assert_eq!(first.get_tags(), vec![String::from("foo")]);
+        assert_eq!(second.get_tags(), vec![String::from("bar")]);
+    }
+
+    #[test]
+    fn test_split_on_or_outside_boundaries() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        let before = datetimew("2020-12-25T08:00:00");
+        let after = datetimew("2020-12-25T10:30:00");
+        assert!(original.split(original.get_start_time(), None).is_err());
+        assert!(original.split(original.get_stop_time(), None).is_err());
+        assert!(original.split(before, None).is_err());
+        assert!(original.split(after, None).is_err());
+    }
+
+    #[test]
+    fn test_merge_adjacent() {
+        let first = activity(
+            "2020-12-25T09:00:00",
+            "2020-12-25T10:00:00",
+            &["foo", "bar"],
+        );
+        let second = activity(
+            "2020-12-25T10:00:00",
+            "2020-12-25T11:00:00",
+            &["bar", "baz"],
+        );
+        for merged in &[
+            first.merge(&second, false).unwrap(),
+            second.merge(&first, false).unwrap(),
+        ] {
+            assert_eq!(merged.get_start_time(), first.get_start_time());
+            assert_eq!(merged.get_stop_time(), second.get_stop_time());
+            assert_eq!(merged.get_tags().len(), 3);
+        }
+        assert_eq!(
+            first.merge(&second, false).unwrap().get_tags(),
+            vec![
+                String::from("foo"),
+                String::from("bar"),
+                String::from("baz")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_merge_overlapping() {
+        let first = activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &["foo"]);
+        let contained = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        let merged = first.merge(&contained, false).unwrap();
+        assert_eq!(merged.get_start_time(), first.get_start_time());
+        assert_eq!(merged.get_stop_time(), first.get_stop_time());
+        let partial = activity("2020-12-25T11:30:00", "2020-12-25T13:00:00", &["bar"]);
+        let merged = first.merge(&partial, false).unwrap();
+        assert_eq!(merged.get_start_time(), first.get_start_time());
+        assert_eq!(merged.get_stop_time(), partial.get_stop_time());
+    }
+
+    #[test]
+    fn test_merge_gap() {
+        let first = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let second = activity("2020-12-25T10:30:00", "2020-12-25T11:00:00", &["bar"]);
+        assert!(first.merge(&second, false).is_err());
+        assert!(second.merge(&first, false).is_err());
+        let merged = second.merge(&first, true).unwrap();
+        assert_eq!(merged.get_start_time(), first.get_start_time());
+        assert_eq!(merged.get_stop_time(), second.get_stop_time());
+        assert_eq!(
+            merged.get_tags(),
+            vec![String::from("bar"), String::from("foo")]
+        );
+    }
+
+    #[test]
+    fn test_annotate() {
+        let original = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        let annotated = original.annotate(Some(String::from("description")));
+        assert_eq!(
+            annotated.get_description(),
+            Some(String::from("description"))
+        );
+        assert_eq!(annotated.get_tags(), original.get_tags());
+        assert_eq!(annotated.get_start_time(), original.get_start_time());
+        assert_eq!(annotated.get_stop_time(), original.get_stop_time());
+    }
+
+    #[test]
+    fn test_renamed_tags() {
+        let tags = vec![String::from("foo"), String::from("bar")];
+        assert_eq!(renamed_tags(&tags, "baz", "qux"), None);
+        assert_eq!(
+            renamed_tags(&tags, "foo", "qux"),
+            Some(vec![String::from("qux"), String::from("bar")])
+        );
+        // no duplicated tag
+        assert_eq!(
+            renamed_tags(&tags, "foo", "bar"),
+            Some(vec![String::from("bar")])
+        );
+    }
+
+    #[test]
+    fn test_retag() {
+        let original = activity(
+            "2020-12-25T09:00:00",
+            "2020-12-25T10:00:00",
+            &["foo", "draft"],
+        );
+        let tags = |tags: &[&str]| tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        // add only, existing tags are not duplicated
+        assert_eq!(
+            original
+                .retag(&tags(&["billable", "foo"]), &[])
+                .map(|a| a.get_tags()),
+            Some(tags(&["foo", "draft", "billable"]))
+        );
+        // remove only
+        assert_eq!(
+            original.retag(&[], &tags(&["draft"])).map(|a| a.get_tags()),
+            Some(tags(&["foo"]))
+        );
+        // combined
+        assert_eq!(
+            original
+                .retag(&tags(&["billable"]), &tags(&["draft"]))
+                .map(|a| a.get_tags()),
+            Some(tags(&["foo", "billable"]))
+        );
+        // unchanged
+        assert_eq!(original.retag(&tags(&["foo"]), &tags(&["bar"])), None);
+    }
+
+    #[test]
+    fn test_sort_by_start_then_stop_then_tags() {
+        let long = activity("2020-12-25T09:00:00", "2020-12-25T11:00:00", &[]);
+        let short = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &[]);
+        let tagged = Activity {
+            tags: vec![String::from("foo")],
+            ..short.clone()
+        };
+        let earlier = activity("2020-12-25T08:00:00", "2020-12-25T12:00:00", &[]);
+        let mut activities = vec![long.clone(), tagged.clone(), short.clone(), earlier.clone()];
+        activities.sort();
+        assert_eq!(activities, vec![earlier, short, tagged, long]);
+    }
+
+    #[test]
+    fn test_sort_ongoing_by_start_then_tags() {
+        let start = Local
+            .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+            .unwrap();
+        let foo = OngoingActivity::new(start.into(), vec![String::from("foo")], None);
+        let bar = OngoingActivity::new(start.into(), vec![String::from("bar")], None);
+        let mut activities = vec![foo.clone(), bar.clone()];
+        activities.sort();
+        assert_eq!(activities, vec![bar, foo]);
+    }
+
+    #[test]
+    fn test_into_activity_stop_before_start() {
+        let start = Local
+            .datetime_from_str("2020-12-25T09:00:00", "%Y-%m-%dT%H:%M:%S")
+            .unwrap();
+        let ongoing = OngoingActivity::new(start.into(), vec![String::from("foo")], None);
+        let error = ongoing
+            .clone()
+            .into_activity((start - chrono::Duration::minutes(3)).into())
+            .unwrap_err();
+        assert!(error.to_string().contains("is before start time"));
+        // zero duration is accepted
+        assert!(ongoing.into_activity(start.into()).is_ok());
+    }
 }
@@ -1,6 +1,7 @@
 //! Clock abstraction
 
 use crate::rtw_core::datetimew::DateTimeW;
+use chrono::Weekday;
 
 /// Time (absolute or relative)
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -31,15 +32,15 @@ pub trait Clock {
 
     /// Get time range for last week
     ///
-    /// last week (ISO 8601, week start on monday)
+    /// last week, starting on `week_start` (ISO 8601: monday)
     ///
-    /// last week: monday: 00:00:00 - sunday: 23:59:59
-    fn last_week_range(&self) -> (DateTimeW, DateTimeW);
+    /// last week: first day: 00:00:00 - last day: 23:59:59
+    fn last_week_range(&self, week_start: Weekday) -> (DateTimeW, DateTimeW);
 
     /// Get time range for this week
     ///
-    /// this week (ISO 8601, week start on monday)
+    /// this week, starting on `week_start` (ISO 8601: monday)
     ///
-    /// this week: monday: 00:00:00 - sunday: 23:59:59
-    fn this_week_range(&self) -> (DateTimeW, DateTimeW);
+    /// this week: first day: 00:00:00 - last day: 23:59:59
+    fn this_week_range(&self, week_start: Weekday) -> (DateTimeW, DateTimeW);
 }
@@ -1,14 +1,26 @@
 //! Newtype on `chrono::Date<Local>`
 use crate::rtw_core::durationw::DurationW;
 use crate::rtw_core::DATETIME_FMT;
-use chrono::{DateTime, Local};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Duration, Local, NaiveDateTime, Offset, TimeZone, Timelike, Utc};
 use std::fmt::{Error, Formatter};
 
 use serde::{Deserialize, Serialize};
 
+/// Check a strftime format given to `DateTimeW::display_with`
+///
+/// Fails if the format is invalid.
+pub fn check_display_format(format: &str) -> anyhow::Result<()> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(anyhow::anyhow!("invalid date format: {}", format));
+    }
+    Ok(())
+}
+
 /// Newtype on `chrono::Date<Local>`
 ///
-/// Date is given in local time for convenience
+/// Date is given in local time for convenience, it is an absolute point in time:
+/// it is serialized with its UTC offset e.g. `2019-12-25T18:43:00+01:00`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct DateTimeW(DateTime<Local>);
 
@@ -32,8 +44,104 @@ impl std::ops::Sub for DateTimeW {
     }
 }
 
+// local time gaps (clocks moving forward) last at most a day e.g. Samoa skipping 2011-12-30
+const MAX_GAP_MINUTES: i64 = 2 * 24 * 60;
+
+// earliest instant displayed as `local` in the local time zone, None if `local` is skipped
+//
+// candidate offsets are the ones a day before, at and a day after `local`
+// (transitions are further apart), each candidate is checked by converting it back
+fn earliest_local(local: NaiveDateTime) -> Option<DateTime<Local>> {
+    [local - Duration::days(1), local, local + Duration::days(1)]
+        .iter()
+        .filter_map(|naive| Local.from_local_datetime(naive).earliest())
+        .map(|datetime| Local.from_utc_datetime(&(local - datetime.offset().fix())))
+        .filter(|datetime| datetime.naive_local() == local)
+        .min()
+}
+
+impl DateTimeW {
+    /// Local date and time in the local time zone
+    ///
+    /// Around daylight saving time transitions:
+    /// * a local time skipped when clocks move forward (e.g. 02:30 when 02:00 jumps to 03:00)
+    ///   is moved forward to the next valid local time (03:00), the returned flag is then true
+    /// * a local time occurring twice when clocks move back resolves to its earlier occurrence
+    pub fn from_local(local: NaiveDateTime) -> (DateTimeW, bool) {
+        if let Some(datetime) = earliest_local(local) {
+            return (DateTimeW(datetime), false);
+        }
+        let minute_start = local
+            .with_second(0)
+            .and_then(|naive| naive.with_nanosecond(0))
+            .unwrap_or(local);
+        let next = (1..=MAX_GAP_MINUTES)
+            .find_map(|minutes| earliest_local(minute_start + Duration::minutes(minutes)))
+            .expect("local time gap longer than 2 days");
+        (DateTimeW(next), true)
+    }
+
+    /// Format in UTC e.g. `2019-12-25T17:43:00Z`
+    pub fn to_utc_string(self) -> String {
+        format!("{}Z", self.0.with_timezone(&Utc).format(DATETIME_FMT))
+    }
+
+    /// Format in UTC (suffixed with `Z`) if `utc`,
+    /// else in local time using a checked strftime `format` (default `DATETIME_FMT`)
+    pub fn display_with(self, utc: bool, format: Option<&str>) -> String {
+        if utc {
+            self.to_utc_string()
+        } else {
+            self.0.format(format.unwrap_or(DATETIME_FMT)).to_string()
+        }
+    }
+}
+
+// local time using `DATETIME_FMT`, see `display_with` for the configured display
 impl std::fmt::Display for DateTimeW {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
         write!(f, "{}", self.0.format(DATETIME_FMT))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rtw_core::datetimew::DateTimeW;
+    use chrono::{DateTime, Local, NaiveDateTime};
+
+    fn from_rfc3339(s: &str) -> DateTimeW {
+        DateTime::parse_from_rfc3339(s)
+            .unwrap()
+            .with_timezone(&Local)
+            .into()
+    }
+
+    #[test]
+    fn test_to_utc_string() {
+        let d = from_rfc3339("2019-12-25T18:43:00+02:00");
+        assert_eq!(d.to_utc_string(), "2019-12-25T16:43:00Z");
+    }
+
+    #[test]
+    fn test_serialize_with_offset() {
+        let d = from_rfc3339("2019-12-25T18:43:00+02:00");
+        let serialized = serde_json::to_string(&d).unwrap();
+        let deserialized: DateTimeW = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, d);
+        // serialized dates carry their offset
+        let with_other_offset: DateTimeW =
+            serde_json::from_str("\"2019-12-25T17:43:00+01:00\"").unwrap();
+        assert_eq!(with_other_offset, d);
+    }
+
+    #[test]
+    fn test_from_local() {
+        // see integration tests (TZ=Europe/Paris) for daylight saving time transitions
+        let local =
+            NaiveDateTime::parse_from_str("2019-12-25T18:43:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+        let (d, moved) = DateTimeW::from_local(local);
+        let d: DateTime<Local> = d.into();
+        assert_eq!(d.naive_local(), local);
+        assert!(!moved);
+    }
+}
@@ -1,20 +1,50 @@
 //! Newtype on `chrono::Duration`
 use chrono::Duration;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::fmt::{Error, Formatter};
 
 /// Newtype on `chrono::Duration`
+#[derive(Clone, Copy)]
 pub struct DurationW(chrono::Duration);
 
+/// How durations are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DurationFormat {
+    /// `02:15:03`
+    #[default]
+    Clock,
+    /// `2h 15m 03s`
+    Human,
+    /// `2d 2h 0m` when at least a day, else `Human`
+    Days,
+    /// `2.25h`
+    Decimal,
+}
+
+/// Smallest unit displayed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DurationPrecision {
+    /// `00:02:30`
+    #[default]
+    Second,
+    /// `00:03`, rounded to the nearest minute
+    Minute,
+}
+
+/// How to round a duration to a multiple of N minutes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RoundMethod {
+    Up,
+    Nearest,
+    Down,
+}
+
+// `DurationFormat::Clock`, see `display_with` for the configured display
 impl fmt::Display for DurationW {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(
-            f,
-            "{:02}:{:02}:{:02}",
-            self.0.num_seconds() / 3600,
-            (self.0.num_seconds() / 60) % 60,
-            (self.0.num_seconds() % 60)
-        )
+        write!(f, "{}", self.display_with(DurationFormat::Clock))
     }
 }
 
@@ -22,6 +52,109 @@ impl DurationW {
     pub fn new(d: Duration) -> Self {
         DurationW(d)
     }
+
+    /// Format using `format` e.g. `02:15:03` for `DurationFormat::Clock`
+    pub fn display_with(&self, format: DurationFormat) -> String {
+        let seconds = self.0.num_seconds();
+        match format {
+            DurationFormat::Clock => format!(
+                "{:02}:{:02}:{:02}",
+                seconds / 3600,
+                (seconds / 60) % 60,
+                seconds % 60
+            ),
+            DurationFormat::Human => self.human(),
+            DurationFormat::Days => self.days_human(),
+            DurationFormat::Decimal => format!("{:.2}h", self.decimal_hours()),
+        }
+    }
+
+    /// Format using `format`, down to `precision` e.g. `00:03` for 2m 30s to the minute
+    ///
+    /// `DurationPrecision::Minute` rounds halfway durations up.
+    pub fn display_with_precision(
+        &self,
+        format: DurationFormat,
+        precision: DurationPrecision,
+    ) -> String {
+        if precision == DurationPrecision::Second {
+            return self.display_with(format);
+        }
+        let rounded = self.round_to(1, RoundMethod::Nearest);
+        let minutes = rounded.0.num_minutes();
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        match format {
+            DurationFormat::Clock => format!("{:02}:{:02}", hours, minutes),
+            DurationFormat::Days if hours >= 24 => rounded.days_human(),
+            DurationFormat::Human | DurationFormat::Days if hours > 0 => {
+                format!("{}h {:02}m", hours, minutes)
+            }
+            DurationFormat::Human | DurationFormat::Days => format!("{}m", minutes),
+            DurationFormat::Decimal => rounded.display_with(format),
+        }
+    }
+
+    /// Compact human form e.g. `2h 15m 03s`, `15m 03s`, `45s`
+    ///
+    /// Units smaller than the largest displayed unit are zero-padded.
+    pub fn human(&self) -> String {
+        let seconds = self.0.num_seconds();
+        let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+        if hours > 0 {
+            format!("{}h {:02}m {:02}s", hours, minutes, seconds)
+        } else if minutes > 0 {
+            format!("{}m {:02}s", minutes, seconds)
+        } else {
+            format!("{}s", seconds)
+        }
+    }
+
+    /// Days, hours and minutes e.g. `2d 2h 0m`, falls back to `human` below a day
+    pub fn days_human(&self) -> String {
+        let seconds = self.0.num_seconds();
+        let days = seconds / 86400;
+        if days > 0 {
+            format!(
+                "{}d {}h {}m",
+                days,
+                (seconds / 3600) % 24,
+                (seconds / 60) % 60
+            )
+        } else {
+            self.human()
+        }
+    }
+
+    /// Hours rounded to two decimal places e.g. `2.25` for 2h 15m
+    pub fn decimal_hours(&self) -> f64 {
+        let hours = self.0.num_seconds() as f64 / 3600.0;
+        (hours * 100.0).round() / 100.0
+    }
+
+    /// Round to a multiple of `minutes` using `method`
+    ///
+    /// Durations already on a multiple are unchanged, `minutes = 0` disables rounding.
+    ///
+    /// `RoundMethod::Nearest` rounds halfway durations up.
+    pub fn round_to(&self, minutes: u32, method: RoundMethod) -> DurationW {
+        if minutes == 0 {
+            return DurationW(self.0);
+        }
+        let step = i64::from(minutes) * 60;
+        let seconds = self.0.num_seconds();
+        let below = seconds - seconds.rem_euclid(step);
+        let rounded = if below == seconds {
+            seconds
+        } else {
+            match method {
+                RoundMethod::Up => below + step,
+                RoundMethod::Down => below,
+                RoundMethod::Nearest if 2 * (seconds - below) >= step => below + step,
+                RoundMethod::Nearest => below,
+            }
+        };
+        DurationW(Duration::seconds(rounded))
+    }
 }
 
 impl From<Duration> for DurationW {
@@ -35,3 +168,135 @@ impl Into<Duration> for DurationW {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::rtw_core::durationw::{DurationFormat, DurationPrecision, DurationW, RoundMethod};
+    use chrono::Duration;
+
+    fn rounded(seconds: i64, minutes: u32, method: RoundMethod) -> i64 {
+        let rounded: Duration = DurationW::new(Duration::seconds(seconds))
+            .round_to(minutes, method)
+            .into();
+        rounded.num_seconds()
+    }
+
+    #[test]
+    fn test_round_to() {
+        let table = vec![
+            // exactly on the multiple
+            (0, 15, RoundMethod::Up, 0),
+            (900, 15, RoundMethod::Up, 900),
+            (900, 15, RoundMethod::Nearest, 900),
+            (900, 15, RoundMethod::Down, 900),
+            // just above the multiple
+            (901, 15, RoundMethod::Up, 1800),
+            (901, 15, RoundMethod::Nearest, 900),
+            (901, 15, RoundMethod::Down, 900),
+            // halfway
+            (1350, 15, RoundMethod::Up, 1800),
+            (1350, 15, RoundMethod::Nearest, 1800),
+            (1350, 15, RoundMethod::Down, 900),
+            // just below halfway
+            (1349, 15, RoundMethod::Nearest, 900),
+            // no rounding
+            (901, 0, RoundMethod::Up, 901),
+        ];
+        for (seconds, minutes, method, expected) in table {
+            assert_eq!(
+                rounded(seconds, minutes, method),
+                expected,
+                "{}s rounded to {}min {:?}",
+                seconds,
+                minutes,
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_with_precision() {
+        let table = vec![
+            (150, DurationFormat::Clock, "00:02:30", "00:03"),
+            (149, DurationFormat::Clock, "00:02:29", "00:02"),
+            (8103, DurationFormat::Clock, "02:15:03", "02:15"),
+            (150, DurationFormat::Human, "2m 30s", "3m"),
+            (8103, DurationFormat::Human, "2h 15m 03s", "2h 15m"),
+            (8103, DurationFormat::Days, "2h 15m 03s", "2h 15m"),
+            (90030, DurationFormat::Days, "1d 1h 0m", "1d 1h 1m"),
+            (8130, DurationFormat::Decimal, "2.26h", "2.27h"),
+        ];
+        for (seconds, format, second, minute) in table {
+            let duration = DurationW::new(Duration::seconds(seconds));
+            assert_eq!(
+                duration.display_with_precision(format, DurationPrecision::Second),
+                second,
+                "{}s {:?}",
+                seconds,
+                format
+            );
+            assert_eq!(
+                duration.display_with_precision(format, DurationPrecision::Minute),
+                minute,
+                "{}s {:?}",
+                seconds,
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_human() {
+        let table = vec![
+            (0, "0s"),
+            (45, "45s"),
+            (60, "1m 00s"),
+            (903, "15m 03s"),
+            (8103, "2h 15m 03s"),
+            (90000, "25h 00m 00s"),
+        ];
+        for (seconds, expected) in table {
+            assert_eq!(DurationW::new(Duration::seconds(seconds)).human(), expected);
+        }
+    }
+
+    #[test]
+    fn test_days_human() {
+        let table = vec![
+            (0, "0s"),
+            (8103, "2h 15m 03s"),
+            (86399, "23h 59m 59s"),
+            (86400, "1d 0h 0m"),
+            (90000, "1d 1h 0m"),
+            (180000, "2d 2h 0m"),
+        ];
+        for (seconds, expected) in table {
+            assert_eq!(
+                DurationW::new(Duration::seconds(seconds)).days_human(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_decimal_hours() {
+        let table = vec![
+            (0, 0.0),
+            (45, 0.01),
+            (8100, 2.25),
+            (8103, 2.25),
+            // 1h 20m = 1.333...
+            (4800, 1.33),
+            // 1h 40m = 1.666...
+            (6000, 1.67),
+        ];
+        for (seconds, expected) in table {
+            assert_eq!(
+                DurationW::new(Duration::seconds(seconds)).decimal_hours(),
+                expected,
+                "{}s",
+                seconds
+            );
+        }
+    }
+}
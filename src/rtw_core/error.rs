@@ -0,0 +1,32 @@
+//! Errors of the core operations.
+use crate::rtw_core::ActivityId;
+use itertools::Itertools;
+use thiserror::Error;
+
+/// Core operations error, match on the variant to handle a failure kind
+#[derive(Error, Debug)]
+pub enum RtwError {
+    /// No finished activity with this id
+    #[error("no activity found for id {0}")]
+    NotFound(ActivityId),
+    /// An activity (title) would overlap finished activities (ids) while overlapping is denied
+    #[error("{0} would overlap activities with ids: {}", .1.iter().join(", "))]
+    Overlap(String, Vec<ActivityId>),
+    /// Several ongoing activities while overlapping is denied
+    #[error("multiple ongoing activities but overlapping is disabled")]
+    MultipleOngoing,
+    /// Stop time before start time
+    #[error("{0}")]
+    InvalidTimeRange(String),
+    #[error("storage io error: {0}")]
+    Io(#[from] std::io::Error),
+    /// Invalid stored data
+    #[error("invalid storage data: {0}")]
+    Parse(String),
+    /// Other storage backend failure e.g. sqlite error
+    #[error("storage error: {0}")]
+    Storage(#[source] Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// `Result<T, RtwError>`
+pub type RtwResult<T> = Result<T, RtwError>;
@@ -3,8 +3,11 @@ pub mod activity;
 pub mod clock;
 pub mod datetimew;
 pub mod durationw;
+pub mod error;
 pub mod service;
 pub mod storage;
+#[cfg(test)]
+pub(crate) mod test_utils;
 
 /// Absolute dates are parsed and displayed using this format
 ///
@@ -15,7 +18,38 @@ pub const DATETIME_FMT: &str = "%Y-%m-%dT%H:%M:%S";
 pub type Tag = String;
 /// `Tags` = `Vec<Tag>`
 pub type Tags = Vec<Tag>;
+/// Split a `key:value` tag e.g. `project:alpha` into its key and value
+///
+/// None for plain tags, without `:`, with an empty value or with a key not starting with a letter
+/// (so that times such as `09:00` are not mistaken for tags).
+pub fn tag_key_value(tag: &str) -> Option<(&str, &str)> {
+    let separator = tag.find(':')?;
+    let (key, value) = (&tag[..separator], &tag[separator + 1..]);
+    if !key.starts_with(char::is_alphabetic) || value.is_empty() {
+        None
+    } else {
+        Some((key, value))
+    }
+}
+
 /// `ActivityId` = `usize`
 pub type ActivityId = usize;
 /// `Description` = `String`
 pub type Description = String;
+
+#[cfg(test)]
+mod tests {
+    use crate::rtw_core::tag_key_value;
+
+    #[test]
+    fn test_tag_key_value() {
+        assert_eq!(tag_key_value("project:alpha"), Some(("project", "alpha")));
+        // split at the first colon
+        assert_eq!(tag_key_value("url:http://x"), Some(("url", "http://x")));
+        assert_eq!(tag_key_value("foo"), None);
+        assert_eq!(tag_key_value(":alpha"), None);
+        assert_eq!(tag_key_value("project:"), None);
+        assert_eq!(tag_key_value("09:00"), None);
+        assert_eq!(tag_key_value("2019-12-25T09:00:00"), None);
+    }
+}
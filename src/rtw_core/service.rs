@@ -1,82 +1,225 @@
 //! A service for activities: abstracts activities queries and modifications.
 use crate::rtw_core::activity::{Activity, OngoingActivity};
 use crate::rtw_core::datetimew::DateTimeW;
-use crate::rtw_core::ActivityId;
+use crate::rtw_core::error::RtwResult;
+use crate::rtw_core::{ActivityId, Description};
+use chrono::Duration;
+
+/// Outcome of stopping an ongoing activity: ongoing activity id, then stopped activity
+/// with its new finished activity id
+pub type StopOutcome = (ActivityId, RtwResult<(ActivityId, Activity)>);
 
 /// A service for activities
 ///
-/// Abstracts activities queries and modifications
+/// Abstracts activities queries and modifications, fails with a `RtwError`
 pub trait ActivityService {
     /// Get ongoing activities if any
     ///
     /// May fail depending on backend implementation
-    fn get_ongoing_activities(&self) -> anyhow::Result<Vec<(ActivityId, OngoingActivity)>>;
+    fn get_ongoing_activities(&self) -> RtwResult<Vec<(ActivityId, OngoingActivity)>>;
     /// Get ongoing activity with id if any
     ///
     /// May fail depending on backend implementation
-    fn get_ongoing_activity(&self, id: ActivityId) -> anyhow::Result<Option<OngoingActivity>>;
+    fn get_ongoing_activity(&self, id: ActivityId) -> RtwResult<Option<OngoingActivity>>;
     /// Start a new activity
     ///
     /// May fail depending on backend implementation
     ///
-    /// Returns new current activity and optionally the previously ongoing activity
+    /// Returns new current activity and optionally the previously ongoing activity,
+    /// stopped with its new finished activity id
+    ///
+    /// When `parallel` is true, ongoing activities are left untouched.
     fn start_activity(
         &mut self,
         activity: OngoingActivity,
         deny_overlapping: bool,
-    ) -> anyhow::Result<(OngoingActivity, Option<Activity>)>;
+        parallel: bool,
+    ) -> RtwResult<(OngoingActivity, Option<(ActivityId, Activity)>)>;
+    /// Find an ongoing activity duplicated by starting `activity`
+    ///
+    /// `activity` duplicates an ongoing activity with the same tags (in any order)
+    /// if it starts within `tolerance` of `now` (in whole seconds)
+    ///
+    /// May fail depending on backend implementation
+    fn find_duplicate_ongoing(
+        &self,
+        activity: &OngoingActivity,
+        now: DateTimeW,
+        tolerance: Duration,
+    ) -> RtwResult<Option<(ActivityId, OngoingActivity)>>;
+    /// Find the finished activity `activity` may be coalesced with
+    ///
+    /// That is the latest finished activity stopped at or before `activity` start,
+    /// if it has the same tags (in any order) and stopped within `tolerance` of `activity` start
+    ///
+    /// May fail depending on backend implementation
+    fn find_coalescable(
+        &self,
+        activity: &Activity,
+        tolerance: Duration,
+    ) -> RtwResult<Option<(ActivityId, Activity)>>;
+    /// Find a finished activity identical to `activity`
+    ///
+    /// That is a finished activity with the same start time, stop time and tags (in any order)
+    ///
+    /// May fail depending on backend implementation
+    fn find_duplicate_finished(&self, activity: &Activity) -> RtwResult<Option<ActivityId>>;
     /// Stop current activity
     ///
+    /// `description`, if any, replaces the description of the stopped activity
+    ///
     /// May fail depending on backend implementation
     ///
-    /// Returns stopped activity if any
+    /// Returns stopped activity with its new finished activity id if any
     fn stop_ongoing_activity(
         &mut self,
         time: DateTimeW,
         id: ActivityId,
         deny_overlapping: bool,
-    ) -> anyhow::Result<Option<Activity>>;
+        description: Option<Description>,
+    ) -> RtwResult<Option<(ActivityId, Activity)>>;
+    /// Stop all ongoing activities
+    ///
+    /// Keeps stopping remaining activities when stopping one of them fails
+    ///
+    /// Returns the outcome of each stop
+    fn stop_all_activities(
+        &mut self,
+        time: DateTimeW,
+        deny_overlapping: bool,
+    ) -> RtwResult<Vec<StopOutcome>>;
     /// Cancel current activity
     ///
     /// May fail depending on backend implementation
     ///
     /// Returns cancelled activity if any
-    fn cancel_ongoing_activity(
-        &mut self,
-        id: ActivityId,
-    ) -> anyhow::Result<Option<OngoingActivity>>;
+    fn cancel_ongoing_activity(&mut self, id: ActivityId) -> RtwResult<Option<OngoingActivity>>;
+    /// Replace ongoing activities with `activities` e.g. to undo a command
+    ///
+    /// Unlike `start_activity`, neither overlaps nor other ongoing activities are checked
+    ///
+    /// May fail depending on backend implementation
+    fn restore_ongoing_activities(&mut self, activities: Vec<OngoingActivity>) -> RtwResult<()>;
     /// Filter finished activities
     ///
     /// May fail depending on implementation
     ///
     /// Returns finished activities sorted by start date
     ///
-    /// ActivityId: 0 <=> last finished activity
-    fn filter_activities<P>(&self, p: P) -> anyhow::Result<Vec<(ActivityId, Activity)>>
+    /// ActivityId: stable id, deleting an activity does not change the ids of others
+    fn filter_activities<P>(&self, p: P) -> RtwResult<Vec<(ActivityId, Activity)>>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool;
+    /// Filter finished activities intersecting `range` (bounds included) and matching `p`
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns finished activities sorted by start date
+    fn filter_activities_within<P>(
+        &self,
+        range: (DateTimeW, DateTimeW),
+        p: P,
+    ) -> RtwResult<Vec<(ActivityId, Activity)>>
     where
         P: Fn(&(ActivityId, Activity)) -> bool;
+    /// Call `f` on each finished activity matching `p`, e.g. to fold totals
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Activities are not necessarily sorted by start date
+    fn for_each_activity<P, F>(&self, p: P, f: F) -> RtwResult<()>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity));
+    /// Get the latest stop time of finished activities
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns None if there is no finished activity
+    fn get_last_stop_time(&self) -> RtwResult<Option<DateTimeW>>;
     /// Get all finished activities
     ///
     /// May fail depending on implementation
     ///
     /// Returns finished activities sorted by start date
     ///
-    /// ActivityId: 0 <=> last finished activity
-    fn get_finished_activities(&self) -> anyhow::Result<Vec<(ActivityId, Activity)>>;
+    /// ActivityId: stable id, deleting an activity does not change the ids of others
+    fn get_finished_activities(&self) -> RtwResult<Vec<(ActivityId, Activity)>>;
+    /// Count finished activities
+    ///
+    /// May fail depending on implementation
+    fn count_activities(&self) -> RtwResult<usize>;
+    /// Get the `count` finished activities stopping last
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns finished activities sorted by stop date, latest first
+    fn get_last_activities(&self, count: usize) -> RtwResult<Vec<(ActivityId, Activity)>>;
+    /// Update finished activities
+    ///
+    /// `f` returns the updated activity, or None to keep the activity unchanged
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns ids of updated activities
+    fn update_activities<F>(&mut self, f: F) -> RtwResult<Vec<ActivityId>>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>;
+    /// Update ongoing activities
+    ///
+    /// `f` returns the updated activity, or None to keep the activity unchanged
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns ids of updated activities
+    fn update_ongoing_activities<F>(&mut self, f: F) -> RtwResult<Vec<ActivityId>>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>;
+    /// Get finished activity with id
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns None if there is no finished activity with id
+    fn get_activity_by_id(&self, id: ActivityId) -> RtwResult<Option<(ActivityId, Activity)>>;
     /// Delete activity with id
     ///
     /// May fail depending on implementation
     ///
-    /// Returns deleted activity if successful
-    fn delete_activity(&self, id: ActivityId) -> anyhow::Result<Option<Activity>>;
+    /// Returns deleted activity, fails with `RtwError::NotFound` if there is no activity with id
+    fn delete_activity(&self, id: ActivityId) -> RtwResult<Activity>;
+    /// Delete finished activities matching predicate
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns deleted activities with their ids
+    fn delete_activities<P>(&self, p: P) -> RtwResult<Vec<(ActivityId, Activity)>>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool;
     /// Track a finished activity
     ///
     /// May fail depending on backend implementation
     ///
-    /// Returns tracked activity if successful
+    /// Returns tracked activity with its id if successful
     fn track_activity(
         &mut self,
         activity: Activity,
         deny_overlapping: bool,
-    ) -> anyhow::Result<Activity>;
+    ) -> RtwResult<(ActivityId, Activity)>;
+    /// Track finished activities at once e.g. on import
+    ///
+    /// Overlaps are checked once against finished activities, nothing is tracked if one of
+    /// them is denied.
+    ///
+    /// May fail depending on backend implementation
+    ///
+    /// Returns tracked activities with their ids if successful
+    fn track_activities(
+        &mut self,
+        activities: Vec<Activity>,
+        deny_overlapping: bool,
+    ) -> RtwResult<Vec<(ActivityId, Activity)>>;
+    /// Restore a finished activity with its previous id e.g. to undo its deletion
+    ///
+    /// May fail depending on backend implementation
+    fn restore_activity(&mut self, id: ActivityId, activity: Activity) -> RtwResult<()>;
 }
@@ -1,40 +1,131 @@
 //! Storage: abstracts activities storage (file, memory...)
 use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::error::RtwError;
 use crate::rtw_core::ActivityId;
 use std::error::Error;
 
 pub trait Storage {
-    // see anyhow::Error type constraints
-    type StorageError: Error + Sync + Send + 'static;
+    // see anyhow::Error type constraints, converted to `RtwError` by the service
+    type StorageError: Error + Sync + Send + 'static + Into<RtwError>;
 
-    /// Write finished activity
+    /// Write finished activity with a new id
     ///
     /// May fail depending on backend implementation
-    fn write_activity(&mut self, activity: Activity) -> Result<(), Self::StorageError>;
+    ///
+    /// Returns the id of the written activity
+    fn write_activity(&mut self, activity: Activity) -> Result<ActivityId, Self::StorageError>;
+    /// Write finished activities with new ids, at once e.g. on import
+    ///
+    /// May fail depending on backend implementation
+    ///
+    /// Returns the ids of the written activities, in the same order
+    fn write_activities(
+        &mut self,
+        activities: Vec<Activity>,
+    ) -> Result<Vec<ActivityId>, Self::StorageError>;
+    /// Write finished activity with a previously assigned id e.g. to undo its deletion
+    ///
+    /// May fail depending on backend implementation
+    fn restore_activity(
+        &mut self,
+        id: ActivityId,
+        activity: Activity,
+    ) -> Result<(), Self::StorageError>;
     /// Filter finished activities
     ///
     /// May fail depending on implementation
     ///
     /// Returns finished activities sorted by start date
     ///
-    /// ActivityId: 0 <=> last finished activity
+    /// ActivityId: stable id, deleting an activity does not change the ids of others
     fn filter_activities<P>(&self, p: P) -> Result<Vec<(ActivityId, Activity)>, Self::StorageError>
     where
         P: Fn(&(ActivityId, Activity)) -> bool;
+    /// Filter finished activities intersecting `range` (bounds included) and matching `p`
+    ///
+    /// Backends may use `range` to read only the activities within it (see SQLite indexes)
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns finished activities sorted by start date
+    fn filter_activities_within<P>(
+        &self,
+        (range_start, range_end): (DateTimeW, DateTimeW),
+        p: P,
+    ) -> Result<Vec<(ActivityId, Activity)>, Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        self.filter_activities(|activity| {
+            let (_id, a) = activity;
+            a.get_start_time() <= range_end && range_start <= a.get_stop_time() && p(activity)
+        })
+    }
+    /// Call `f` on each finished activity matching `p`
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Unlike `filter_activities`, activities may be streamed (depending on implementation):
+    /// they are not necessarily sorted by start date
+    fn for_each_activity<P, F>(&self, p: P, f: F) -> Result<(), Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity));
     /// Get all finished activities
     ///
     /// May fail depending on implementation
     ///
     /// Returns finished activities sorted by start date
     ///
-    /// ActivityId: 0 <=> last finished activity
+    /// ActivityId: stable id, deleting an activity does not change the ids of others
     fn get_finished_activities(&self) -> Result<Vec<(ActivityId, Activity)>, Self::StorageError>;
+    /// Count finished activities
+    ///
+    /// Backends may count without reading activities (see SQLite `COUNT(*)`)
+    ///
+    /// May fail depending on implementation
+    fn count_activities(&self) -> Result<usize, Self::StorageError>;
+    /// Get the `count` finished activities stopping last
+    ///
+    /// Backends may read only these activities (see SQLite `ORDER BY stop DESC LIMIT`)
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns finished activities sorted by stop date, latest first (then by id, latest first)
+    fn get_last_activities(
+        &self,
+        count: usize,
+    ) -> Result<Vec<(ActivityId, Activity)>, Self::StorageError> {
+        let mut activities = self.get_finished_activities()?;
+        activities.sort_by(|(id, a), (other_id, other)| {
+            (other.get_stop_time(), other_id).cmp(&(a.get_stop_time(), id))
+        });
+        activities.truncate(count);
+        Ok(activities)
+    }
+    /// Get finished activity with id
+    ///
+    /// May fail depending on implementation
+    ///
+    /// ActivityId: stable id, deleting an activity does not change the ids of others
+    fn get_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError>;
     /// Delete activity with id
     ///
     /// May fail depending on implementation
     ///
     /// Returns deleted activity if successful
     fn delete_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError>;
+    /// Update finished activities
+    ///
+    /// `f` returns the updated activity, or None to keep the activity unchanged
+    ///
+    /// May fail depending on implementation
+    ///
+    /// Returns ids of updated activities
+    fn update_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>;
     /// Retrieve ongoing activities if any
     ///
     /// May fail depending on backend implementation
@@ -60,4 +151,14 @@ pub trait Storage {
         &mut self,
         id: ActivityId,
     ) -> Result<Option<OngoingActivity>, Self::StorageError>;
+    /// Update ongoing activities
+    ///
+    /// `f` returns the updated activity, or None to keep the activity unchanged
+    ///
+    /// May fail depending on backend implementation
+    ///
+    /// Returns ids of updated activities
+    fn update_ongoing_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>;
 }
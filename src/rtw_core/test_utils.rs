@@ -0,0 +1,56 @@
+//! Fixtures shared by unit tests.
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::clock::{Clock, Time};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::DATETIME_FMT;
+use chrono::{Local, TimeZone, Weekday};
+
+/// Local date e.g. `2020-12-25T09:00:00`
+pub fn datetimew(s: &str) -> DateTimeW {
+    Local.datetime_from_str(s, DATETIME_FMT).unwrap().into()
+}
+
+/// Finished activity from `start` to `stop` (local dates) tagged with `tags`
+pub fn activity(start: &str, stop: &str, tags: &[&str]) -> Activity {
+    OngoingActivity::new(
+        datetimew(start),
+        tags.iter().map(|t| t.to_string()).collect(),
+        None,
+    )
+    .into_activity(datetimew(stop))
+    .unwrap()
+}
+
+/// Clock stuck at `now`, ranges are not supported
+pub struct FixedClock {
+    pub now: DateTimeW,
+}
+
+impl Clock for FixedClock {
+    fn get_time(&self) -> DateTimeW {
+        self.now
+    }
+
+    fn date_time(&self, time: Time) -> DateTimeW {
+        match time {
+            Time::Now => self.get_time(),
+            Time::DateTime(abs_time) => abs_time,
+        }
+    }
+
+    fn today_range(&self) -> (DateTimeW, DateTimeW) {
+        unimplemented!()
+    }
+
+    fn yesterday_range(&self) -> (DateTimeW, DateTimeW) {
+        unimplemented!()
+    }
+
+    fn last_week_range(&self, _week_start: Weekday) -> (DateTimeW, DateTimeW) {
+        unimplemented!()
+    }
+
+    fn this_week_range(&self, _week_start: Weekday) -> (DateTimeW, DateTimeW) {
+        unimplemented!()
+    }
+}
@@ -1,10 +1,12 @@
 //! Logic above an activity storage
 use crate::rtw_core::activity::{intersect, overlap, Activity, OngoingActivity};
 use crate::rtw_core::datetimew::DateTimeW;
-use crate::rtw_core::service::ActivityService;
+use crate::rtw_core::error::{RtwError, RtwResult};
+use crate::rtw_core::service::{ActivityService, StopOutcome};
 use crate::rtw_core::storage::Storage;
-use crate::rtw_core::ActivityId;
-use anyhow::anyhow;
+use crate::rtw_core::{ActivityId, Description, Tag, Tags};
+use chrono::{DateTime, Duration, Local};
+use itertools::Itertools;
 
 pub struct Service<S>
 where
@@ -20,135 +22,355 @@ where
     pub fn new(storage: S) -> Self {
         Service { storage }
     }
+
+    // candidates for overlap checks, only activities within the range are read
+    fn finished_within(
+        &self,
+        range: (DateTimeW, DateTimeW),
+    ) -> RtwResult<Vec<(ActivityId, Activity)>> {
+        self.storage
+            .filter_activities_within(range, |_| true)
+            .map_err(Into::into)
+    }
 }
 
 impl<S> ActivityService for Service<S>
 where
     S: Storage,
 {
-    fn get_ongoing_activities(&self) -> anyhow::Result<Vec<(ActivityId, OngoingActivity)>> {
-        self.storage.get_ongoing_activities().map_err(|e| e.into())
+    fn get_ongoing_activities(&self) -> RtwResult<Vec<(ActivityId, OngoingActivity)>> {
+        self.storage.get_ongoing_activities().map_err(Into::into)
     }
 
-    fn get_ongoing_activity(&self, id: ActivityId) -> anyhow::Result<Option<OngoingActivity>> {
-        self.storage.get_ongoing_activity(id).map_err(|e| e.into())
+    fn get_ongoing_activity(&self, id: ActivityId) -> RtwResult<Option<OngoingActivity>> {
+        self.storage.get_ongoing_activity(id).map_err(Into::into)
     }
 
     fn start_activity(
         &mut self,
         activity: OngoingActivity,
         deny_overlapping: bool,
-    ) -> anyhow::Result<(OngoingActivity, Option<Activity>)> {
-        let finished = self.storage.get_finished_activities()?;
+        parallel: bool,
+    ) -> RtwResult<(OngoingActivity, Option<(ActivityId, Activity)>)> {
         if deny_overlapping {
+            let finished = self.finished_within((activity.start_time, activity.start_time))?;
             let intersections = time_intersections(finished.as_slice(), &activity.start_time);
             if intersections.is_empty() {
-                let ongoing_activities = self.storage.get_ongoing_activities()?;
-                match ongoing_activities.as_slice() {
-                    [] => {
-                        self.storage.add_ongoing_activity(activity.clone())?;
+                let ongoing_activities =
+                    self.storage.get_ongoing_activities().map_err(Into::into)?;
+                match (parallel, ongoing_activities.as_slice()) {
+                    (true, _) | (false, []) => {
+                        self.storage
+                            .add_ongoing_activity(activity.clone())
+                            .map_err(Into::into)?;
                         Ok((activity, None))
                     }
-                    [(ongoing_id, _ongoing)] => {
-                        let stopped_maybe =
-                            self.stop_ongoing_activity(activity.start_time, *ongoing_id, true)?;
-                        self.storage.add_ongoing_activity(activity.clone())?;
+                    (false, [(ongoing_id, _ongoing)]) => {
+                        let stopped_maybe = self.stop_ongoing_activity(
+                            activity.start_time,
+                            *ongoing_id,
+                            true,
+                            None,
+                        )?;
+                        self.storage
+                            .add_ongoing_activity(activity.clone())
+                            .map_err(Into::into)?;
                         Ok((activity, stopped_maybe))
                     }
-                    _ => Err(anyhow!(
-                        "multiple ongoing activities but overlapping is disabled"
-                    )),
+                    (false, _) => Err(RtwError::MultipleOngoing),
                 }
             } else {
-                Err(anyhow!("{:?} would overlap {:?}", activity, intersections))
+                Err(RtwError::Overlap(activity.get_title(), intersections))
             }
         } else {
-            self.storage.add_ongoing_activity(activity.clone())?;
+            self.storage
+                .add_ongoing_activity(activity.clone())
+                .map_err(Into::into)?;
             Ok((activity, None))
         }
     }
 
+    fn find_duplicate_ongoing(
+        &self,
+        activity: &OngoingActivity,
+        now: DateTimeW,
+        tolerance: Duration,
+    ) -> RtwResult<Option<(ActivityId, OngoingActivity)>> {
+        let now: DateTime<Local> = now.into();
+        let start_time: DateTime<Local> = activity.start_time.into();
+        if (now - start_time).num_seconds().abs() > tolerance.num_seconds() {
+            return Ok(None);
+        }
+        let tags: Vec<&Tag> = activity.tags.iter().sorted().collect();
+        let ongoing_activities = self.storage.get_ongoing_activities().map_err(Into::into)?;
+        Ok(ongoing_activities
+            .into_iter()
+            .find(|(_id, ongoing)| ongoing.tags.iter().sorted().collect::<Vec<&Tag>>() == tags))
+    }
+
+    fn find_coalescable(
+        &self,
+        activity: &Activity,
+        tolerance: Duration,
+    ) -> RtwResult<Option<(ActivityId, Activity)>> {
+        let start_time = activity.get_start_time();
+        let previous = self
+            .storage
+            .filter_activities(|(_id, a)| a.get_stop_time() <= start_time)
+            .map_err(Into::into)?
+            .into_iter()
+            .max_by_key(|(_id, a)| a.get_stop_time());
+        let tags: Tags = activity.get_tags().into_iter().sorted().collect();
+        Ok(previous.filter(|(_id, previous)| {
+            let gap: Duration = (start_time - previous.get_stop_time()).into();
+            gap <= tolerance && previous.get_tags().into_iter().sorted().collect::<Tags>() == tags
+        }))
+    }
+
+    fn find_duplicate_finished(&self, activity: &Activity) -> RtwResult<Option<ActivityId>> {
+        let tags: Tags = activity.get_tags().into_iter().sorted().collect();
+        Ok(self
+            .finished_within((activity.get_start_time(), activity.get_stop_time()))?
+            .into_iter()
+            .find(|(_id, finished)| {
+                finished.get_start_time() == activity.get_start_time()
+                    && finished.get_stop_time() == activity.get_stop_time()
+                    && finished.get_tags().into_iter().sorted().collect::<Tags>() == tags
+            })
+            .map(|(id, _finished)| id))
+    }
+
     fn stop_ongoing_activity(
         &mut self,
         time: DateTimeW,
         id: ActivityId,
         deny_overlapping: bool,
-    ) -> anyhow::Result<Option<Activity>> {
-        let stopped_maybe = self.storage.get_ongoing_activity(id)?;
+        description: Option<Description>,
+    ) -> RtwResult<Option<(ActivityId, Activity)>> {
+        let stopped_maybe = self.storage.get_ongoing_activity(id).map_err(Into::into)?;
         match stopped_maybe {
             None => Ok(None),
             Some(ongoing_activity) => {
-                let stopped = ongoing_activity.clone().into_activity(time)?;
-                let finished = self.storage.get_finished_activities()?;
-                let intersections = activity_intersections(finished.as_slice(), &stopped);
+                let ongoing_activity = match description {
+                    None => ongoing_activity,
+                    Some(description) => OngoingActivity {
+                        description: Some(description),
+                        ..ongoing_activity
+                    },
+                };
+                let stopped = ongoing_activity.into_activity(time)?;
+                let finished =
+                    self.finished_within((stopped.get_start_time(), stopped.get_stop_time()))?;
+                let intersections = overlapping_ids(finished.as_slice(), &stopped);
                 if !deny_overlapping || intersections.is_empty() {
-                    self.storage.write_activity(stopped)?;
-                    self.storage.remove_ongoing_activity(id)?;
-                    Ok(Some(ongoing_activity.into_activity(time)?))
+                    let stopped_id = self
+                        .storage
+                        .write_activity(stopped.clone())
+                        .map_err(Into::into)?;
+                    self.storage
+                        .remove_ongoing_activity(id)
+                        .map_err(Into::into)?;
+                    Ok(Some((stopped_id, stopped)))
                 } else {
-                    Err(anyhow!("{:?} would overlap {:?}", stopped, intersections))
+                    Err(RtwError::Overlap(stopped.get_title(), intersections))
                 }
             }
         }
     }
 
-    fn cancel_ongoing_activity(
+    fn stop_all_activities(
         &mut self,
-        id: ActivityId,
-    ) -> anyhow::Result<Option<OngoingActivity>> {
+        time: DateTimeW,
+        deny_overlapping: bool,
+    ) -> RtwResult<Vec<StopOutcome>> {
+        let ongoing_activities = self.storage.get_ongoing_activities().map_err(Into::into)?;
+        // stop from the highest id: stopping an activity shifts the ids of later ones
+        Ok(ongoing_activities
+            .iter()
+            .rev()
+            .filter_map(|(id, _ongoing)| {
+                match self.stop_ongoing_activity(time, *id, deny_overlapping, None) {
+                    Ok(None) => None,
+                    Ok(Some(stopped)) => Some((*id, Ok(stopped))),
+                    Err(e) => Some((*id, Err(e))),
+                }
+            })
+            .collect())
+    }
+
+    fn cancel_ongoing_activity(&mut self, id: ActivityId) -> RtwResult<Option<OngoingActivity>> {
+        self.storage.remove_ongoing_activity(id).map_err(Into::into)
+    }
+
+    fn restore_ongoing_activities(&mut self, activities: Vec<OngoingActivity>) -> RtwResult<()> {
+        let ongoing_activities = self.storage.get_ongoing_activities().map_err(Into::into)?;
+        // remove from the highest id: removing an activity shifts the ids of later ones
+        for (id, _ongoing) in ongoing_activities.iter().rev() {
+            self.storage
+                .remove_ongoing_activity(*id)
+                .map_err(Into::into)?;
+        }
+        for activity in activities {
+            self.storage
+                .add_ongoing_activity(activity)
+                .map_err(Into::into)?;
+        }
+        Ok(())
+    }
+
+    fn filter_activities<P>(&self, p: P) -> RtwResult<Vec<(ActivityId, Activity)>>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        self.storage.filter_activities(p).map_err(Into::into)
+    }
+
+    fn filter_activities_within<P>(
+        &self,
+        range: (DateTimeW, DateTimeW),
+        p: P,
+    ) -> RtwResult<Vec<(ActivityId, Activity)>>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
         self.storage
-            .remove_ongoing_activity(id)
-            .map_err(|e| e.into())
+            .filter_activities_within(range, p)
+            .map_err(Into::into)
     }
 
-    fn filter_activities<P>(&self, p: P) -> anyhow::Result<Vec<(ActivityId, Activity)>>
+    fn for_each_activity<P, F>(&self, p: P, f: F) -> RtwResult<()>
     where
         P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity)),
     {
-        self.storage.filter_activities(p).map_err(|e| e.into())
+        self.storage.for_each_activity(p, f).map_err(Into::into)
+    }
+
+    fn get_last_stop_time(&self) -> RtwResult<Option<DateTimeW>> {
+        let mut last: Option<DateTimeW> = None;
+        self.for_each_activity(
+            |_| true,
+            |(_id, activity)| {
+                last = last.max(Some(activity.get_stop_time()));
+            },
+        )?;
+        Ok(last)
+    }
+
+    fn get_finished_activities(&self) -> RtwResult<Vec<(ActivityId, Activity)>> {
+        self.storage.get_finished_activities().map_err(Into::into)
     }
 
-    fn get_finished_activities(&self) -> anyhow::Result<Vec<(ActivityId, Activity)>> {
-        self.storage.get_finished_activities().map_err(|e| e.into())
+    fn count_activities(&self) -> RtwResult<usize> {
+        self.storage.count_activities().map_err(Into::into)
     }
 
-    fn delete_activity(&self, id: ActivityId) -> anyhow::Result<Option<Activity>> {
-        self.storage.delete_activity(id).map_err(|e| e.into())
+    fn get_last_activities(&self, count: usize) -> RtwResult<Vec<(ActivityId, Activity)>> {
+        self.storage.get_last_activities(count).map_err(Into::into)
+    }
+
+    fn update_activities<F>(&mut self, f: F) -> RtwResult<Vec<ActivityId>>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>,
+    {
+        self.storage.update_activities(f).map_err(Into::into)
+    }
+
+    fn update_ongoing_activities<F>(&mut self, f: F) -> RtwResult<Vec<ActivityId>>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>,
+    {
+        self.storage
+            .update_ongoing_activities(f)
+            .map_err(Into::into)
+    }
+
+    fn get_activity_by_id(&self, id: ActivityId) -> RtwResult<Option<(ActivityId, Activity)>> {
+        let activity = self.storage.get_activity(id).map_err(Into::into)?;
+        Ok(activity.map(|activity| (id, activity)))
+    }
+
+    fn delete_activity(&self, id: ActivityId) -> RtwResult<Activity> {
+        self.storage
+            .delete_activity(id)
+            .map_err(Into::into)?
+            .ok_or(RtwError::NotFound(id))
+    }
+
+    fn delete_activities<P>(&self, p: P) -> RtwResult<Vec<(ActivityId, Activity)>>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        let deleted = self.storage.filter_activities(p).map_err(Into::into)?;
+        for (id, _activity) in deleted.iter() {
+            self.storage.delete_activity(*id).map_err(Into::into)?;
+        }
+        Ok(deleted)
     }
 
     fn track_activity(
         &mut self,
         activity: Activity,
         deny_overlapping: bool,
-    ) -> anyhow::Result<Activity> {
-        let finished = self.storage.get_finished_activities()?;
-        let intersections = activity_intersections(finished.as_slice(), &activity);
-        if !deny_overlapping || intersections.is_empty() {
-            self.storage.write_activity(activity.clone())?;
-            Ok(activity)
+    ) -> RtwResult<(ActivityId, Activity)> {
+        let finished =
+            self.finished_within((activity.get_start_time(), activity.get_stop_time()))?;
+        let overlapping_ids = overlapping_ids(finished.as_slice(), &activity);
+        if !deny_overlapping || overlapping_ids.is_empty() {
+            let id = self
+                .storage
+                .write_activity(activity.clone())
+                .map_err(Into::into)?;
+            Ok((id, activity))
         } else {
-            Err(anyhow!("{:?} would overlap {:?}", activity, intersections))
+            Err(RtwError::Overlap(activity.get_title(), overlapping_ids))
         }
     }
+
+    fn track_activities(
+        &mut self,
+        activities: Vec<Activity>,
+        deny_overlapping: bool,
+    ) -> RtwResult<Vec<(ActivityId, Activity)>> {
+        if deny_overlapping {
+            let finished = self.storage.get_finished_activities().map_err(Into::into)?;
+            for activity in activities.iter() {
+                let overlapping_ids = overlapping_ids(finished.as_slice(), activity);
+                if !overlapping_ids.is_empty() {
+                    return Err(RtwError::Overlap(activity.get_title(), overlapping_ids));
+                }
+            }
+        }
+        let ids = self
+            .storage
+            .write_activities(activities.clone())
+            .map_err(Into::into)?;
+        Ok(ids.into_iter().zip(activities).collect())
+    }
+
+    fn restore_activity(&mut self, id: ActivityId, activity: Activity) -> RtwResult<()> {
+        self.storage
+            .restore_activity(id, activity)
+            .map_err(Into::into)
+    }
 }
 
-fn activity_intersections(
-    activities: &[(ActivityId, Activity)],
-    activity: &Activity,
-) -> Vec<Activity> {
+fn overlapping_ids(activities: &[(ActivityId, Activity)], activity: &Activity) -> Vec<ActivityId> {
     activities
         .iter()
-        .filter_map(|(_, a)| overlap(a, activity))
+        .filter(|(_, a)| overlap(a, activity).is_some())
+        .map(|(id, _)| *id)
         .collect()
 }
 
 fn time_intersections(
     activities: &[(ActivityId, Activity)],
     start_time: &DateTimeW,
-) -> Vec<Activity> {
+) -> Vec<ActivityId> {
     activities
         .iter()
-        .filter_map(|(_, a)| intersect(a, start_time))
+        .filter(|(_, a)| intersect(a, start_time).is_some())
+        .map(|(id, _)| *id)
         .collect()
 }
 
@@ -159,9 +381,12 @@ mod tests {
     use crate::rtw_core::activity::OngoingActivity;
     use crate::rtw_core::clock::Clock;
     use crate::rtw_core::datetimew::DateTimeW;
+    use crate::rtw_core::error::RtwError;
     use crate::rtw_core::service::ActivityService;
+    use crate::rtw_core::test_utils::activity;
+    use crate::rtw_core::ActivityId;
     use crate::service::Service;
-    use chrono::{Local, TimeZone};
+    use chrono::{DateTime, Duration, Local, TimeZone};
     use tempfile::{tempdir, TempDir};
 
     fn build_json_service(test_dir: &TempDir) -> Service<JsonStorage> {
@@ -172,22 +397,172 @@ mod tests {
 
     #[test]
     fn test_no_activity() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let test_dir = tempdir().expect("error while creating tempdir");
         let mut service = build_json_service(&test_dir);
         assert!(service
-            .stop_ongoing_activity(clock.get_time(), 0, true)
+            .stop_ongoing_activity(clock.get_time(), 0, true, None)
             .is_ok());
         assert!(service.get_ongoing_activities().unwrap().is_empty());
     }
 
+    #[test]
+    fn test_delete_missing_activity_not_found() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let service = build_json_service(&test_dir);
+        assert!(matches!(
+            service.delete_activity(42),
+            Err(RtwError::NotFound(42))
+        ));
+    }
+
+    #[test]
+    fn test_track_overlapping_activity_overlap() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        let activity = OngoingActivity::new(day.and_hms(9, 0, 0).into(), vec!["foo".into()], None)
+            .into_activity(day.and_hms(10, 0, 0).into())
+            .unwrap();
+        service.track_activity(activity.clone(), true).unwrap();
+        match service.track_activity(activity, true) {
+            Err(RtwError::Overlap(title, ids)) => {
+                assert_eq!(title, "foo");
+                assert_eq!(ids, vec![0]);
+            }
+            other => panic!("expected overlap, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_track_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        let activity = |start: u32, tag: &str| {
+            OngoingActivity::new(day.and_hms(start, 0, 0).into(), vec![tag.into()], None)
+                .into_activity(day.and_hms(start + 1, 0, 0).into())
+                .unwrap()
+        };
+        service.track_activity(activity(9, "foo"), true).unwrap();
+        let tracked = service
+            .track_activities(vec![activity(10, "bar"), activity(11, "baz")], true)
+            .unwrap();
+        assert_eq!(
+            tracked,
+            vec![(1, activity(10, "bar")), (2, activity(11, "baz"))]
+        );
+        assert!(matches!(
+            service.track_activities(vec![activity(12, "qux"), activity(9, "foo")], true),
+            Err(RtwError::Overlap(_, _))
+        ));
+        assert_eq!(service.get_finished_activities().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_find_duplicate_finished() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        service
+            .track_activity(
+                activity(
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T10:00:00",
+                    &["foo", "bar"],
+                ),
+                true,
+            )
+            .unwrap();
+        // tags in any order
+        assert_eq!(
+            service
+                .find_duplicate_finished(&activity(
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T10:00:00",
+                    &["bar", "foo"]
+                ))
+                .unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            service
+                .find_duplicate_finished(&activity(
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T10:00:00",
+                    &["foo"]
+                ))
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            service
+                .find_duplicate_finished(&activity(
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T09:30:00",
+                    &["foo", "bar"]
+                ))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_stop_before_start_invalid_time_range() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        service
+            .start_activity(
+                OngoingActivity::new(day.and_hms(10, 0, 0).into(), vec![], None),
+                true,
+                false,
+            )
+            .unwrap();
+        assert!(matches!(
+            service.stop_ongoing_activity(day.and_hms(9, 0, 0).into(), 0, true, None),
+            Err(RtwError::InvalidTimeRange(_))
+        ));
+    }
+
+    #[test]
+    fn test_stop_with_description() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        service
+            .start_activity(
+                OngoingActivity::new(day.and_hms(9, 0, 0).into(), vec!["foo".into()], None),
+                true,
+                false,
+            )
+            .unwrap();
+        let (_, stopped) = service
+            .stop_ongoing_activity(
+                day.and_hms(10, 0, 0).into(),
+                0,
+                true,
+                Some("finished the report".to_string()),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            stopped.get_description(),
+            Some("finished the report".to_string())
+        );
+        let finished = service.get_finished_activities().unwrap();
+        assert_eq!(
+            finished[0].1.get_description(),
+            Some("finished the report".to_string())
+        );
+    }
+
     #[test]
     fn test_start_activity() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let test_dir = tempdir().expect("error while creating tempdir");
         let mut service = build_json_service(&test_dir);
         assert!(service
-            .stop_ongoing_activity(clock.get_time(), 0, true)
+            .stop_ongoing_activity(clock.get_time(), 0, true, None)
             .is_ok());
         let start = service.start_activity(
             OngoingActivity {
@@ -196,6 +571,7 @@ mod tests {
                 description: None,
             },
             true,
+            false,
         );
         start.unwrap();
         let current = service.get_ongoing_activities();
@@ -205,7 +581,7 @@ mod tests {
 
     #[test]
     fn test_stop_activity_with_active() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let test_dir = tempdir().expect("error while creating tempdir");
         let mut service = build_json_service(&test_dir);
         let start = service.start_activity(
@@ -215,18 +591,19 @@ mod tests {
                 description: None,
             },
             true,
+            false,
         );
         start.unwrap();
         assert!(!service.get_ongoing_activities().unwrap().is_empty());
         assert!(service
-            .stop_ongoing_activity(clock.get_time(), 0, true)
+            .stop_ongoing_activity(clock.get_time(), 0, true, None)
             .is_ok());
         assert!(service.get_ongoing_activities().unwrap().is_empty());
     }
 
     #[test]
     fn test_start_stop_start() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let test_dir = tempdir().expect("error while creating tempdir");
         let mut service = build_json_service(&test_dir);
         let start_0 = service.start_activity(
@@ -236,10 +613,11 @@ mod tests {
                 description: None,
             },
             true,
+            false,
         );
         assert!(start_0.is_ok());
         assert!(!service.get_ongoing_activities().unwrap().is_empty());
-        let stop = service.stop_ongoing_activity(clock.get_time(), 0, true);
+        let stop = service.stop_ongoing_activity(clock.get_time(), 0, true, None);
         assert!(stop.is_ok());
         assert!(service.get_ongoing_activities().unwrap().is_empty());
         let start_1 = service.start_activity(
@@ -249,11 +627,104 @@ mod tests {
                 description: None,
             },
             true,
+            false,
         );
         assert!(start_1.is_ok());
         assert!(!service.get_ongoing_activities().unwrap().is_empty());
     }
 
+    #[test]
+    fn test_start_parallel_activities() {
+        let clock = ChronoClock::default();
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let (_started, stopped) = service
+            .start_activity(
+                OngoingActivity::new(clock.get_time(), vec![String::from("a")], None),
+                true,
+                false,
+            )
+            .unwrap();
+        assert!(stopped.is_none());
+        let (_started, stopped) = service
+            .start_activity(
+                OngoingActivity::new(clock.get_time(), vec![String::from("b")], None),
+                true,
+                true,
+            )
+            .unwrap();
+        assert!(stopped.is_none());
+        assert_eq!(service.get_ongoing_activities().unwrap().len(), 2);
+        assert!(service.get_finished_activities().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stop_all_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        for (start, tag) in &[("2020-12-25T09:00:00", "a"), ("2020-12-25T10:00:00", "b")] {
+            let _started = service
+                .start_activity(
+                    OngoingActivity::new(
+                        Local
+                            .datetime_from_str(start, "%Y-%m-%dT%H:%M:%S")
+                            .unwrap()
+                            .into(),
+                        vec![String::from(*tag)],
+                        None,
+                    ),
+                    true,
+                    true,
+                )
+                .unwrap();
+        }
+        let stop_time: DateTimeW = Local
+            .datetime_from_str("2020-12-25T11:00:00", "%Y-%m-%dT%H:%M:%S")
+            .unwrap()
+            .into();
+        let stopped = service.stop_all_activities(stop_time, false).unwrap();
+        assert_eq!(stopped.len(), 2);
+        assert!(stopped.iter().all(|(_id, result)| result.is_ok()));
+        assert!(service.get_ongoing_activities().unwrap().is_empty());
+        let finished = service.get_finished_activities().unwrap();
+        assert_eq!(finished.len(), 2);
+        assert!(finished
+            .iter()
+            .all(|(_id, a)| a.get_stop_time() == stop_time));
+    }
+
+    #[test]
+    fn test_stop_all_activities_continue_on_error() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let datetimew = |s: &str| -> DateTimeW {
+            Local
+                .datetime_from_str(s, "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into()
+        };
+        for (start, tag) in &[("2020-12-25T09:00:00", "a"), ("2020-12-25T12:00:00", "b")] {
+            let _started = service
+                .start_activity(
+                    OngoingActivity::new(datetimew(start), vec![String::from(*tag)], None),
+                    true,
+                    true,
+                )
+                .unwrap();
+        }
+        // b (id 1) cannot stop before it started, a (id 0) can
+        let stopped = service
+            .stop_all_activities(datetimew("2020-12-25T11:00:00"), false)
+            .unwrap();
+        assert_eq!(stopped.len(), 2);
+        assert!(matches!(stopped[0], (1, Err(_))));
+        assert!(matches!(stopped[1], (0, Ok(_))));
+        let ongoing = service.get_ongoing_activities().unwrap();
+        assert_eq!(ongoing.len(), 1);
+        assert_eq!(ongoing[0].1.tags, vec![String::from("b")]);
+        assert_eq!(service.get_finished_activities().unwrap().len(), 1);
+    }
+
     #[test]
     fn test_start_intersecting_activity() {
         let test_dir = tempdir().expect("error while creating tempdir");
@@ -283,7 +754,7 @@ mod tests {
             vec![],
             None,
         );
-        let started = service.start_activity(other, true);
+        let started = service.start_activity(other, true, false);
         assert!(started.is_err());
     }
 
@@ -316,7 +787,7 @@ mod tests {
             vec![],
             None,
         );
-        let started = service.start_activity(other, true);
+        let started = service.start_activity(other, true, false);
         assert!(started.is_ok());
         let stopped = service.stop_ongoing_activity(
             Local
@@ -325,13 +796,87 @@ mod tests {
                 .into(),
             0, // only one ongoing activity => id is 0
             true,
+            None,
         );
         assert!(stopped.is_err());
     }
 
+    #[test]
+    fn test_get_last_stop_time() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        assert_eq!(service.get_last_stop_time().unwrap(), None);
+        let day = Local.ymd(2020, 12, 25);
+        // the latest stop time wins, not the latest start time
+        for (start, stop) in &[((9, 0), (12, 0)), ((10, 0), (11, 0))] {
+            let activity =
+                OngoingActivity::new(day.and_hms(start.0, start.1, 0).into(), vec![], None)
+                    .into_activity(day.and_hms(stop.0, stop.1, 0).into())
+                    .unwrap();
+            service.track_activity(activity, false).unwrap();
+        }
+        let expected: DateTimeW = day.and_hms(12, 0, 0).into();
+        assert_eq!(service.get_last_stop_time().unwrap(), Some(expected));
+    }
+
+    #[test]
+    fn test_find_coalescable_adjacent_same_tags() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        let previous = OngoingActivity::new(
+            day.and_hms(9, 0, 0).into(),
+            vec!["foo".into(), "bar".into()],
+            None,
+        )
+        .into_activity(day.and_hms(10, 0, 0).into())
+        .unwrap();
+        service.track_activity(previous.clone(), true).unwrap();
+        let stopped = OngoingActivity::new(
+            day.and_hms(10, 0, 30).into(),
+            vec!["bar".into(), "foo".into()],
+            None,
+        )
+        .into_activity(day.and_hms(11, 0, 0).into())
+        .unwrap();
+        assert_eq!(
+            service
+                .find_coalescable(&stopped, Duration::seconds(60))
+                .unwrap(),
+            Some((0, previous))
+        );
+        // gap larger than the tolerance
+        assert_eq!(
+            service
+                .find_coalescable(&stopped, Duration::seconds(10))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_coalescable_adjacent_different_tags() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let day = Local.ymd(2020, 12, 25);
+        let previous = OngoingActivity::new(day.and_hms(9, 0, 0).into(), vec!["foo".into()], None)
+            .into_activity(day.and_hms(10, 0, 0).into())
+            .unwrap();
+        service.track_activity(previous, true).unwrap();
+        let stopped = OngoingActivity::new(day.and_hms(10, 0, 0).into(), vec!["bar".into()], None)
+            .into_activity(day.and_hms(11, 0, 0).into())
+            .unwrap();
+        assert_eq!(
+            service
+                .find_coalescable(&stopped, Duration::seconds(60))
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_summary_nothing() {
-        let clock = ChronoClock {};
+        let clock = ChronoClock::default();
         let test_dir = tempdir().expect("error while creating tempdir");
         let service = build_json_service(&test_dir);
         let (today_start, today_end) = clock.today_range();
@@ -426,4 +971,120 @@ mod tests {
         let tracked = service.track_activity(other, true);
         assert!(tracked.is_err());
     }
+
+    #[test]
+    fn test_delete_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let datetimew = |s: &str| -> DateTimeW {
+            Local
+                .datetime_from_str(s, "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into()
+        };
+        for (start, stop, tag) in &[
+            ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "a"),
+            ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "b"),
+            ("2020-12-25T11:00:00", "2020-12-25T12:00:00", "a"),
+        ] {
+            let activity = OngoingActivity::new(datetimew(start), vec![String::from(*tag)], None)
+                .into_activity(datetimew(stop))
+                .unwrap();
+            let _tracked = service.track_activity(activity, true).unwrap();
+        }
+        let deleted = service
+            .delete_activities(|(_id, a)| a.has_tags(&[String::from("a")], false))
+            .unwrap();
+        let deleted_ids: Vec<ActivityId> = deleted.iter().map(|(id, _a)| *id).collect();
+        assert_eq!(deleted_ids, vec![0, 2]);
+        let finished = service.get_finished_activities().unwrap();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].1.get_title(), "b");
+    }
+
+    #[test]
+    fn test_get_activity_by_id() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        assert_eq!(service.get_activity_by_id(0).unwrap(), None);
+        let datetimew = |s: &str| -> DateTimeW {
+            Local
+                .datetime_from_str(s, "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into()
+        };
+        for (start, stop, tag) in &[
+            ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "a"),
+            ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "b"),
+        ] {
+            let activity = OngoingActivity::new(datetimew(start), vec![String::from(*tag)], None)
+                .into_activity(datetimew(stop))
+                .unwrap();
+            let _tracked = service.track_activity(activity, true).unwrap();
+        }
+        let (id, found) = service.get_activity_by_id(1).unwrap().unwrap();
+        assert_eq!(id, 1);
+        assert_eq!(found.get_title(), "b");
+        assert_eq!(service.get_activity_by_id(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_find_duplicate_ongoing() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let now: DateTimeW = Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into();
+        let ongoing = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(8, 0, 0).into(),
+            vec![String::from("a"), String::from("b")],
+            None,
+        );
+        service
+            .start_activity(ongoing.clone(), true, false)
+            .unwrap();
+        let duplicate = OngoingActivity::new(now, vec![String::from("b"), String::from("a")], None);
+        assert_eq!(
+            service
+                .find_duplicate_ongoing(&duplicate, now, Duration::seconds(0))
+                .unwrap(),
+            Some((0, ongoing))
+        );
+        let other_tags = OngoingActivity::new(now, vec![String::from("a")], None);
+        assert_eq!(
+            service
+                .find_duplicate_ongoing(&other_tags, now, Duration::seconds(0))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_ongoing_tolerance() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let now: DateTimeW = Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into();
+        let ongoing = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(8, 0, 0).into(),
+            vec![String::from("a")],
+            None,
+        );
+        service.start_activity(ongoing, true, false).unwrap();
+        let started_ago = |seconds: i64| {
+            let start: DateTime<Local> = Local.ymd(2020, 12, 25).and_hms(9, 0, 0);
+            OngoingActivity::new(
+                (start - Duration::seconds(seconds)).into(),
+                vec![String::from("a")],
+                None,
+            )
+        };
+        let find = |started: &OngoingActivity, tolerance: i64| {
+            service
+                .find_duplicate_ongoing(started, now, Duration::seconds(tolerance))
+                .unwrap()
+                .is_some()
+        };
+        // default tolerance: only a start time equal to now
+        assert!(!find(&started_ago(30), 0));
+        assert!(find(&started_ago(30), 30));
+        assert!(!find(&started_ago(31), 30));
+    }
 }
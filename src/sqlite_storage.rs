@@ -0,0 +1,664 @@
+//! Store activities (current, finished) in a SQLite database.
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::datetimew::DateTimeW;
+use crate::rtw_core::error::RtwError;
+use crate::rtw_core::storage::Storage;
+use crate::rtw_core::{ActivityId, Description, Tags};
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection, ToSql, NO_PARAMS};
+use std::path::Path;
+use thiserror::Error;
+
+pub const DATABASE_FILE_NAME: &str = ".rtw.sqlite";
+
+type ActivityWithId = (ActivityId, Activity);
+type OngoingActivityWithId = (ActivityId, OngoingActivity);
+type RowId = i64;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS activities (
+    id INTEGER,
+    start INTEGER NOT NULL,
+    stop INTEGER NOT NULL,
+    tags TEXT NOT NULL,
+    description TEXT
+);
+CREATE INDEX IF NOT EXISTS activities_start ON activities (start);
+CREATE INDEX IF NOT EXISTS activities_stop ON activities (stop);
+CREATE TABLE IF NOT EXISTS next_id (value INTEGER NOT NULL);
+CREATE TABLE IF NOT EXISTS ongoing (
+    start INTEGER NOT NULL,
+    tags TEXT NOT NULL,
+    description TEXT
+);
+";
+
+// databases created by older versions have no id column:
+// existing activities keep the ids displayed by older versions, 0 is the latest
+const MIGRATE_IDS: &str = "
+ALTER TABLE activities ADD COLUMN id INTEGER;
+UPDATE activities SET id = (
+    SELECT COUNT(*) FROM activities AS newer
+    WHERE newer.start > activities.start
+    OR (newer.start = activities.start AND newer.rowid > activities.rowid)
+);
+";
+
+const ID_INDEX: &str = "CREATE UNIQUE INDEX IF NOT EXISTS activities_id ON activities (id);";
+
+#[derive(Error, Debug)]
+pub enum SqliteStorageError {
+    #[error("sqlite error")]
+    SqliteError(#[from] rusqlite::Error),
+    #[error("(de)serialization failed")]
+    SerdeJsonError(#[from] serde_json::error::Error),
+    #[error("invalid activity: {0}")]
+    InvalidActivity(String),
+}
+
+impl From<SqliteStorageError> for RtwError {
+    fn from(e: SqliteStorageError) -> Self {
+        match e {
+            SqliteStorageError::SqliteError(e) => RtwError::Storage(Box::new(e)),
+            SqliteStorageError::SerdeJsonError(e) => RtwError::Parse(e.to_string()),
+            SqliteStorageError::InvalidActivity(e) => RtwError::Parse(e),
+        }
+    }
+}
+
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+// datetimes are stored as nanoseconds since epoch so that they are sorted chronologically
+fn to_nanos(datetimew: DateTimeW) -> i64 {
+    let datetime: DateTime<Local> = datetimew.into();
+    datetime.timestamp_nanos()
+}
+
+fn from_nanos(nanos: i64) -> DateTimeW {
+    let seconds = nanos.div_euclid(NANOS_PER_SECOND);
+    let nanoseconds = nanos.rem_euclid(NANOS_PER_SECOND) as u32;
+    Local.timestamp(seconds, nanoseconds).into()
+}
+
+// ids are never reused: the next id is stored (at least the highest id + 1)
+fn reserve_ids(connection: &Connection, count: usize) -> Result<ActivityId, SqliteStorageError> {
+    let first_id: i64 = connection.query_row(
+        "SELECT MAX(
+            COALESCE((SELECT MAX(value) FROM next_id), 0),
+            COALESCE((SELECT MAX(id) + 1 FROM activities), 0)
+        )",
+        NO_PARAMS,
+        |row| row.get(0),
+    )?;
+    connection.execute("DELETE FROM next_id", NO_PARAMS)?;
+    connection.execute(
+        "INSERT INTO next_id (value) VALUES (?1)",
+        params![first_id + count as i64],
+    )?;
+    Ok(first_id as ActivityId)
+}
+
+fn insert_activity(
+    connection: &Connection,
+    id: ActivityId,
+    activity: &Activity,
+) -> Result<(), SqliteStorageError> {
+    connection.execute(
+        "INSERT INTO activities (id, start, stop, tags, description) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            id as i64,
+            to_nanos(activity.get_start_time()),
+            to_nanos(activity.get_stop_time()),
+            serde_json::to_string(&activity.get_tags())?,
+            activity.get_description(),
+        ],
+    )?;
+    Ok(())
+}
+
+fn has_id_column(connection: &Connection) -> Result<bool, SqliteStorageError> {
+    let mut statement = connection.prepare("PRAGMA table_info(activities)")?;
+    let names = statement.query_map(NO_PARAMS, |row| row.get::<_, String>(1))?;
+    for name in names {
+        if name? == "id" {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+impl SqliteStorage {
+    pub fn new(path: &Path) -> Result<Self, SqliteStorageError> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        if !has_id_column(&connection)? {
+            connection.execute_batch(MIGRATE_IDS)?;
+        }
+        connection.execute_batch(ID_INDEX)?;
+        Ok(SqliteStorage { connection })
+    }
+
+    // finished activities with their id, sorted by start date
+    fn get_rows(&self) -> Result<Vec<ActivityWithId>, SqliteStorageError> {
+        self.query_rows("", NO_PARAMS)
+    }
+
+    // finished activities with their id, sorted by start date, `sql_where` e.g. "WHERE id = ?1"
+    fn query_rows(
+        &self,
+        sql_where: &str,
+        params: &[&dyn ToSql],
+    ) -> Result<Vec<ActivityWithId>, SqliteStorageError> {
+        let mut activities = vec![];
+        self.for_each_row(sql_where, params, |activity| activities.push(activity))?;
+        Ok(activities)
+    }
+
+    // call `f` on each finished activity row (sorted by start date) without collecting them
+    fn for_each_row<F>(
+        &self,
+        sql_where: &str,
+        params: &[&dyn ToSql],
+        f: F,
+    ) -> Result<(), SqliteStorageError>
+    where
+        F: FnMut(ActivityWithId),
+    {
+        self.for_each_selected_row(&format!("{} ORDER BY start, id", sql_where), params, f)
+    }
+
+    // call `f` on each finished activity row, `sql_tail` e.g. "ORDER BY stop DESC LIMIT ?1"
+    fn for_each_selected_row<F>(
+        &self,
+        sql_tail: &str,
+        params: &[&dyn ToSql],
+        mut f: F,
+    ) -> Result<(), SqliteStorageError>
+    where
+        F: FnMut(ActivityWithId),
+    {
+        let mut statement = self.connection.prepare(&format!(
+            "SELECT id, start, stop, tags, description FROM activities {}",
+            sql_tail
+        ))?;
+        let rows = statement.query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<Description>>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (id, start, stop, tags, description) = row?;
+            let tags: Tags = serde_json::from_str(&tags)?;
+            let activity = OngoingActivity::new(from_nanos(start), tags, description)
+                .into_activity(from_nanos(stop))
+                .map_err(|e| SqliteStorageError::InvalidActivity(e.to_string()))?;
+            f((id as ActivityId, activity));
+        }
+        Ok(())
+    }
+
+    // ongoing activities with their row id, sorted by start time
+    fn get_ongoing_rows(&self) -> Result<Vec<(RowId, OngoingActivity)>, SqliteStorageError> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT rowid, start, tags, description FROM ongoing ORDER BY start, rowid")?;
+        let rows = statement.query_map(NO_PARAMS, |row| {
+            Ok((
+                row.get::<_, RowId>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<Description>>(3)?,
+            ))
+        })?;
+        let mut activities = vec![];
+        for row in rows {
+            let (row_id, start, tags, description) = row?;
+            let tags: Tags = serde_json::from_str(&tags)?;
+            activities.push((
+                row_id,
+                OngoingActivity::new(from_nanos(start), tags, description),
+            ));
+        }
+        Ok(activities)
+    }
+}
+
+impl Storage for SqliteStorage {
+    type StorageError = SqliteStorageError;
+
+    fn write_activity(&mut self, activity: Activity) -> Result<ActivityId, Self::StorageError> {
+        let transaction = self.connection.transaction()?;
+        let id = reserve_ids(&transaction, 1)?;
+        insert_activity(&transaction, id, &activity)?;
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    fn write_activities(
+        &mut self,
+        activities: Vec<Activity>,
+    ) -> Result<Vec<ActivityId>, Self::StorageError> {
+        let transaction = self.connection.transaction()?;
+        let first_id = reserve_ids(&transaction, activities.len())?;
+        let mut ids: Vec<ActivityId> = vec![];
+        for (index, activity) in activities.into_iter().enumerate() {
+            let id = first_id + index;
+            insert_activity(&transaction, id, &activity)?;
+            ids.push(id);
+        }
+        transaction.commit()?;
+        Ok(ids)
+    }
+
+    fn restore_activity(
+        &mut self,
+        id: ActivityId,
+        activity: Activity,
+    ) -> Result<(), Self::StorageError> {
+        insert_activity(&self.connection, id, &activity)
+    }
+
+    fn filter_activities<P>(&self, p: P) -> Result<Vec<ActivityWithId>, Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        let activities = self.get_finished_activities()?;
+        Ok(activities.into_iter().filter(p).collect())
+    }
+
+    // only activities within the range are read, using the start and stop indexes
+    fn filter_activities_within<P>(
+        &self,
+        (range_start, range_end): (DateTimeW, DateTimeW),
+        p: P,
+    ) -> Result<Vec<ActivityWithId>, Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+    {
+        let mut activities = vec![];
+        self.for_each_row(
+            "WHERE start <= ?1 AND stop >= ?2",
+            params![to_nanos(range_end), to_nanos(range_start)],
+            |activity| {
+                if p(&activity) {
+                    activities.push(activity)
+                }
+            },
+        )?;
+        Ok(activities)
+    }
+
+    fn for_each_activity<P, F>(&self, p: P, mut f: F) -> Result<(), Self::StorageError>
+    where
+        P: Fn(&(ActivityId, Activity)) -> bool,
+        F: FnMut((ActivityId, Activity)),
+    {
+        self.for_each_row("", NO_PARAMS, |activity| {
+            if p(&activity) {
+                f(activity)
+            }
+        })
+    }
+
+    fn get_finished_activities(&self) -> Result<Vec<ActivityWithId>, Self::StorageError> {
+        self.get_rows()
+    }
+
+    fn count_activities(&self) -> Result<usize, Self::StorageError> {
+        let count: i64 =
+            self.connection
+                .query_row("SELECT COUNT(*) FROM activities", NO_PARAMS, |row| {
+                    row.get(0)
+                })?;
+        Ok(count as usize)
+    }
+
+    fn get_last_activities(&self, count: usize) -> Result<Vec<ActivityWithId>, Self::StorageError> {
+        let mut activities = vec![];
+        self.for_each_selected_row(
+            "ORDER BY stop DESC, id DESC LIMIT ?1",
+            params![count as i64],
+            |activity| activities.push(activity),
+        )?;
+        Ok(activities)
+    }
+
+    fn get_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError> {
+        let mut rows = self.query_rows("WHERE id = ?1", params![id as i64])?;
+        Ok(rows.pop().map(|(_id, activity)| activity))
+    }
+
+    fn delete_activity(&self, id: ActivityId) -> Result<Option<Activity>, Self::StorageError> {
+        let deleted_maybe = self.get_activity(id)?;
+        if deleted_maybe.is_some() {
+            self.connection
+                .execute("DELETE FROM activities WHERE id = ?1", params![id as i64])?;
+        }
+        Ok(deleted_maybe)
+    }
+
+    fn update_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, Activity)) -> Option<Activity>,
+    {
+        let rows = self.get_rows()?;
+        let transaction = self.connection.transaction()?;
+        let mut updated_ids: Vec<ActivityId> = vec![];
+        for (id, activity) in rows.into_iter() {
+            if let Some(updated) = f(&(id, activity)) {
+                transaction.execute(
+                    "UPDATE activities SET start = ?1, stop = ?2, tags = ?3, description = ?4 WHERE id = ?5",
+                    params![
+                        to_nanos(updated.get_start_time()),
+                        to_nanos(updated.get_stop_time()),
+                        serde_json::to_string(&updated.get_tags())?,
+                        updated.get_description(),
+                        id as i64,
+                    ],
+                )?;
+                updated_ids.push(id);
+            }
+        }
+        transaction.commit()?;
+        Ok(updated_ids)
+    }
+
+    fn get_ongoing_activities(&self) -> Result<Vec<OngoingActivityWithId>, Self::StorageError> {
+        let rows = self.get_ongoing_rows()?;
+        Ok(rows
+            .into_iter()
+            .map(|(_row_id, activity)| activity)
+            .enumerate()
+            .collect())
+    }
+
+    fn get_ongoing_activity(
+        &self,
+        id: ActivityId,
+    ) -> Result<Option<OngoingActivity>, Self::StorageError> {
+        let rows = self.get_ongoing_rows()?;
+        Ok(rows.into_iter().nth(id).map(|(_row_id, activity)| activity))
+    }
+
+    fn add_ongoing_activity(
+        &mut self,
+        activity: OngoingActivity,
+    ) -> Result<(), Self::StorageError> {
+        self.connection.execute(
+            "INSERT INTO ongoing (start, tags, description) VALUES (?1, ?2, ?3)",
+            params![
+                to_nanos(activity.get_start_time()),
+                serde_json::to_string(&activity.tags)?,
+                activity.description,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn remove_ongoing_activity(
+        &mut self,
+        id: ActivityId,
+    ) -> Result<Option<OngoingActivity>, Self::StorageError> {
+        let rows = self.get_ongoing_rows()?;
+        match rows.into_iter().nth(id) {
+            None => Ok(None),
+            Some((row_id, activity)) => {
+                self.connection
+                    .execute("DELETE FROM ongoing WHERE rowid = ?1", params![row_id])?;
+                Ok(Some(activity))
+            }
+        }
+    }
+
+    fn update_ongoing_activities<F>(&mut self, f: F) -> Result<Vec<ActivityId>, Self::StorageError>
+    where
+        F: Fn(&(ActivityId, OngoingActivity)) -> Option<OngoingActivity>,
+    {
+        let rows = self.get_ongoing_rows()?;
+        let transaction = self.connection.transaction()?;
+        let mut updated_ids: Vec<ActivityId> = vec![];
+        for (id, (row_id, activity)) in rows.into_iter().enumerate() {
+            if let Some(updated) = f(&(id, activity)) {
+                transaction.execute(
+                    "UPDATE ongoing SET start = ?1, tags = ?2, description = ?3 WHERE rowid = ?4",
+                    params![
+                        to_nanos(updated.get_start_time()),
+                        serde_json::to_string(&updated.tags)?,
+                        updated.description,
+                        row_id,
+                    ],
+                )?;
+                updated_ids.push(id);
+            }
+        }
+        transaction.commit()?;
+        Ok(updated_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rtw_core::activity::OngoingActivity;
+    use crate::rtw_core::storage::Storage;
+    use crate::rtw_core::test_utils::activity;
+    use crate::sqlite_storage::SqliteStorage;
+    use chrono::{Local, TimeZone};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_then_delete() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        // written out of order
+        storage.write_activity(bar.clone()).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        let finished = storage.get_finished_activities().unwrap();
+        // sorted by start date, ids in write order
+        assert_eq!(finished, vec![(1, foo.clone()), (0, bar.clone())]);
+        assert_eq!(storage.delete_activity(0).unwrap(), Some(bar));
+        assert_eq!(storage.delete_activity(2).unwrap(), None);
+        // ids are unchanged by deletion
+        assert_eq!(storage.get_finished_activities().unwrap(), vec![(1, foo)]);
+    }
+
+    #[test]
+    fn test_deleted_highest_id_not_reused() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        assert_eq!(storage.write_activity(foo).unwrap(), 0);
+        assert_eq!(storage.write_activity(bar.clone()).unwrap(), 1);
+        assert_eq!(storage.delete_activity(1).unwrap(), Some(bar.clone()));
+        assert_eq!(storage.write_activity(bar.clone()).unwrap(), 2);
+        assert_eq!(storage.write_activities(vec![bar]).unwrap(), vec![3]);
+    }
+
+    #[test]
+    fn test_count_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        assert_eq!(storage.count_activities().unwrap(), 0);
+        for hour in 9..12 {
+            let start = format!("2020-12-25T{:02}:00:00", hour);
+            let stop = format!("2020-12-25T{:02}:30:00", hour);
+            storage
+                .write_activity(activity(&start, &stop, &["foo"]))
+                .unwrap();
+        }
+        assert_eq!(storage.count_activities().unwrap(), 3);
+        storage.delete_activity(1).unwrap();
+        assert_eq!(storage.count_activities().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_get_last_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        assert_eq!(storage.get_last_activities(2).unwrap(), vec![]);
+        // the latest stop time comes first, not the latest start time
+        let long = activity("2020-12-25T09:00:00", "2020-12-25T12:00:00", &["long"]);
+        let foo = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["foo"]);
+        let bar = activity("2020-12-25T11:00:00", "2020-12-25T11:30:00", &["bar"]);
+        storage.write_activity(long.clone()).unwrap();
+        storage.write_activity(foo).unwrap();
+        storage.write_activity(bar.clone()).unwrap();
+        assert_eq!(
+            storage.get_last_activities(2).unwrap(),
+            vec![(0, long), (2, bar)]
+        );
+    }
+
+    #[test]
+    fn test_for_each_activity() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        storage.write_activity(bar).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        let mut streamed = vec![];
+        storage
+            .for_each_activity(
+                |(_id, a)| a.has_tags(&[String::from("foo")], false),
+                |a| streamed.push(a),
+            )
+            .unwrap();
+        assert_eq!(streamed, vec![(1, foo)]);
+    }
+
+    #[test]
+    fn test_get_activity() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        storage.write_activity(bar.clone()).unwrap();
+        storage.write_activity(foo.clone()).unwrap();
+        assert_eq!(storage.get_activity(0).unwrap(), Some(bar));
+        assert_eq!(storage.get_activity(1).unwrap(), Some(foo));
+        assert_eq!(storage.get_activity(2).unwrap(), None);
+    }
+
+    #[test]
+    fn test_write_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        storage.write_activity(foo.clone()).unwrap();
+        let ids = storage
+            .write_activities(vec![bar.clone(), foo.clone()])
+            .unwrap();
+        assert_eq!(ids, vec![1, 2]);
+        assert_eq!(storage.get_activity(1).unwrap(), Some(bar));
+        assert_eq!(storage.get_activity(2).unwrap(), Some(foo));
+    }
+
+    #[test]
+    fn test_filter_activities_within() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["bar"]);
+        let baz = activity("2020-12-25T13:00:00", "2020-12-25T14:00:00", &["baz"]);
+        storage
+            .write_activities(vec![foo, bar.clone(), baz.clone()])
+            .unwrap();
+        let range = (
+            bar.get_stop_time(),
+            Local
+                .datetime_from_str("2020-12-26T09:00:00", "%Y-%m-%dT%H:%M:%S")
+                .unwrap()
+                .into(),
+        );
+        assert_eq!(
+            storage.filter_activities_within(range, |_| true).unwrap(),
+            vec![(1, bar), (2, baz.clone())]
+        );
+        assert_eq!(
+            storage
+                .filter_activities_within(range, |(id, _a)| *id != 1)
+                .unwrap(),
+            vec![(2, baz)]
+        );
+    }
+
+    #[test]
+    fn test_persisted() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let path = test_dir.path().join(".rtw.sqlite");
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        {
+            let mut storage = SqliteStorage::new(&path).unwrap();
+            storage.write_activity(foo.clone()).unwrap();
+        }
+        let storage = SqliteStorage::new(&path).unwrap();
+        assert_eq!(storage.get_finished_activities().unwrap(), vec![(0, foo)]);
+    }
+
+    #[test]
+    fn test_ongoing_activities() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut storage = SqliteStorage::new(&test_dir.path().join(".rtw.sqlite")).unwrap();
+        let foo = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(9, 0, 0).into(),
+            vec![String::from("foo")],
+            None,
+        );
+        let bar = OngoingActivity::new(
+            Local.ymd(2020, 12, 25).and_hms(8, 0, 0).into(),
+            vec![String::from("bar")],
+            Some(String::from("description")),
+        );
+        storage.add_ongoing_activity(foo.clone()).unwrap();
+        storage.add_ongoing_activity(bar.clone()).unwrap();
+        assert_eq!(
+            storage.get_ongoing_activities().unwrap(),
+            vec![(0, bar.clone()), (1, foo.clone())]
+        );
+        assert_eq!(storage.remove_ongoing_activity(0).unwrap(), Some(bar));
+        assert_eq!(storage.get_ongoing_activity(0).unwrap(), Some(foo));
+    }
+
+    #[test]
+    fn test_migrate_ids() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let path = test_dir.path().join(".rtw.sqlite");
+        let foo = activity("2020-12-25T09:00:00", "2020-12-25T10:00:00", &["foo"]);
+        let bar = activity("2020-12-25T10:00:00", "2020-12-25T11:00:00", &["bar"]);
+        {
+            // database created by an older version, without id column
+            let mut storage = SqliteStorage::new(&path).unwrap();
+            storage.write_activity(bar.clone()).unwrap();
+            storage.write_activity(foo.clone()).unwrap();
+            storage
+                .connection
+                .execute_batch(
+                    "DROP INDEX activities_id;
+                    CREATE TABLE legacy AS SELECT start, stop, tags, description FROM activities;
+                    DROP TABLE activities;
+                    ALTER TABLE legacy RENAME TO activities;",
+                )
+                .unwrap();
+        }
+        let mut storage = SqliteStorage::new(&path).unwrap();
+        // ids displayed by older versions: 0 is the latest
+        assert_eq!(
+            storage.get_finished_activities().unwrap(),
+            vec![(1, foo), (0, bar)]
+        );
+        let baz = activity("2020-12-25T11:00:00", "2020-12-25T12:00:00", &["baz"]);
+        assert_eq!(storage.write_activity(baz).unwrap(), 2);
+    }
+}
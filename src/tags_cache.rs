@@ -0,0 +1,180 @@
+//! Tags index for `tags` and shell completion, stored as Json.
+//!
+//! The cache records the size and modification time of storage files when saved,
+//! it is stale when one of them changed since, it is then rebuilt by readers.
+use crate::json_storage::tmp_path;
+use crate::report::{sort_tag_stats, TagStats};
+use crate::rtw_core::activity::Activity;
+use crate::rtw_core::Tag;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const TAGS_CACHE_FILE_NAME: &str = ".rtw-tags.json";
+
+/// Tags of finished activities (with statistics) and of ongoing activities
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TagsCache {
+    /// tag -> (activity count, total duration in milliseconds)
+    finished: BTreeMap<Tag, (usize, i64)>,
+    ongoing: BTreeSet<Tag>,
+}
+
+impl TagsCache {
+    pub fn new() -> Self {
+        TagsCache::default()
+    }
+
+    pub fn add_finished(&mut self, activity: &Activity) {
+        let duration: Duration = activity.get_duration().into();
+        for tag in activity.get_tags() {
+            let entry = self.finished.entry(tag).or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += duration.num_milliseconds();
+        }
+    }
+
+    pub fn set_ongoing<I: IntoIterator<Item = Tag>>(&mut self, tags: I) {
+        self.ongoing = tags.into_iter().collect();
+    }
+
+    /// Distinct tags of finished and ongoing activities, sorted
+    pub fn names(&self) -> BTreeSet<Tag> {
+        self.finished
+            .keys()
+            .chain(self.ongoing.iter())
+            .cloned()
+            .collect()
+    }
+
+    /// Finished activities statistics, same as `report::tag_stats` over all finished activities
+    pub fn stats(&self) -> Vec<TagStats> {
+        sort_tag_stats(
+            self.finished
+                .iter()
+                .map(|(tag, (count, total))| (tag.clone(), *count, Duration::milliseconds(*total)))
+                .collect(),
+        )
+    }
+}
+
+/// Size and modification time of a storage file, None if missing
+type FileStamp = Option<(u64, SystemTime)>;
+
+#[derive(Serialize, Deserialize)]
+struct StoredTagsCache {
+    storage: Vec<FileStamp>,
+    tags: TagsCache,
+}
+
+fn stamps(storage_paths: &[PathBuf]) -> Vec<FileStamp> {
+    storage_paths
+        .iter()
+        .map(|path| {
+            path.metadata()
+                .and_then(|m| Ok((m.len(), m.modified()?)))
+                .ok()
+        })
+        .collect()
+}
+
+/// Load cached tags
+///
+/// None if the cache is missing, unreadable or one of `storage_paths` changed since it was saved.
+pub fn load_tags_cache(path: &Path, storage_paths: &[PathBuf]) -> Option<TagsCache> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    let stored: StoredTagsCache = serde_json::from_reader(reader).ok()?;
+    if stored.storage == stamps(storage_paths) {
+        Some(stored.tags)
+    } else {
+        None
+    }
+}
+
+/// Save cached tags, replacing `path`
+///
+/// Callers hold the storage lock, `storage_paths` must not change meanwhile.
+pub fn save_tags_cache(
+    path: &Path,
+    tags: &TagsCache,
+    storage_paths: &[PathBuf],
+) -> anyhow::Result<()> {
+    let stored = StoredTagsCache {
+        storage: stamps(storage_paths),
+        tags: tags.clone(),
+    };
+    let tmp_path = tmp_path(path);
+    {
+        let writer = BufWriter::new(File::create(&tmp_path)?);
+        serde_json::to_writer(writer, &stored)?;
+    }
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rtw_core::test_utils::activity;
+    use crate::tags_cache::{load_tags_cache, save_tags_cache, TagsCache, TAGS_CACHE_FILE_NAME};
+    use chrono::Duration;
+    use std::fs::File;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_load_missing_tags_cache() {
+        let test_dir = tempdir().unwrap();
+        let path = test_dir.path().join(TAGS_CACHE_FILE_NAME);
+        assert_eq!(load_tags_cache(&path, &[]), None);
+    }
+
+    #[test]
+    fn test_save_load_tags_cache() {
+        let test_dir = tempdir().unwrap();
+        let path = test_dir.path().join(TAGS_CACHE_FILE_NAME);
+        let storage_paths = vec![test_dir.path().join(".rtwh.json")];
+        File::create(&storage_paths[0]).unwrap();
+        let mut tags = TagsCache::new();
+        tags.add_finished(&activity(
+            "2020-12-25T09:00:00",
+            "2020-12-25T10:00:00",
+            &["foo", "bar"],
+        ));
+        tags.add_finished(&activity(
+            "2020-12-25T10:00:00",
+            "2020-12-25T10:30:00",
+            &["bar"],
+        ));
+        tags.set_ongoing(vec!["baz".to_string()]);
+        save_tags_cache(&path, &tags, &storage_paths).unwrap();
+        let loaded = load_tags_cache(&path, &storage_paths).unwrap();
+        assert_eq!(
+            loaded.names().into_iter().collect::<Vec<_>>(),
+            vec!["bar", "baz", "foo"]
+        );
+        assert_eq!(
+            loaded.stats(),
+            vec![
+                ("bar".to_string(), 2, Duration::minutes(90)),
+                ("foo".to_string(), 1, Duration::hours(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stale_tags_cache() {
+        let test_dir = tempdir().unwrap();
+        let path = test_dir.path().join(TAGS_CACHE_FILE_NAME);
+        let storage_paths = vec![test_dir.path().join(".rtwh.json")];
+        save_tags_cache(&path, &TagsCache::new(), &storage_paths).unwrap();
+        // storage written after the cache
+        let mut storage = File::create(&storage_paths[0]).unwrap();
+        writeln!(storage, "[]").unwrap();
+        drop(storage);
+        assert_eq!(load_tags_cache(&path, &storage_paths), None);
+    }
+}
@@ -0,0 +1,76 @@
+//! Fit text to terminal columns.
+use crate::rtw_core::Tag;
+
+const ELLIPSIS: char = '…';
+
+/// Join `tags` with spaces, truncated to at most `width` characters
+///
+/// Whole tags are kept while they fit, followed by ` …` when some are left out.
+/// The first tag is cut (with a trailing `…`) only when it does not fit on its own.
+pub fn fit_tags(tags: &[Tag], width: usize) -> String {
+    let title = tags.join(" ");
+    if title.chars().count() <= width {
+        return title;
+    }
+    let mut fitted = String::new();
+    let mut fitted_width = 0;
+    for tag in tags {
+        let separator = if fitted.is_empty() { 0 } else { 1 };
+        let tag_width = tag.chars().count();
+        // room left for ` …` after this tag
+        if fitted_width + separator + tag_width + 2 > width {
+            break;
+        }
+        if separator == 1 {
+            fitted.push(' ');
+        }
+        fitted.push_str(tag);
+        fitted_width += separator + tag_width;
+    }
+    if fitted.is_empty() {
+        let mut cut: String = title.chars().take(width.saturating_sub(1)).collect();
+        if width > 0 {
+            cut.push(ELLIPSIS);
+        }
+        cut
+    } else {
+        format!("{} {}", fitted, ELLIPSIS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::text::fit_tags;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fit_tags() {
+        let foo_bar_baz = tags(&["foo", "bar", "baz"]);
+        let table = vec![
+            (11, "foo bar baz"),
+            (20, "foo bar baz"),
+            (10, "foo bar …"),
+            (9, "foo bar …"),
+            (8, "foo …"),
+            (5, "foo …"),
+            (4, "foo…"),
+            (1, "…"),
+            (0, ""),
+        ];
+        for (width, expected) in table {
+            let fitted = fit_tags(&foo_bar_baz, width);
+            assert_eq!(fitted, expected, "width {}", width);
+            assert!(fitted.chars().count() <= width, "width {}", width);
+        }
+    }
+
+    #[test]
+    fn test_fit_tags_long_first_tag() {
+        assert_eq!(fit_tags(&tags(&["documentation", "foo"]), 6), "docum…");
+        assert_eq!(fit_tags(&tags(&["été"]), 2), "é…");
+        assert_eq!(fit_tags(&[], 0), "");
+    }
+}
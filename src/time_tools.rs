@@ -1,20 +1,426 @@
 //! Time parsing utils.
 use crate::rtw_core::clock::{Clock, Time};
+use crate::rtw_core::datetimew::DateTimeW;
 use anyhow::anyhow;
-use chrono::Local;
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
 use htp::parse;
+use std::str::FromStr;
 
 pub struct TimeTools {}
 
+const ACCEPTED_FORMATS: &str =
+    "now, 4 min ago, 10 ago (minutes), 90m ago, 2d ago, 09:00, 9am, yesterday, yesterday 14:00, \
+monday 9am, last monday, last friday at 19:45, 2019-12-25, 2019-12-25T19:43:00, \
+2019-12-25T19:43:00+02:00 (fractional seconds e.g. 19:43:00.500 are truncated)";
+
+// parsed time, true if it was moved forward out of a daylight saving time gap
+type Parsed = (DateTime<Local>, bool);
+
+// local date and time, see `DateTimeW::from_local`
+fn resolve_local(local: NaiveDateTime) -> Parsed {
+    let (datetime, moved) = DateTimeW::from_local(local);
+    (datetime.into(), moved)
+}
+
+// 2019-12-25T18:43:00+02:00 or 2019-12-25T16:43:00Z, fractional seconds are truncated
+fn parse_with_offset(s: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .and_then(|dt| dt.with_nanosecond(0))
+        .map(|dt| dt.with_timezone(&Local))
+}
+
+// 2019-12-25T18:43:00 or 2019-12-25T18:43:00.500, fractional seconds are truncated
+// (durations are displayed in whole seconds)
+fn parse_local_date_time(s: &str) -> Option<Parsed> {
+    let date_time = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Some(resolve_local(date_time.with_nanosecond(0)?))
+}
+
+// 09:00 or 09:00:30 today
+fn parse_clock_time(s: &str, now: DateTime<Local>) -> Option<Parsed> {
+    let time = NaiveTime::parse_from_str(s.trim(), "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s.trim(), "%H:%M"))
+        .ok()?;
+    Some(resolve_local(now.date().naive_local().and_time(time)))
+}
+
+// [last] (today|yesterday|weekday) [[at] time]
+//
+// a weekday resolves to its most recent occurrence (today included),
+// "last weekday" to its most recent occurrence before today.
+// time defaults to 00:00:00
+fn parse_day_clue(s: &str, now: DateTime<Local>) -> Option<Parsed> {
+    let tokens: Vec<String> = s.split_whitespace().map(str::to_lowercase).collect();
+    let (last, tokens) = match tokens.split_first() {
+        Some((first, rest)) if first == "last" => (true, rest),
+        _ => (false, tokens.as_slice()),
+    };
+    let (day, time_tokens) = tokens.split_first()?;
+    let days_back = match day.as_str() {
+        "today" if !last => 0,
+        "yesterday" if !last => 1,
+        _ => {
+            let weekday = Weekday::from_str(day).ok()?;
+            let days_back =
+                (now.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            if last && days_back == 0 {
+                7
+            } else {
+                days_back
+            }
+        }
+    };
+    let time_tokens = match time_tokens.split_first() {
+        Some((at, rest)) if at == "at" && !rest.is_empty() => rest,
+        _ => time_tokens,
+    };
+    let time = if time_tokens.is_empty() {
+        NaiveTime::from_hms(0, 0, 0)
+    } else {
+        parse(&time_tokens.join(" "), now).ok()?.time()
+    };
+    let date = now.date().naive_local() - Duration::days(days_back as i64);
+    Some(resolve_local(date.and_time(time)))
+}
+
+// <duration> ago e.g. 10 ago (minutes), 90m ago, 1h30m ago, 2d ago
+fn parse_ago(s: &str, now: DateTime<Local>) -> Option<DateTime<Local>> {
+    let tokens: Vec<&str> = s.split_whitespace().collect();
+    let (last, duration_tokens) = tokens.split_last()?;
+    if !last.eq_ignore_ascii_case("ago") || duration_tokens.is_empty() {
+        return None;
+    }
+    let duration = duration_tokens.join("");
+    let duration = match duration.strip_suffix('d') {
+        Some(days) => Duration::days(i64::from(days.parse::<u32>().ok()?)),
+        None => TimeTools::duration_from_str(&duration).ok()?,
+    };
+    Some(now - duration)
+}
+
+// 2019-12-25 -> 2019-12-25T00:00:00
+fn parse_date(s: &str) -> Option<Parsed> {
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(resolve_local(date.and_hms(0, 0, 0)))
+}
+
+// local times are resolved by `DateTimeW::from_local`, except times left to htp e.g. 9am
+fn parse_time(s: &str, now: DateTime<Local>) -> Option<Parsed> {
+    parse_with_offset(s)
+        .map(|dt| (dt, false))
+        .or_else(|| parse_local_date_time(s))
+        .or_else(|| parse_date(s))
+        .or_else(|| parse_clock_time(s, now))
+        .or_else(|| parse_day_clue(s, now))
+        .or_else(|| parse_ago(s, now).map(|dt| (dt, false)))
+        .or_else(|| parse(s, now).ok().map(|dt| (dt, false)))
+}
+
+const DURATION_FORMATS: &str = "90m, 1h30m, 45s or 45 (minutes)";
+
 impl TimeTools {
+    /// True if `s` is a date without time e.g. `2019-12-25`
+    pub fn is_date(s: &str) -> bool {
+        parse_date(s).is_some()
+    }
+
     pub fn is_time(s: &str) -> bool {
-        parse(s, Local::now()).is_ok()
+        parse_time(s, Local::now()).is_some()
+    }
+
+    /// Parse a duration e.g. `90m`, `1h30m`, `45s`, a bare number is a number of minutes
+    pub fn duration_from_str(s: &str) -> anyhow::Result<Duration> {
+        let invalid = || {
+            anyhow!(
+                "invalid duration '{}', expected e.g. {}",
+                s,
+                DURATION_FORMATS
+            )
+        };
+        let compact: String = s.split_whitespace().collect();
+        if compact.is_empty() {
+            return Err(invalid());
+        }
+        if let Ok(minutes) = compact.parse::<u32>() {
+            return Ok(Duration::minutes(i64::from(minutes)));
+        }
+        let mut total = Duration::seconds(0);
+        let mut number = String::new();
+        for c in compact.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let value: u32 = number.parse().map_err(|_| invalid())?;
+            let value = i64::from(value);
+            number.clear();
+            total = total
+                + match c {
+                    'h' => Duration::hours(value),
+                    'm' => Duration::minutes(value),
+                    's' => Duration::seconds(value),
+                    _ => return Err(invalid()),
+                };
+        }
+        if number.is_empty() {
+            Ok(total)
+        } else {
+            // trailing number without unit e.g. 1h30
+            Err(invalid())
+        }
+    }
+
+    /// Parse a duration before now without "ago" e.g. `5min`, `-5min`, `1h30m`
+    ///
+    /// A unit is required so that numbers are not mistaken for durations (e.g. tags).
+    pub fn duration_before_now_from_str(s: &str) -> Option<Duration> {
+        let duration = s.trim();
+        let duration = duration.strip_prefix('-').unwrap_or(duration);
+        if !duration.ends_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        // `min` and `mins` are also accepted for minutes, like in `5 min ago`
+        let duration = match duration
+            .strip_suffix("mins")
+            .or_else(|| duration.strip_suffix("min"))
+        {
+            Some(minutes) => format!("{}m", minutes),
+            None => duration.to_string(),
+        };
+        TimeTools::duration_from_str(&duration).ok()
+    }
+
+    /// Time `duration_before_now_from_str` before now
+    pub fn time_before_now_from_str(s: &str, clock: &dyn Clock) -> Option<Time> {
+        let duration = TimeTools::duration_before_now_from_str(s)?;
+        let now: DateTime<Local> = clock.get_time().into();
+        Some(Time::DateTime((now - duration).into()))
+    }
+
+    /// Parse a duration optionally prefixed by a sign e.g. `+15m`, `-1h`
+    pub fn signed_duration_from_str(s: &str) -> anyhow::Result<Duration> {
+        let s = s.trim();
+        if let Some(negative) = s.strip_prefix('-') {
+            Ok(-TimeTools::duration_from_str(negative)?)
+        } else {
+            TimeTools::duration_from_str(s.strip_prefix('+').unwrap_or(s))
+        }
     }
 
     pub fn time_from_str(s: &str, clock: &dyn Clock) -> anyhow::Result<Time> {
-        match parse(s, clock.get_time().into()) {
-            Ok(dt) => Ok(Time::DateTime(dt.into())),
-            Err(e) => Err(anyhow!(e.to_string())),
+        // resolved by the clock, e.g. fixed by `--now`
+        if s.trim().eq_ignore_ascii_case("now") {
+            return Ok(Time::Now);
+        }
+        match parse_time(s, clock.get_time().into()) {
+            Some((dt, moved)) => {
+                if moved {
+                    eprintln!(
+                        "Warning: '{}' is skipped by a daylight saving time change, using {}",
+                        s,
+                        DateTimeW::from(dt)
+                    );
+                }
+                Ok(Time::DateTime(dt.into()))
+            }
+            None => Err(anyhow!(
+                "invalid time clue '{}', accepted formats: {}",
+                s,
+                ACCEPTED_FORMATS
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chrono_clock::ChronoClock;
+    use crate::rtw_core::clock::Time;
+    use crate::rtw_core::test_utils::{datetimew, FixedClock};
+    use crate::time_tools::TimeTools;
+    use chrono::{DateTime, Duration, Local};
+
+    #[test]
+    fn test_time_from_str_with_offset() {
+        let clock = ChronoClock::default();
+        let expected: DateTime<Local> = DateTime::parse_from_rfc3339("2019-12-25T16:43:00Z")
+            .unwrap()
+            .with_timezone(&Local);
+        assert!(TimeTools::is_time("2019-12-25T18:43:00+02:00"));
+        assert_eq!(
+            TimeTools::time_from_str("2019-12-25T18:43:00+02:00", &clock).unwrap(),
+            Time::DateTime(expected.into())
+        );
+        assert_eq!(
+            TimeTools::time_from_str("2019-12-25T16:43:00Z", &clock).unwrap(),
+            Time::DateTime(expected.into())
+        );
+    }
+
+    #[test]
+    fn test_time_from_str_day_clues() {
+        // wednesday
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        let table = vec![
+            ("today", "2020-12-23T00:00:00"),
+            ("2020-12-01", "2020-12-01T00:00:00"),
+            ("yesterday", "2020-12-22T00:00:00"),
+            ("yesterday 14:00", "2020-12-22T14:00:00"),
+            ("yesterday at 14:00", "2020-12-22T14:00:00"),
+            ("last monday", "2020-12-21T00:00:00"),
+            ("last wednesday", "2020-12-16T00:00:00"),
+            ("last friday at 19:45", "2020-12-18T19:45:00"),
+            ("monday 9am", "2020-12-21T09:00:00"),
+            ("Monday 9am", "2020-12-21T09:00:00"),
+            ("wednesday 9am", "2020-12-23T09:00:00"),
+            ("friday", "2020-12-18T00:00:00"),
+        ];
+        for (clue, expected) in table {
+            assert!(TimeTools::is_time(clue), "{}", clue);
+            assert_eq!(
+                TimeTools::time_from_str(clue, &clock).unwrap(),
+                Time::DateTime(datetimew(expected)),
+                "{}",
+                clue
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_from_str_now() {
+        let clock = ChronoClock::default();
+        assert_eq!(TimeTools::time_from_str("now", &clock).unwrap(), Time::Now);
+        assert_eq!(TimeTools::time_from_str("Now", &clock).unwrap(), Time::Now);
+    }
+
+    #[test]
+    fn test_time_from_str_ago_shorthands() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        let table = vec![
+            ("10 ago", "2020-12-23T15:20:00"),
+            ("90m ago", "2020-12-23T14:00:00"),
+            ("1h ago", "2020-12-23T14:30:00"),
+            ("2d ago", "2020-12-21T15:30:00"),
+            ("45s ago", "2020-12-23T15:29:15"),
+            ("10 min ago", "2020-12-23T15:20:00"),
+        ];
+        for (clue, expected) in table {
+            assert!(TimeTools::is_time(clue), "{}", clue);
+            assert_eq!(
+                TimeTools::time_from_str(clue, &clock).unwrap(),
+                Time::DateTime(datetimew(expected)),
+                "{}",
+                clue
+            );
+        }
+    }
+
+    #[test]
+    fn test_time_from_str_fractional_seconds_truncated() {
+        let clock = ChronoClock::default();
+        for clue in &[
+            "2019-12-25T18:43:00",
+            "2019-12-25T18:43:00.000",
+            "2019-12-25T18:43:00.5",
+            "2019-12-25T18:43:00.999",
+        ] {
+            assert!(TimeTools::is_time(clue), "{}", clue);
+            assert_eq!(
+                TimeTools::time_from_str(clue, &clock).unwrap(),
+                Time::DateTime(datetimew("2019-12-25T18:43:00")),
+                "{}",
+                clue
+            );
+        }
+        let expected: DateTime<Local> = DateTime::parse_from_rfc3339("2019-12-25T16:43:00Z")
+            .unwrap()
+            .with_timezone(&Local);
+        assert_eq!(
+            TimeTools::time_from_str("2019-12-25T18:43:00.999+02:00", &clock).unwrap(),
+            Time::DateTime(expected.into())
+        );
+    }
+
+    #[test]
+    fn test_time_from_str_invalid() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        for clue in &["foo", "monday foo", "last yesterday"] {
+            assert!(!TimeTools::is_time(clue), "{}", clue);
+            let error = TimeTools::time_from_str(clue, &clock).unwrap_err();
+            assert!(error.to_string().contains("accepted formats"), "{}", clue);
+        }
+    }
+
+    #[test]
+    fn test_duration_from_str() {
+        let table = vec![
+            ("45", Duration::minutes(45)),
+            ("90m", Duration::minutes(90)),
+            ("1h30m", Duration::minutes(90)),
+            ("1h 30m", Duration::minutes(90)),
+            ("4h", Duration::hours(4)),
+            ("45s", Duration::seconds(45)),
+            ("1m30s", Duration::seconds(90)),
+        ];
+        for (s, expected) in table {
+            assert_eq!(TimeTools::duration_from_str(s).unwrap(), expected, "{}", s);
+        }
+        for s in &["", "h", "1h30", "1d", "foo", "-5", "-5m"] {
+            assert!(TimeTools::duration_from_str(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_signed_duration_from_str() {
+        let table = vec![
+            ("+15m", Duration::minutes(15)),
+            ("15m", Duration::minutes(15)),
+            ("-1h", Duration::hours(-1)),
+            ("-1h30m", Duration::minutes(-90)),
+        ];
+        for (s, expected) in table {
+            assert_eq!(
+                TimeTools::signed_duration_from_str(s).unwrap(),
+                expected,
+                "{}",
+                s
+            );
+        }
+        for s in &["", "+", "-", "+-5m", "--5m"] {
+            assert!(TimeTools::signed_duration_from_str(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_time_before_now_from_str() {
+        let clock = FixedClock {
+            now: datetimew("2020-12-23T15:30:00"),
+        };
+        for s in &["5min", "-5min", "5mins", "5m", "-300s"] {
+            assert_eq!(
+                TimeTools::time_before_now_from_str(s, &clock),
+                Some(Time::DateTime(datetimew("2020-12-23T15:25:00"))),
+                "{}",
+                s
+            );
+        }
+        // a unit is required
+        for s in &["5", "-5", "foo", "min"] {
+            assert_eq!(
+                TimeTools::time_before_now_from_str(s, &clock),
+                None,
+                "{}",
+                s
+            );
         }
     }
 }
@@ -1,6 +1,6 @@
 //! Timeline display
 use crate::rtw_core::activity::{Activity, OngoingActivity};
-use crate::rtw_core::durationw::DurationW;
+use crate::rtw_core::durationw::{DurationFormat, DurationPrecision, DurationW};
 use crate::rtw_core::ActivityId;
 use ansi_term::{Color, Style};
 use anyhow::anyhow;
@@ -183,7 +183,12 @@ fn days(activities: &[Interval]) -> (i32, i32) {
     (min_day, max_day)
 }
 
-pub(crate) fn render_days(activities: &[Interval], colors: &[RGB]) -> anyhow::Result<Vec<String>> {
+pub(crate) fn render_days(
+    activities: &[Interval],
+    colors: &[RGB],
+    duration_format: DurationFormat,
+    duration_precision: DurationPrecision,
+) -> anyhow::Result<Vec<String>> {
     let (width, _height) = term_size::dimensions().unwrap_or((DEFAULT_TERMINAL_SIZE, 0));
     let (min_second, max_second) = day_bounds(activities);
     let (min_day, max_day) = days(activities);
@@ -205,7 +210,7 @@ pub(crate) fn render_days(activities: &[Interval], colors: &[RGB]) -> anyhow::Re
             })
             .unwrap_or_else(|| "??/??".to_string());
         let total: DurationW = DurationW::from(day_total(day_activities.as_slice()));
-        let total_string = total.to_string();
+        let total_string = total.display_with_precision(duration_format, duration_precision);
         let right_padding = total_string.len() + 1; // +1 space
         let available_length = max(0, width - right_padding as usize) as usize;
         let data = Renderer::new(day_activities.as_slice(), &bounds, &|a| label(a, colors))
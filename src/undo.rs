@@ -0,0 +1,245 @@
+//! Undo journal: one entry per mutating command, stored as Json Lines.
+//!
+//! Entries store finished activities with their ids so that undoing a deletion restores the id.
+use crate::json_storage::tmp_path;
+use crate::rtw_core::activity::{Activity, OngoingActivity};
+use crate::rtw_core::error::RtwError;
+use crate::rtw_core::service::ActivityService;
+use crate::rtw_core::storage::Storage;
+use crate::rtw_core::ActivityId;
+use crate::service::Service;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const UNDO_FILE_NAME: &str = ".rtw-undo";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UndoEntry {
+    /// command name e.g. "stop"
+    pub command: String,
+    /// ongoing activities before the command, None if the command did not touch them
+    #[serde(default)]
+    pub ongoing: Option<Vec<OngoingActivity>>,
+    /// finished activities (with their id) removed by the command
+    pub removed: Vec<(ActivityId, Activity)>,
+    /// finished activities (with their id) added by the command
+    pub added: Vec<(ActivityId, Activity)>,
+}
+
+impl UndoEntry {
+    /// Empty entry, mutations record the activities they touch
+    pub fn new(command: &str) -> Self {
+        UndoEntry {
+            command: command.to_string(),
+            ongoing: None,
+            removed: vec![],
+            added: vec![],
+        }
+    }
+
+    /// Record ongoing activities, before the command changes them
+    pub fn record_ongoing<S: Storage>(&mut self, service: &Service<S>) -> anyhow::Result<()> {
+        if self.ongoing.is_none() {
+            self.ongoing = Some(
+                service
+                    .get_ongoing_activities()?
+                    .into_iter()
+                    .map(|(_id, a)| a)
+                    .collect(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the command changed anything, ongoing activities are compared to the recorded ones
+    pub fn has_changes<S: Storage>(&self, service: &Service<S>) -> anyhow::Result<bool> {
+        if !self.removed.is_empty() || !self.added.is_empty() {
+            return Ok(true);
+        }
+        match &self.ongoing {
+            None => Ok(false),
+            Some(ongoing) => {
+                let current: Vec<OngoingActivity> = service
+                    .get_ongoing_activities()?
+                    .into_iter()
+                    .map(|(_id, a)| a)
+                    .collect();
+                Ok(*ongoing != current)
+            }
+        }
+    }
+
+    /// Revert the command: delete added activities, restore removed and ongoing activities
+    pub fn revert<S: Storage>(&self, service: &mut Service<S>) -> anyhow::Result<()> {
+        for (id, _added) in self.added.iter() {
+            match service.delete_activity(*id) {
+                // already deleted e.g. by an external edit
+                Ok(_) | Err(RtwError::NotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        for (id, removed) in self.removed.iter() {
+            service.restore_activity(*id, removed.clone())?;
+        }
+        if let Some(ongoing) = &self.ongoing {
+            service.restore_ongoing_activities(ongoing.clone())?;
+        }
+        Ok(())
+    }
+}
+
+pub struct UndoJournal {
+    path: PathBuf,
+}
+
+impl UndoJournal {
+    pub fn new(path: PathBuf) -> Self {
+        UndoJournal { path }
+    }
+
+    fn read_entries(&self) -> anyhow::Result<Vec<UndoEntry>> {
+        if !Path::exists(&self.path) {
+            return Ok(vec![]);
+        }
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut entries = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                entries.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn push(&self, entry: &UndoEntry) -> anyhow::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        file.write_all(&line)?;
+        Ok(())
+    }
+
+    /// Most recent entry
+    pub fn last(&self) -> anyhow::Result<Option<UndoEntry>> {
+        Ok(self.read_entries()?.pop())
+    }
+
+    /// Remove and return the most recent entry
+    pub fn pop(&self) -> anyhow::Result<Option<UndoEntry>> {
+        let mut entries = self.read_entries()?;
+        let last = entries.pop();
+        if last.is_some() {
+            let tmp_path = tmp_path(&self.path);
+            {
+                let mut writer = BufWriter::new(File::create(&tmp_path)?);
+                for entry in entries.iter() {
+                    serde_json::to_writer(&mut writer, entry)?;
+                    writer.write_all(b"\n")?;
+                }
+                writer.flush()?;
+            }
+            std::fs::rename(&tmp_path, &self.path)?;
+        }
+        Ok(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json_storage::JsonStorage;
+    use crate::rtw_core::activity::OngoingActivity;
+    use crate::rtw_core::service::ActivityService;
+    use crate::rtw_core::test_utils::datetimew;
+    use crate::service::Service;
+    use crate::undo::{UndoEntry, UndoJournal};
+    use tempfile::{tempdir, TempDir};
+
+    fn build_json_service(test_dir: &TempDir) -> Service<JsonStorage> {
+        let finished_path = test_dir.path().join(".rtwh.json");
+        let current_path = test_dir.path().join(".rtwc.json");
+        Service::new(JsonStorage::new(current_path, finished_path))
+    }
+
+    #[test]
+    fn test_undo_start() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let mut entry = UndoEntry::new("start");
+        entry.record_ongoing(&service).unwrap();
+        let started = OngoingActivity::new(datetimew("2020-12-25T09:00:00"), vec![], None);
+        service.start_activity(started, true, false).unwrap();
+        assert!(entry.has_changes(&service).unwrap());
+        entry.revert(&mut service).unwrap();
+        assert!(service.get_ongoing_activities().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_undo_stop_restores_ongoing() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let foo = OngoingActivity::new(datetimew("2020-12-25T09:00:00"), vec!["foo".into()], None);
+        let bar = OngoingActivity::new(datetimew("2020-12-25T10:00:00"), vec!["bar".into()], None);
+        service.start_activity(foo.clone(), true, true).unwrap();
+        service.start_activity(bar.clone(), true, true).unwrap();
+        let mut entry = UndoEntry::new("stop");
+        entry.record_ongoing(&service).unwrap();
+        let (id, stopped) = service
+            .stop_ongoing_activity(datetimew("2020-12-25T11:00:00"), 0, true, None)
+            .unwrap()
+            .unwrap();
+        entry.added.push((id, stopped));
+        entry.revert(&mut service).unwrap();
+        assert!(service.get_finished_activities().unwrap().is_empty());
+        assert_eq!(
+            service.get_ongoing_activities().unwrap(),
+            vec![(0, foo), (1, bar)]
+        );
+    }
+
+    #[test]
+    fn test_undo_delete() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let mut service = build_json_service(&test_dir);
+        let activity = OngoingActivity::new(datetimew("2020-12-25T09:00:00"), vec![], None)
+            .into_activity(datetimew("2020-12-25T10:00:00"))
+            .unwrap();
+        service.track_activity(activity.clone(), true).unwrap();
+        let mut entry = UndoEntry::new("delete");
+        let deleted = service.delete_activity(0).unwrap();
+        entry.removed.push((0, deleted));
+        entry.revert(&mut service).unwrap();
+        assert_eq!(
+            service.get_finished_activities().unwrap(),
+            vec![(0, activity)]
+        );
+    }
+
+    #[test]
+    fn test_no_change_no_entry() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let service = build_json_service(&test_dir);
+        let mut entry = UndoEntry::new("stop");
+        assert!(!entry.has_changes(&service).unwrap());
+        entry.record_ongoing(&service).unwrap();
+        assert!(!entry.has_changes(&service).unwrap());
+    }
+
+    #[test]
+    fn test_journal_push_pop() {
+        let test_dir = tempdir().expect("error while creating tempdir");
+        let journal = UndoJournal::new(test_dir.path().join(".rtw-undo"));
+        assert_eq!(journal.pop().unwrap(), None);
+        journal.push(&UndoEntry::new("start")).unwrap();
+        journal.push(&UndoEntry::new("stop")).unwrap();
+        assert_eq!(journal.last().unwrap(), Some(UndoEntry::new("stop")));
+        assert_eq!(journal.pop().unwrap(), Some(UndoEntry::new("stop")));
+        assert_eq!(journal.pop().unwrap(), Some(UndoEntry::new("start")));
+        assert_eq!(journal.pop().unwrap(), None);
+    }
+}
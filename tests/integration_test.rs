@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod tests {
     use assert_cmd::Command;
+    use predicates::prelude::*;
     use tempfile::tempdir;
 
     const NO_ACTIVE_TIME_TRACKING: &str = "There is no active time tracking.\n";
@@ -69,6 +70,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -106,6 +108,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -198,6 +201,76 @@ mod tests {
             .stdout("No activity to continue from.\n");
     }
 
+    #[test]
+    fn continue_id_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("continue")
+            .arg("42")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicates::str::starts_with(
+                "Error: no activity found for id 42\n",
+            ));
+    }
+
+    #[test]
+    fn continue_id_some() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:00")
+            .arg("-")
+            .arg("10:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .arg("track")
+            .arg("--allow-future")
+            .arg("10:00")
+            .arg("-")
+            .arg("11:00")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .arg("continue")
+            .arg("0")
+            .arg("5")
+            .arg("min")
+            .arg("ago")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+    }
+
     #[test]
     fn delete_none() {
         let test_dir = tempdir().expect("could not create temp directory");
@@ -208,8 +281,252 @@ mod tests {
             .arg("delete")
             .arg("42")
             .assert()
+            .failure()
+            .code(2)
+            .stderr(predicates::str::starts_with(
+                "Error: no activity found for id 42\n",
+            ));
+    }
+
+    #[test]
+    fn annotate_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("annotate")
+            .arg("42")
+            .arg("some")
+            .arg("text")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicates::str::starts_with(
+                "Error: no activity found for id 42\n",
+            ));
+    }
+
+    #[test]
+    fn annotate_some() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:00")
+            .arg("-")
+            .arg("10:00")
+            .arg("foo")
+            .arg("--annotation")
+            .arg("first")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("annotate")
+            .arg("0")
+            .arg("fixed")
+            .arg("bug")
+            .assert()
+            .success()
+            .stdout("Annotated foo\nfixed bug\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--description")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("fixed bug"));
+    }
+
+    #[test]
+    fn stop_with_annotation() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("1h ago")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("--annotation")
+            .arg("finished the report")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded foo"))
+            .stdout(predicates::str::ends_with("finished the report\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--description")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("finished the report"));
+    }
+
+    #[test]
+    fn stop_before_now_without_ago() {
+        for stop_args in &[vec!["5min"], vec!["-5min"], vec!["5", "min", "ago"]] {
+            let test_dir = tempdir().expect("could not create temp directory");
+            let test_dir_path = test_dir.path().to_str().unwrap();
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--now")
+                .arg("2020-12-25T10:00:00")
+                .arg("start")
+                .arg("09:00")
+                .arg("foo")
+                .assert()
+                .success();
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--now")
+                .arg("2020-12-25T10:00:00")
+                .arg("stop")
+                .args(stop_args)
+                .arg("foo")
+                .assert()
+                .success()
+                .stdout(predicates::str::contains("Ended   2020-12-25T09:55:00"))
+                .stdout(predicates::str::contains("Total   00:55:00"));
+        }
+    }
+
+    #[test]
+    fn stop_unknown_flag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        for stop_args in &[
+            vec!["--alll"],
+            vec!["foo", "--alll"],
+            vec!["-5min", "--alll"],
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("stop")
+                .args(stop_args)
+                .assert()
+                .failure()
+                .stderr(predicates::str::contains("'--alll'"));
+        }
+    }
+
+    #[test]
+    fn modify_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("modify")
+            .arg("42")
+            .arg("--tags")
+            .arg("foo")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicates::str::starts_with(
+                "Error: no activity found for id 42\n",
+            ));
+    }
+
+    #[test]
+    fn modify_end_and_tags() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:00")
+            .arg("-")
+            .arg("10:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("modify")
+            .arg("0")
+            .arg("--end")
+            .arg("11:00")
+            .arg("--tags")
+            .arg("bar,baz")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Modified bar baz"))
+            .stdout(predicates::str::contains("Total   02:00:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .assert()
             .success()
-            .stdout("No activity found for id 42.\n");
+            .stdout(predicates::str::contains("0 bar baz"));
+    }
+
+    #[test]
+    fn modify_inverted() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:00")
+            .arg("-")
+            .arg("10:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("modify")
+            .arg("0")
+            .arg("--start")
+            .arg("10:00")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn modify_nothing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("modify")
+            .arg("0")
+            .assert()
+            .failure();
     }
 
     #[test]
@@ -220,6 +537,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -289,7 +607,56 @@ mod tests {
             .arg("cancel")
             .assert()
             .success()
-            .stdout(predicates::str::contains("Cancelled foo"));
+            .stdout(predicates::str::contains("Cancelled tracking of foo."));
+    }
+
+    #[test]
+    fn cancel_not_recorded() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("cancel")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Cancelled tracking of foo.\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "There is no active time tracking.",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout("No filtered data found.\n");
+    }
+
+    #[test]
+    fn cancel_nothing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("cancel")
+            .assert()
+            .success()
+            .stdout("Nothing to cancel.\n");
     }
 
     #[test]
@@ -378,6 +745,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -395,6 +763,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09am")
             .arg("-")
             .arg("10am")
@@ -442,6 +811,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -454,23 +824,27 @@ mod tests {
             .arg(test_dir_path)
             .arg("--no_overlap") // deny overlapping
             .arg("track")
+            .arg("--allow-future")
             .arg("09:30")
             .arg("-")
             .arg("11:00")
             .arg("bar")
             .assert()
             .failure()
-            .stderr(predicates::str::contains("would overlap"));
+            .code(3)
+            .stderr(predicates::str::contains("would overlap"))
+            .stderr(predicates::str::contains("ids: 0"));
     }
 
     #[test]
-    fn track_overlap_allowed() {
+    fn track_overlap_allowed_with_flag() {
         let test_dir = tempdir().expect("could not create temp directory");
         let test_dir_path = test_dir.path().to_str().unwrap();
         let mut cmd = Command::cargo_bin("rtw").unwrap();
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("track")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("-")
             .arg("10:00")
@@ -481,8 +855,10 @@ mod tests {
         let mut cmd = Command::cargo_bin("rtw").unwrap();
         cmd.arg("-d")
             .arg(test_dir_path)
-            .arg("--overlap") // deny overlapping
+            .arg("--no_overlap") // deny overlapping
             .arg("track")
+            .arg("--allow-future")
+            .arg("--allow-overlap")
             .arg("09:30")
             .arg("-")
             .arg("11:00")
@@ -493,13 +869,44 @@ mod tests {
     }
 
     #[test]
-    fn start_nothing_now() {
+    fn track_overlap_allowed() {
         let test_dir = tempdir().expect("could not create temp directory");
         let test_dir_path = test_dir.path().to_str().unwrap();
         let mut cmd = Command::cargo_bin("rtw").unwrap();
         cmd.arg("-d")
             .arg(test_dir_path)
-            .arg("start")
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:00")
+            .arg("-")
+            .arg("10:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--overlap") // deny overlapping
+            .arg("track")
+            .arg("--allow-future")
+            .arg("09:30")
+            .arg("-")
+            .arg("11:00")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded bar"));
+    }
+
+    #[test]
+    fn start_nothing_now() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
             .assert()
             .failure();
     }
@@ -534,6 +941,39 @@ mod tests {
             .stdout(predicates::str::contains("Tracking foo"));
     }
 
+    #[test]
+    fn start_prints_resolved_start_time() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T09:00:00")
+            .arg("start")
+            .arg("15min")
+            .arg("ago")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "Tracking foo\nStarted  2020-12-25T08:45:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T09:00:00")
+            .arg("--quiet")
+            .arg("start")
+            .arg("15min")
+            .arg("ago")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout("");
+    }
+
     #[test]
     fn start_foo_today_at_9() {
         let test_dir = tempdir().expect("could not create temp directory");
@@ -542,6 +982,7 @@ mod tests {
         cmd.arg("-d")
             .arg(test_dir_path)
             .arg("start")
+            .arg("--allow-future")
             .arg("09:00")
             .arg("foo")
             .assert()
@@ -603,4 +1044,4411 @@ mod tests {
             .success()
             .stdout(predicates::str::contains("Recorded foo"));
     }
+
+    #[test]
+    fn start_parallel() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--no_overlap")
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--no_overlap")
+            .arg("start")
+            .arg("--parallel")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"))
+            .stdout(predicates::str::contains("Tracking bar"));
+    }
+
+    #[test]
+    fn tags_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("No tags found.\n");
+    }
+
+    #[test]
+    fn tags_something() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T10:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:30:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("foo 2 01:30:00\nbar 1 01:00:00\n");
+    }
+
+    #[test]
+    fn rename_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout("No activity tagged with foo.\n");
+    }
+
+    #[test]
+    fn rename_finished_and_ongoing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Finished 1"))
+            .stdout(predicates::str::contains("Ongoing  1"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("bar 1 01:00:00\n");
+    }
+
+    #[test]
+    fn rename_dry_run() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("--dry-run")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Finished ids 0"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("foo 1 01:00:00\n");
+    }
+
+    #[test]
+    fn retag_add_remove() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, stop, tags) in &[
+            (
+                "2019-11-30T09:00:00",
+                "2019-11-30T10:00:00",
+                ["foo", "draft"],
+            ),
+            (
+                "2019-12-24T09:00:00",
+                "2019-12-24T10:00:00",
+                ["foo", "draft"],
+            ),
+            (
+                "2019-12-24T10:00:00",
+                "2019-12-24T11:00:00",
+                ["bar", "draft"],
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("track")
+                .arg(start)
+                .arg("-")
+                .arg(stop)
+                .args(tags)
+                .assert()
+                .success();
+        }
+        // add only
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("retag")
+            .arg("2019-12")
+            .arg("--tag")
+            .arg("foo")
+            .arg("--add")
+            .arg("billable")
+            .assert()
+            .success()
+            .stdout("Retagged 1 activities.\n");
+        // remove only
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("retag")
+            .arg("2019-12")
+            .arg("--remove")
+            .arg("draft")
+            .assert()
+            .success()
+            .stdout("Retagged 2 activities.\n");
+        // combined, the activity already billable without draft is unchanged
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("retag")
+            .arg("--add")
+            .arg("billable")
+            .arg("--remove")
+            .arg("draft")
+            .arg("--dry-run")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Retagged 2 activities.\nIds "));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout(predicates::str::is_match("billable +1 01:00:00").unwrap())
+            .stdout(predicates::str::is_match("draft +1 01:00:00").unwrap());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("retag")
+            .arg("--add")
+            .arg("billable")
+            .arg("--remove")
+            .arg("draft")
+            .assert()
+            .success()
+            .stdout("Retagged 2 activities.\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout(predicates::str::is_match("billable +3 03:00:00").unwrap())
+            .stdout(predicates::str::contains("draft").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("retag")
+            .arg("--remove")
+            .arg("draft")
+            .assert()
+            .success()
+            .stdout("No activity changed.\n");
+    }
+
+    #[test]
+    fn rename_invalid_new_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2019-12-25T09:00:00", "2019-12-25T10:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("foo")
+            .arg(" ")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("empty tag"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("foo")
+            .arg("foo;bar")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid tag 'foo;bar'"));
+        // surrounding whitespace is trimmed
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("rename")
+            .arg("foo")
+            .arg(" bar ")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("bar 1 01:00:00\n");
+    }
+
+    #[test]
+    fn export_csv_something() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("export")
+            .arg("--format")
+            .arg("csv")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "id,start,end,duration,tags\n",
+                "0,2019-12-25T09:00:00,2019-12-25T10:00:00,3600,foo;bar\n"
+            ));
+    }
+
+    #[test]
+    fn export_json_then_import() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        let exported = cmd
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("export")
+            .arg("--format")
+            .arg("json")
+            .output()
+            .unwrap()
+            .stdout;
+        let export_path = test_dir.path().join("export.json");
+        std::fs::write(&export_path, exported).unwrap();
+        let other_dir = tempdir().expect("could not create temp directory");
+        let other_dir_path = other_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(other_dir_path)
+            .arg("import")
+            .arg(export_path.to_str().unwrap())
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Imported 1"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(other_dir_path)
+            .arg("export")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "id,start,end,duration,tags\n",
+                "0,2019-12-25T09:00:00,2019-12-25T10:00:00,3600,foo\n"
+            ));
+        // importing twice skips duplicates
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(other_dir_path)
+            .arg("import")
+            .arg(export_path.to_str().unwrap())
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Imported 0"));
+    }
+
+    #[test]
+    fn import_malformed() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let import_path = test_dir.path().join("import.json");
+        std::fs::write(
+            &import_path,
+            concat!(
+                "[",
+                r#"{"id": 1, "start": "2019-12-25T09:00:00+01:00", "end": "2019-12-25T10:00:00+01:00", "tags": ["foo"]},"#,
+                r#"{"id": 0, "start": "2019-12-25T11:00:00+01:00", "end": "2019-12-25T10:00:00+01:00", "tags": ["bar"]}"#,
+                "]"
+            ),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("import")
+            .arg(import_path.to_str().unwrap())
+            .assert()
+            .failure();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("No tags found.\n");
+    }
+
+    #[test]
+    fn import_invalid_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let import_path = test_dir.path().join("import.json");
+        std::fs::write(
+            &import_path,
+            concat!(
+                "[",
+                r#"{"id": 1, "start": "2019-12-25T09:00:00+01:00", "end": "2019-12-25T10:00:00+01:00", "tags": ["foo"]},"#,
+                r#"{"id": 0, "start": "2019-12-25T10:00:00+01:00", "end": "2019-12-25T11:00:00+01:00", "tags": ["foo;bar"]}"#,
+                "]"
+            ),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("import")
+            .arg(import_path.to_str().unwrap())
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid tag 'foo;bar'"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout("No tags found.\n");
+    }
+
+    #[test]
+    fn import_timewarrior() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let import_path = test_dir.path().join("timew.json");
+        std::fs::write(
+            &import_path,
+            concat!(
+                "[",
+                r#"{"id":2,"start":"20191225T090000Z","end":"20191225T100000Z","tags":["foo"]},"#,
+                r#"{"id":1,"start":"20191225T110000Z","tags":["bar"]}"#,
+                "]"
+            ),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("import")
+            .arg("--from")
+            .arg("timewarrior")
+            .arg(import_path.to_str().unwrap())
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Imported 1"))
+            .stdout(predicates::str::contains("Tracking bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"));
+    }
+
+    #[test]
+    fn sqlite_backend_start_stop_summary() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--backend")
+            .arg("sqlite")
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--backend")
+            .arg("sqlite")
+            .arg("stop")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--backend")
+            .arg("sqlite")
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+        assert!(test_dir.path().join(".rtw.sqlite").exists());
+        assert!(!test_dir.path().join(".rtwh.json").exists());
+    }
+
+    #[test]
+    fn summary_range_token_week() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg(":week")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn summary_month_and_iso_week_tokens() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2019-11-30T09:00:00", "2019-11-30T10:00:00", "foo"),
+                ("2019-12-24T09:00:00", "2019-12-24T10:00:00", "bar"),
+                ("2019-12-31T09:00:00", "2019-12-31T10:00:00", "baz"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2019-12")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("bar"))
+            .stdout(predicates::str::contains("baz"))
+            .stdout(predicates::str::contains("foo").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2019-W52")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("bar"))
+            .stdout(predicates::str::contains("baz").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2019-13")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid month in 2019-13"));
+    }
+
+    #[test]
+    fn summary_max_width() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[(
+                "2020-12-25T09:00:00",
+                "2020-12-25T10:00:00",
+                "documentation",
+            )],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T10:00:00")
+            .arg("-")
+            .arg("2020-12-25T11:00:00")
+            .arg("write")
+            .arg("review")
+            .arg("release")
+            .assert()
+            .success();
+        // 19 + 1 + 19 + 1 + 8 = 48 columns for times and duration, 12 left for tags
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--max-width")
+            .arg("61")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with(
+                "documentati… 2020-12-25T09:00:00 2020-12-25T10:00:00 01:00:00\n\
+                 write …      2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00\n",
+            ));
+        // not a terminal: no truncation by default
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "write review release 2020-12-25T10:00:00",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--max-width")
+            .arg("61")
+            .arg("--no-truncate")
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn summary_range_token_invalid() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg(":fortnight")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid range"));
+    }
+
+    #[test]
+    fn gaps_some() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T11:00:00")
+            .arg("-")
+            .arg("2020-12-25T12:00:00")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("gaps")
+            .arg("2020-12-25T08:30:00")
+            .arg("-")
+            .arg("2020-12-25T12:00:00")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "2020-12-25T08:30:00 2020-12-25T09:00:00 00:30:00\n",
+                "2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("gaps")
+            .arg("--min")
+            .arg("31")
+            .arg("2020-12-25T08:30:00")
+            .arg("-")
+            .arg("2020-12-25T12:00:00")
+            .assert()
+            .success()
+            .stdout("2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00\n");
+    }
+
+    #[test]
+    fn gaps_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("gaps")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .assert()
+            .success()
+            .stdout("No gaps found.\n");
+    }
+
+    #[test]
+    fn summary_round() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T09:07:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T10:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:20:00")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--round")
+            .arg("15")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "foo 2020-12-25T09:00:00 2020-12-25T09:07:00 00:15:00\n",
+                "bar 2020-12-25T10:00:00 2020-12-25T10:20:00 00:30:00\n",
+                "\n",
+                "bar   00:30:00\n",
+                "foo   00:15:00\n",
+                "Total 00:45:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--round")
+            .arg("15")
+            .arg("--round-method")
+            .arg("nearest")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Total 00:15:00"));
+    }
+
+    #[test]
+    fn track_with_offset_display_utc() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--utc")
+            .arg("track")
+            .arg("2020-03-29T01:30:00+01:00")
+            .arg("-")
+            .arg("2020-03-29T03:30:00+02:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Started 2020-03-29T00:30:00Z"))
+            .stdout(predicates::str::contains("Ended   2020-03-29T01:30:00Z"))
+            .stdout(predicates::str::contains("Total   01:00:00"));
+    }
+
+    #[test]
+    fn track_dst_gap_moved_forward() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        // 2021-03-28 02:00 CET jumps to 03:00 CEST, 02:30 does not exist
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("TZ", "Europe/Paris")
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("--utc")
+            .arg("track")
+            .arg("2021-03-28T02:30:00")
+            .arg("-")
+            .arg("2021-03-28T04:00:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stderr(predicates::str::contains(
+                "Warning: '2021-03-28T02:30:00' is skipped by a daylight saving time change, using 2021-03-28T03:00:00",
+            ))
+            .stdout(predicates::str::contains("Started 2021-03-28T01:00:00Z"))
+            .stdout(predicates::str::contains("Total   01:00:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("TZ", "Europe/Paris")
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2021-03-28T12:00:00")
+            .arg("--utc")
+            .arg("--overlap")
+            .arg("track")
+            .arg("02:15")
+            .arg("-")
+            .arg("05:00")
+            .arg("bar")
+            .assert()
+            .success()
+            .stderr(predicates::str::contains("Warning: '02:15'"))
+            .stdout(predicates::str::contains("Started 2021-03-28T01:00:00Z"));
+    }
+
+    #[test]
+    fn track_dst_ambiguous_earlier() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        // 2021-10-31 03:00 CEST goes back to 02:00 CET, 02:30 occurs twice
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("TZ", "Europe/Paris")
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("--utc")
+            .arg("track")
+            .arg("2021-10-31T02:30:00")
+            .arg("-")
+            .arg("2021-10-31T03:30:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stderr(predicates::str::contains("Warning").not())
+            .stdout(predicates::str::contains("Started 2021-10-31T00:30:00Z"))
+            .stdout(predicates::str::contains("Ended   2021-10-31T02:30:00Z"))
+            .stdout(predicates::str::contains("Total   02:00:00"));
+    }
+
+    #[test]
+    fn day_chart() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, stop, tag) in &[
+            ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+            ("2020-12-25T10:30:00", "2020-12-25T11:00:00", "bar"),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("track")
+                .arg(start)
+                .arg("-")
+                .arg(stop)
+                .arg(tag)
+                .assert()
+                .success();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("day")
+            .arg("2020-12-25T00:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("09"))
+            .stdout(predicates::str::contains("  0 foo |"))
+            .stdout(predicates::str::contains("  1 bar |"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("day")
+            .arg("2020-12-26T00:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("No filtered data found."));
+    }
+
+    #[test]
+    fn stop_all_parallel() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, tag) in &[
+            ("2020-12-25T09:00:00", "foo"),
+            ("2020-12-25T10:00:00", "bar"),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--overlap")
+                .arg("start")
+                .arg("--parallel")
+                .arg(start)
+                .arg(tag)
+                .assert()
+                .success();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--overlap")
+            .arg("stop")
+            .arg("--all")
+            .arg("2020-12-25T11:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "Recorded foo 2020-12-25T09:00:00 2020-12-25T11:00:00 02:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "Recorded bar 2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "There is no active time tracking.",
+            ));
+    }
+
+    #[test]
+    fn stop_all_single() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("2020-12-25T09:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("--all")
+            .arg("2020-12-25T10:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded foo"))
+            .stdout(predicates::str::contains("Total   01:00:00"));
+    }
+
+    #[test]
+    fn stop_all_partial_failure() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, tag) in &[
+            ("2020-12-25T09:00:00", "foo"),
+            ("2020-12-25T10:00:00", "bar"),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--overlap")
+                .arg("start")
+                .arg("--parallel")
+                .arg(start)
+                .arg(tag)
+                .assert()
+                .success();
+        }
+        // bar stops first, then foo would overlap it
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--no_overlap")
+            .arg("stop")
+            .arg("--all")
+            .arg("2020-12-25T11:00:00")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("Failed to stop activity 0"))
+            .stderr(predicates::str::contains(
+                "1 ongoing activities could not be stopped",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+    }
+
+    #[test]
+    fn jsonl_backend_track_delete_summary() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, stop, tag) in &[
+            ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+            ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--backend")
+                .arg("jsonl")
+                .arg("track")
+                .arg(start)
+                .arg("-")
+                .arg(stop)
+                .arg(tag)
+                .assert()
+                .success();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--backend")
+            .arg("jsonl")
+            .arg("delete")
+            .arg("1")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--backend")
+            .arg("jsonl")
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+        let finished = std::fs::read_to_string(test_dir.path().join(".rtwh.jsonl")).unwrap();
+        assert_eq!(finished.lines().count(), 1);
+        assert!(finished.contains("foo"));
+        assert!(!test_dir.path().join(".rtwh.json").exists());
+    }
+
+    #[test]
+    fn current_none() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "There is no active time tracking.",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("[]"));
+    }
+
+    #[test]
+    fn current_ongoing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("2020-12-25T09:00:00")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo bar"))
+            .stdout(predicates::str::contains("Started  2020-12-25T09:00:00"))
+            .stdout(predicates::str::contains("Elapsed"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "\"tags\": [\n      \"foo\",\n      \"bar\"\n    ]",
+            ))
+            .stdout(predicates::str::contains(
+                "\"start\": \"2020-12-25T09:00:00",
+            ))
+            .stdout(predicates::str::contains("\"elapsed_seconds\":"));
+    }
+
+    #[test]
+    fn summary_format_json() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T09:50:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--format")
+            .arg("json")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("\"duration_seconds\": 3000"))
+            .stdout(predicates::str::contains("\"total_seconds\": 3000"))
+            .stdout(predicates::str::contains("\"id\"").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--format")
+            .arg("json")
+            .arg("--id")
+            .arg("--round")
+            .arg("60")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("\"id\": 0"))
+            .stdout(predicates::str::contains("\"total_seconds\": 3600"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--format")
+            .arg("json")
+            .arg("2020-12-26T00:00:00")
+            .arg("-")
+            .arg("2020-12-26T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("\"activities\": []"))
+            .stdout(predicates::str::contains("\"total_seconds\": 0"));
+    }
+
+    #[test]
+    fn summary_filter_tags_all_any() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        // same day last week: always within :lastweek
+        let day = (chrono::Local::today() - chrono::Duration::days(7)).format("%Y-%m-%d");
+        for (start, stop, tags) in &[
+            ("09:00:00", "10:00:00", vec!["foo", "bar"]),
+            ("10:00:00", "11:00:00", vec!["bar", "baz"]),
+            ("11:00:00", "12:00:00", vec!["qux"]),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("track")
+                .arg(format!("{}T{}", day, start))
+                .arg("-")
+                .arg(format!("{}T{}", day, stop))
+                .args(tags)
+                .assert()
+                .success();
+        }
+        // all of foo, bar
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg(":lastweek")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo bar"))
+            .stdout(predicates::str::contains("bar baz").not())
+            .stdout(predicates::str::contains("qux").not());
+        // any of foo, baz
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--any")
+            .arg(":lastweek")
+            .arg("foo")
+            .arg("baz")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo bar"))
+            .stdout(predicates::str::contains("bar baz"))
+            .stdout(predicates::str::contains("qux").not());
+        // all of foo, baz
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg(":lastweek")
+            .arg("foo")
+            .arg("baz")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("No filtered data found."));
+    }
+
+    #[test]
+    fn summary_tag_totals() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (start, stop, tags) in &[
+            ("2020-12-25T09:00:00", "2020-12-25T10:00:00", vec!["foo"]),
+            (
+                "2020-12-25T09:30:00",
+                "2020-12-25T10:30:00",
+                vec!["foo", "bar"],
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--overlap")
+                .arg("track")
+                .arg("--allow-future")
+                .arg(start)
+                .arg("-")
+                .arg(stop)
+                .args(tags)
+                .assert()
+                .success();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::ends_with(concat!(
+                "\n",
+                "bar        01:00:00\n",
+                "foo        02:00:00\n",
+                "Total      02:00:00\n",
+                "Wall clock 01:30:00\n",
+            )));
+    }
+
+    #[test]
+    fn split_activity() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("split")
+            .arg("0")
+            .arg("2020-12-25T09:20:00")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Split foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T09:00:00 2020-12-25T09:20:00 00:20:00",
+            ))
+            .stdout(predicates::str::contains(
+                "1 bar 2020-12-25T09:20:00 2020-12-25T10:00:00 00:40:00",
+            ));
+    }
+
+    #[test]
+    fn split_activity_outside() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("split")
+            .arg("0")
+            .arg("2020-12-25T10:00:00")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "is not strictly within the activity",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("split")
+            .arg("1")
+            .arg("2020-12-25T09:30:00")
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicates::str::contains("no activity found for id 1"));
+    }
+
+    fn track_all(test_dir_path: &str, activities: &[(&str, &str, &str)]) {
+        for (start, stop, tag) in activities {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--overlap")
+                .arg("track")
+                .arg("--allow-future")
+                .arg(start)
+                .arg("-")
+                .arg(stop)
+                .arg(tag)
+                .assert()
+                .success();
+        }
+    }
+
+    #[test]
+    fn merge_adjacent() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T08:00:00", "2020-12-25T08:30:00", "baz"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("merge")
+            .arg("1")
+            .arg("2")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Merged foo bar"))
+            .stdout(predicates::str::contains("Total   02:00:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "1 foo bar 2020-12-25T09:00:00 2020-12-25T11:00:00 02:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "0 baz     2020-12-25T08:00:00 2020-12-25T08:30:00 00:30:00",
+            ));
+    }
+
+    #[test]
+    fn merge_overlapping() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T09:30:00", "2020-12-25T10:30:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("merge")
+            .arg("1")
+            .arg("0")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Merged foo bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo bar 2020-12-25T09:00:00 2020-12-25T10:30:00 01:30:00",
+            ));
+    }
+
+    #[test]
+    fn merge_gap() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:30:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("merge")
+            .arg("0")
+            .arg("1")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("gap: 00:30:00"))
+            .stderr(predicates::str::contains("--force"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("merge")
+            .arg("--force")
+            .arg("0")
+            .arg("1")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Merged foo bar"))
+            .stdout(predicates::str::contains("Total   02:00:00"));
+    }
+
+    #[test]
+    fn move_forward_and_backward() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("move")
+            .arg("0")
+            .arg("+15m")
+            .assert()
+            .success()
+            .stdout("Moved foo\nStarted 2020-12-25T09:15:00\nEnded   2020-12-25T10:15:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("move")
+            .arg("0")
+            .arg("-1h")
+            .assert()
+            .success()
+            .stdout("Moved foo\nStarted 2020-12-25T08:15:00\nEnded   2020-12-25T09:15:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T08:15:00 2020-12-25T09:15:00 01:00:00",
+            ));
+    }
+
+    #[test]
+    fn move_overlap_or_future() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("move")
+            .arg("0")
+            .arg("+30m")
+            .assert()
+            .failure()
+            .code(3)
+            .stderr(predicates::str::contains(
+                "foo would overlap activities with ids: 1",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("move")
+            .arg("--allow-overlap")
+            .arg("0")
+            .arg("+30m")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Moved foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .arg("move")
+            .arg("1")
+            .arg("+2h")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("--allow-future"));
+    }
+
+    #[test]
+    fn delete_by_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T08:00:00", "2020-12-25T08:30:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("--tag")
+            .arg("foo")
+            .arg("--yes")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted 2 activities."));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "1 bar 2020-12-25T09:00:00 2020-12-25T10:00:00 01:00:00",
+            ))
+            .stdout(predicates::str::contains("foo").not());
+    }
+
+    #[test]
+    fn delete_by_range() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-24T09:00:00", "2020-12-24T10:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "baz"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("--range")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .arg("--yes")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted 2 activities."));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-24T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-24T09:00:00 2020-12-24T10:00:00 01:00:00",
+            ))
+            .stdout(predicates::str::contains("bar").not());
+    }
+
+    #[test]
+    fn delete_by_tag_without_confirmation() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("--tag")
+            .arg("foo")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "1 activities match, use --yes to delete them",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn doctor_findings_and_fix() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("doctor")
+            .assert()
+            .success()
+            .stdout("All good.\n");
+        track_all(
+            test_dir_path,
+            &[
+                ("2019-12-25T09:00:00", "2019-12-25T10:00:00", "foo"),
+                ("2019-12-25T09:30:00", "2019-12-25T11:00:00", "bar"),
+                ("2019-12-25T12:00:00", "2019-12-25T12:00:00", "baz"),
+            ],
+        );
+        // ongoing activity already finished
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--overlap")
+            .arg("start")
+            .arg("2019-12-25T09:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("doctor")
+            .assert()
+            .failure()
+            .stdout(concat!(
+                "activity 2 lasts 0s\n",
+                "activities 0 and 1 overlap\n",
+                "ongoing activity 0 is already finished as activity 0\n",
+            ))
+            .stderr(predicates::str::contains("3 problems found"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("doctor")
+            .arg("--fix")
+            .assert()
+            .failure()
+            .stdout(concat!(
+                "activity 2 lasts 0s (fixed)\n",
+                "ongoing activity 0 is already finished as activity 0 (fixed)\n",
+                "activities 0 and 1 overlap\n",
+            ))
+            .stderr(predicates::str::contains("1 problems left"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("count")
+            .assert()
+            .success()
+            .stdout("2\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(NO_ACTIVE_TIME_TRACKING);
+    }
+
+    #[test]
+    fn doctor_unreadable_storage() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        std::fs::write(test_dir.path().join(".rtwh.json"), "not json").unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("doctor")
+            .assert()
+            .failure()
+            .stdout(predicates::str::starts_with("unreadable storage: "))
+            .stderr(predicates::str::contains("1 problems found"));
+    }
+
+    #[test]
+    fn undo_nothing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("undo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Nothing to undo."));
+    }
+
+    #[test]
+    fn start_then_undo() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("undo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Undo start"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "There is no active time tracking.",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("undo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Nothing to undo."));
+    }
+
+    #[test]
+    fn delete_then_undo() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("0")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("undo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Undo delete"))
+            .stdout(predicates::str::contains("Restored foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T09:00:00 2020-12-25T10:00:00 01:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "1 bar 2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00",
+            ));
+    }
+
+    #[test]
+    fn config_file_option() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let data_dir = test_dir.path().join("data");
+        std::fs::create_dir(&data_dir).unwrap();
+        let config_path = test_dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                "data_dir = \"{}\"\ndate_format = \"%d/%m/%Y %H:%M\"\n",
+                data_dir.to_str().unwrap()
+            ),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("--config")
+            .arg(config_path.to_str().unwrap())
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("25/12/2020 09:00"));
+        assert!(data_dir.join(".rtwh.json").exists());
+    }
+
+    fn write_default_tags_config(test_dir: &std::path::Path) -> String {
+        let config_path = test_dir.join("config.toml");
+        std::fs::write(
+            &config_path,
+            "default_tags = [\"work\"]\n\
+             [[window_default_tags]]\ntags = [\"morning\"]\nfrom = \"06:00\"\nto = \"12:00\"\n",
+        )
+        .unwrap();
+        config_path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn start_default_tags() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let config_path = write_default_tags_config(test_dir.path());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--now")
+            .arg("2020-12-25T09:00:00")
+            .arg("start")
+            .arg("foo")
+            .arg("work")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Tracking foo work morning\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--now")
+            .arg("2020-12-25T14:00:00")
+            .arg("track")
+            .arg("13:00")
+            .arg("-")
+            .arg("14:00")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Recorded bar work\n"));
+    }
+
+    #[test]
+    fn start_no_defaults() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let config_path = write_default_tags_config(test_dir.path());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("--now")
+            .arg("2020-12-25T09:00:00")
+            .arg("start")
+            .arg("--no-defaults")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Tracking foo\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T09:30:00")
+            .arg("current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("work").not());
+    }
+
+    #[test]
+    fn config_compress() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let config_path = test_dir.path().join("config.toml");
+        std::fs::write(&config_path, "compress = true\n").unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(config_path.to_str().unwrap())
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        let compressed = std::fs::read(test_dir.path().join(".rtwh.json.gz")).unwrap();
+        // gzip magic number
+        assert_eq!(&compressed[..2], &[0x1f, 0x8b]);
+        assert!(!test_dir.path().join(".rtwh.json").exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(config_path.to_str().unwrap())
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with(
+                "foo 2020-12-25T09:00:00 2020-12-25T10:00:00 01:00:00\n",
+            ));
+    }
+
+    #[test]
+    fn config_file_option_missing() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let config_path = test_dir.path().join("missing.toml");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("--config")
+            .arg(config_path.to_str().unwrap())
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn summary_since_until() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2019-11-25T09:00:00", "2019-11-25T10:00:00", "foo"),
+                ("2019-12-25T09:00:00", "2019-12-25T10:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--since")
+            .arg("2019-12-01")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("bar 2019-12-25T09:00:00"))
+            .stdout(predicates::str::contains("foo").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--until")
+            .arg("2019-11-25")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo 2019-11-25T09:00:00"))
+            .stdout(predicates::str::contains("bar").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--since")
+            .arg("2019-11-01")
+            .arg("--until")
+            .arg("2019-12-31")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo 2019-11-25T09:00:00"))
+            .stdout(predicates::str::contains("bar 2019-12-25T09:00:00"));
+    }
+
+    #[test]
+    fn summary_since_future() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2099-01-01T09:00:00", "2099-01-01T10:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--since")
+            .arg("2098-01-01")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("No filtered data found."));
+    }
+
+    #[test]
+    fn summary_duration_format() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T11:15:03", "foo")],
+        );
+        for (format, expected) in &[("human", "2h 15m 03s"), ("decimal", "2.25h")] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--duration-format")
+                .arg(format)
+                .arg("summary")
+                .arg("2020-12-25T00:00:00")
+                .arg("-")
+                .arg("2020-12-25T23:00:00")
+                .assert()
+                .success()
+                .stdout(predicates::str::contains(format!(
+                    "foo 2020-12-25T09:00:00 2020-12-25T11:15:03 {}",
+                    expected
+                )));
+        }
+    }
+
+    #[test]
+    fn summary_key_value_tags() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                (
+                    "2020-12-25T09:00:00",
+                    "2020-12-25T10:00:00",
+                    "project:alpha",
+                ),
+                ("2020-12-25T10:00:00", "2020-12-25T10:30:00", "project:beta"),
+                ("2020-12-25T11:00:00", "2020-12-25T11:45:00", "lunch"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .arg("project:alpha")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with(
+                "project:alpha 2020-12-25T09:00:00 2020-12-25T10:00:00 01:00:00\n\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--group-by")
+            .arg("project")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::ends_with(concat!(
+                "\n",
+                "(none) 00:45:00\n",
+                "alpha  01:00:00\n",
+                "beta   00:30:00\n",
+                "Total  02:15:00\n",
+            )));
+        for format in &["json", "markdown"] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("summary")
+                .arg("--group-by")
+                .arg("project")
+                .arg("--format")
+                .arg(format)
+                .assert()
+                .failure()
+                .stderr(predicates::str::contains(
+                    "--group-by cannot be combined with --format",
+                ));
+        }
+    }
+
+    #[test]
+    fn summary_by_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T12:00:00", "bar"),
+                ("2020-12-25T13:00:00", "2020-12-25T13:30:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--by")
+            .arg("tag")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout("bar 1 02:00:00\nfoo 2 01:30:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--by")
+            .arg("tag")
+            .arg("--sort")
+            .arg("tags")
+            .arg("--reverse")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout("foo 2 01:30:00\nbar 1 02:00:00\n");
+    }
+
+    #[test]
+    fn summary_total_only() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-24T09:00:00", "2020-12-24T10:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T13:00:00", "2020-12-25T13:30:00", "bar"),
+            ],
+        );
+        let summary_total = |args: &[&str], expected: &str| {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--now")
+                .arg("2020-12-25T18:00:00")
+                .arg("summary")
+                .arg("--total-only")
+                .args(args)
+                .assert()
+                .success()
+                .stdout(expected.to_string());
+        };
+        summary_total(&[":today"], "5400\n");
+        summary_total(&["--human", ":today"], "1h 30m 00s\n");
+        summary_total(&[":today", "foo"], "3600\n");
+        summary_total(&[":today", "baz"], "0\n");
+    }
+
+    #[test]
+    fn summary_precision() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T09:02:30", "foo")],
+        );
+        for (precision, expected) in &[("second", "00:02:30"), ("minute", "00:03")] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--precision")
+                .arg(precision)
+                .arg("summary")
+                .arg("2020-12-25T00:00:00")
+                .arg("-")
+                .arg("2020-12-25T23:00:00")
+                .assert()
+                .success()
+                .stdout(predicates::str::contains(format!(
+                    "foo 2020-12-25T09:00:00 2020-12-25T09:02:30 {}\n",
+                    expected
+                )))
+                .stdout(predicates::str::is_match(format!("Total +{}\n", expected)).unwrap());
+        }
+    }
+
+    #[test]
+    fn summary_precision_minute_total_is_sum_of_rows() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T09:10:40", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T10:10:40", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--precision")
+            .arg("minute")
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "foo 2020-12-25T09:00:00 2020-12-25T09:10:40 00:11\n",
+            ))
+            .stdout(predicates::str::contains(
+                "bar 2020-12-25T10:00:00 2020-12-25T10:10:40 00:11\n",
+            ))
+            .stdout(predicates::str::ends_with("Total 00:22\n"));
+    }
+
+    #[test]
+    fn start_future_rejected() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("2099-01-01T09:00:00")
+            .arg("foo")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "2099-01-01T09:00:00 is in the future, use --allow-future to record it anyway",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("--allow-future")
+            .arg("2099-01-01T09:00:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Started  2099-01-01T09:00:00"));
+    }
+
+    #[test]
+    fn track_future_rejected() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2099-01-01T09:00:00")
+            .arg("foo")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("is in the future"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("--allow-future")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2099-01-01T09:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+    }
+
+    #[test]
+    fn completion_bash() {
+        for subcommand in &["completion", "completions"] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg(subcommand)
+                .arg("bash")
+                .assert()
+                .success()
+                .stdout(predicates::str::is_empty().not())
+                .stdout(predicates::str::contains("rtw"));
+        }
+    }
+
+    #[cfg(unix)]
+    fn write_hook(hooks_dir: &std::path::Path, event: &str, script: &str) {
+        use std::os::unix::fs::PermissionsExt;
+        let hook_path = hooks_dir.join(event);
+        std::fs::write(&hook_path, script).unwrap();
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[cfg(unix)]
+    fn write_hooks_config(test_dir: &std::path::Path, abort_on_failure: bool) -> String {
+        let config_path = test_dir.join("config.json");
+        std::fs::write(
+            &config_path,
+            format!(
+                "{{\"hooks_dir\": \"{}\", \"hooks_abort_on_failure\": {}}}",
+                test_dir.join("hooks").to_str().unwrap(),
+                abort_on_failure
+            ),
+        )
+        .unwrap();
+        config_path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hooks_on_start_on_stop() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let hooks_dir = test_dir.path().join("hooks");
+        std::fs::create_dir(&hooks_dir).unwrap();
+        write_hook(
+            &hooks_dir,
+            "on-start",
+            "#!/bin/sh\ncat > \"$(dirname \"$0\")/started\"\n",
+        );
+        write_hook(
+            &hooks_dir,
+            "on-stop",
+            "#!/bin/sh\ncat > \"$(dirname \"$0\")/stopped\"\n",
+        );
+        let config_path = write_hooks_config(test_dir.path(), false);
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let started = std::fs::read_to_string(hooks_dir.join("started")).unwrap();
+        assert!(started.contains("\"foo\""));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("stop")
+            .assert()
+            .success();
+        let stopped = std::fs::read_to_string(hooks_dir.join("stopped")).unwrap();
+        assert!(stopped.contains("stop_time"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn hooks_failure() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let hooks_dir = test_dir.path().join("hooks");
+        std::fs::create_dir(&hooks_dir).unwrap();
+        write_hook(&hooks_dir, "on-start", "#!/bin/sh\nexit 1\n");
+        let config_path = write_hooks_config(test_dir.path(), false);
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success()
+            .stderr(predicates::str::contains("Warning: hook"));
+        let config_path = write_hooks_config(test_dir.path(), true);
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--config")
+            .arg(&config_path)
+            .arg("start")
+            .arg("bar")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("failed"));
+    }
+
+    #[test]
+    fn report_by_day_and_week() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-20T09:00:00", "2020-12-20T10:00:00", "foo"),
+                ("2020-12-21T09:00:00", "2020-12-21T10:00:00", "foo"),
+                ("2020-12-21T11:00:00", "2020-12-21T11:30:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("report")
+            .arg("2020-12-14T00:00:00")
+            .arg("-")
+            .arg("2020-12-27T00:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "2020-12-20 1 01:00:00\n2020-12-21 2 01:30:00\nTotal      3 02:30:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("report")
+            .arg("--by")
+            .arg("week")
+            .arg("2020-12-14T00:00:00")
+            .arg("-")
+            .arg("2020-12-27T00:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "2020-W51 1 01:00:00\n2020-W52 2 01:30:00\nTotal    3 02:30:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--week-start")
+            .arg("sun")
+            .arg("report")
+            .arg("--by")
+            .arg("week")
+            .arg("2020-12-14T00:00:00")
+            .arg("-")
+            .arg("2020-12-27T00:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "2020-12-20 3 02:30:00\nTotal      3 02:30:00\n",
+            ));
+    }
+
+    #[test]
+    fn summary_longer_shorter_than() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T01:00:00", "2020-12-25T06:00:00", "long"),
+                ("2020-12-25T09:00:00", "2020-12-25T09:00:30", "short"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:30:00", "medium"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--longer-than")
+            .arg("4h")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("long 2020-12-25T01:00:00"))
+            .stdout(predicates::str::contains("short").not())
+            .stdout(predicates::str::contains("medium").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--shorter-than")
+            .arg("1m")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("short 2020-12-25T09:00:00"))
+            .stdout(predicates::str::contains("long").not())
+            .stdout(predicates::str::contains("medium").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--longer-than")
+            .arg("1m")
+            .arg("--shorter-than")
+            .arg("1h30m")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(NO_FILTERED_DATA_FOUND));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--longer-than")
+            .arg("1d")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid duration '1d'"));
+    }
+
+    #[test]
+    fn start_blank_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg(" ")
+            .arg("foo")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("empty tag"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T10:00:00")
+            .arg("foo;bar")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid tag 'foo;bar'"));
+    }
+
+    #[test]
+    fn start_normalized_tags() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg(" foo")
+            .arg("bar")
+            .arg("foo ")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo bar\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("modify")
+            .arg("0")
+            .arg("--tags")
+            .arg("baz,,qux")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("empty tag"));
+    }
+
+    #[test]
+    fn delete_keeps_other_ids() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T08:00:00", "2020-12-25T09:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "baz"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("1")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted bar"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T08:00:00 2020-12-25T09:00:00 01:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "2 baz 2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00",
+            ));
+        // a new activity does not reuse ids
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T12:00:00", "2020-12-25T13:00:00", "qux")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("2")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted baz"));
+    }
+
+    #[test]
+    fn delete_highest_id_not_reused() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T08:00:00", "2020-12-25T09:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("1")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted bar"));
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T10:00:00", "2020-12-25T11:00:00", "baz")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T08:00:00 2020-12-25T09:00:00 01:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "2 baz 2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00",
+            ));
+    }
+
+    #[test]
+    fn count_activities() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-24T09:00:00", "2020-12-24T10:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "baz"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("count")
+            .assert()
+            .success()
+            .stdout("3\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("1")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("count")
+            .assert()
+            .success()
+            .stdout("2\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("count")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout("1\n");
+    }
+
+    #[test]
+    fn last_activities() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("last")
+            .assert()
+            .success()
+            .stdout("No finished activities.\n");
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T12:00:00", "long"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "foo"),
+                ("2020-12-25T11:00:00", "2020-12-25T11:30:00", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("last")
+            .arg("2")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "0 long 2020-12-25T09:00:00 2020-12-25T12:00:00 03:00:00\n",
+                "2 bar  2020-12-25T11:00:00 2020-12-25T11:30:00 00:30:00\n",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("last")
+            .assert()
+            .success()
+            .stdout("0 long 2020-12-25T09:00:00 2020-12-25T12:00:00 03:00:00\n");
+    }
+
+    #[test]
+    fn summary_id_column_width() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let activities: Vec<(String, String, &str)> = (0..11)
+            .map(|hour| {
+                (
+                    format!("2020-12-25T{:02}:00:00", hour),
+                    format!("2020-12-25T{:02}:30:00", hour),
+                    "foo",
+                )
+            })
+            .collect();
+        let activities: Vec<(&str, &str, &str)> = activities
+            .iter()
+            .map(|(start, stop, tag)| (start.as_str(), stop.as_str(), *tag))
+            .collect();
+        track_all(test_dir_path, &activities);
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with(
+                " 0 foo 2020-12-25T00:00:00 2020-12-25T00:30:00 00:30:00\n",
+            ))
+            .stdout(predicates::str::contains(
+                "\n10 foo 2020-12-25T10:00:00 2020-12-25T10:30:00 00:30:00\n",
+            ));
+    }
+
+    #[test]
+    fn track_if_not_exists() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let track = |tags: &[&str]| {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("track")
+                .arg("--if-not-exists")
+                .arg("2019-12-25T09:00:00")
+                .arg("-")
+                .arg("2019-12-25T10:00:00")
+                .args(tags)
+                .assert()
+                .success()
+        };
+        track(&["foo", "bar"]).stdout(predicates::str::starts_with("Recorded foo bar\n"));
+        track(&["bar", "foo"]).stdout("Skipped bar foo, already tracked with id 0\n");
+        // differing tags are tracked, overlapping the first activity
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--overlap")
+            .arg("track")
+            .arg("--if-not-exists")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::starts_with("Recorded foo\n"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("count")
+            .assert()
+            .success()
+            .stdout("2\n");
+    }
+
+    #[test]
+    fn summary_with_current() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2019-12-25T08:00:00", "2019-12-25T09:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:00:00")
+            .arg("start")
+            .arg("bar")
+            .assert()
+            .success();
+        // today's summary displays the ongoing activity by default
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "bar 2019-12-25T09:00:00 2019-12-25T09:30:00 00:30:00 (ongoing)\n",
+            ))
+            .stdout(predicates::str::is_match("Total +01:30:00").unwrap());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .arg("--no-current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("ongoing").not());
+        // other ranges only with --with-current
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .arg(":week")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("ongoing").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .arg(":week")
+            .arg("--with-current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("00:30:00 (ongoing)"));
+    }
+
+    #[test]
+    fn summary_with_current_json_markdown() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2019-12-25T08:00:00", "2019-12-25T09:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:00:00")
+            .arg("start")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .arg(":week")
+            .arg("--with-current")
+            .arg("--format")
+            .arg("json")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("\"ongoing\": true").count(1))
+            .stdout(predicates::str::contains("\"duration_seconds\": 1800"))
+            .stdout(predicates::str::contains("\"total_seconds\": 5400"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2019-12-25T09:30:00")
+            .arg("summary")
+            .arg(":week")
+            .arg("--with-current")
+            .arg("--format")
+            .arg("markdown")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "| 2019-12-25T09:00:00 | 2019-12-25T09:30:00 | 00:30:00 (ongoing) | bar |",
+            ))
+            .stdout(predicates::str::contains("**01:30:00**"));
+    }
+
+    #[test]
+    fn start_same_tags_twice() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout("Already tracking foo.\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--no-current")
+            .assert()
+            .success()
+            .stdout(NO_FILTERED_DATA_FOUND);
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("--restart")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn stop_by_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--no_overlap")
+            .arg("start")
+            .arg("10 min ago")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--no_overlap")
+            .arg("start")
+            .arg("--parallel")
+            .arg("5 min ago")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "multiple ongoing activities, please provide an id or tags",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("baz")
+            .assert()
+            .success()
+            .stdout("No ongoing activity with tag baz.\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("1 min ago")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded foo"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"))
+            .stdout(predicates::str::contains("foo").not());
+    }
+
+    #[test]
+    fn stop_single_by_unknown_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout("No ongoing activity with tag bar.\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+    }
+
+    #[test]
+    fn track_confirmation() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T09:02:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "Recorded foo\n",
+                "Started 2020-12-25T09:00:00\n",
+                "Ended   2020-12-25T09:02:00\n",
+                "Total   00:02:00\n",
+            ));
+    }
+
+    #[test]
+    fn track_quiet() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--quiet")
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("-")
+            .arg("2020-12-25T09:02:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout("");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn start_stop_quiet() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--quiet")
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout("");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("-q")
+            .arg("stop")
+            .assert()
+            .success()
+            .stdout("");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("-q")
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn start_verbose() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--verbose")
+            .arg("start")
+            .arg("2020-12-25T09:00:00")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"))
+            .stderr(predicates::str::contains(".rtw.json"))
+            .stderr(predicates::str::contains(
+                "Resolved start time 2020-12-25T09:00:00",
+            ));
+    }
+
+    #[test]
+    fn quiet_conflicts_with_verbose() {
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("--quiet").arg("--verbose").assert().failure();
+    }
+
+    #[test]
+    fn stop_with_corrupt_ongoing_file() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        std::fs::write(test_dir.path().join(".rtw.json"), "{\"ongoing\": [").unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .assert()
+            .success()
+            .stdout("There is no active time tracking.\n")
+            .stderr(predicates::str::contains(".rtw.json.corrupt"));
+        assert!(test_dir.path().join(".rtw.json.corrupt").exists());
+    }
+
+    #[test]
+    fn continue_pick() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T08:00:00", "2020-12-25T09:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("continue")
+            .arg("--pick")
+            .write_stdin("3\n")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "invalid choice '3', expected a number between 1 and 2",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("continue")
+            .arg("--pick")
+            .write_stdin("2\n")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "1 foo\n",
+                "2 bar\n",
+                "Continue which activity? [1-2] Tracking bar\n"
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"));
+    }
+
+    #[test]
+    fn total_for_tag() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-24T08:00:00", "2020-12-24T09:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T10:30:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("total")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout("01:30:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("total")
+            .arg("foo")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout("00:30:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("total")
+            .arg("qux")
+            .assert()
+            .success()
+            .stdout("00:00:00\nNo activity tagged with qux.\n");
+    }
+
+    #[test]
+    fn rtw_dir_env() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let other_dir = tempdir().expect("could not create temp directory");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("RTW_DIR", test_dir.path())
+            .arg("--default")
+            .arg("start")
+            .arg("foo")
+            .assert()
+            .success();
+        assert!(test_dir.path().join(".rtw.json").exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("RTW_DIR", test_dir.path())
+            .arg("--default")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+        // -d takes precedence
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("RTW_DIR", test_dir.path())
+            .arg("-d")
+            .arg(other_dir.path())
+            .assert()
+            .success()
+            .stdout(NO_ACTIVE_TIME_TRACKING);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn xdg_data_home() {
+        let home_dir = tempdir().expect("could not create temp directory");
+        let data_dir = tempdir().expect("could not create temp directory");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env_remove("RTW_DIR")
+            .env("HOME", home_dir.path())
+            .env("XDG_DATA_HOME", data_dir.path())
+            .arg("--default")
+            .arg("track")
+            .arg("2019-12-25T09:00:00")
+            .arg("-")
+            .arg("2019-12-25T10:00:00")
+            .arg("foo")
+            .assert()
+            .success();
+        assert!(data_dir.path().join("rtw").join(".rtwh.json").exists());
+        assert!(!home_dir.path().join(".rtwh.json").exists());
+        // activities stored in the home directory by earlier versions are kept there
+        let legacy_dir = tempdir().expect("could not create temp directory");
+        std::fs::write(legacy_dir.path().join(".rtwh.json"), "[]").unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env_remove("RTW_DIR")
+            .env("HOME", legacy_dir.path())
+            .env("XDG_DATA_HOME", data_dir.path())
+            .arg("--default")
+            .arg("count")
+            .assert()
+            .success()
+            .stdout("0\n");
+    }
+
+    #[test]
+    fn summary_total_is_sum_of_rows() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T09:45:30", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:20:45", "bar"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::ends_with("Total 02:06:15\n"));
+    }
+
+    #[test]
+    fn summary_total_is_sum_of_overlapping_rows() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T09:30:00", "2020-12-25T10:30:00", "bar"),
+            ],
+        );
+        let summary = |args: &[&str], expected: &str| {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("summary")
+                .args(args)
+                .arg("2020-12-25T00:00:00")
+                .arg("-")
+                .arg("2020-12-25T23:00:00")
+                .assert()
+                .success()
+                .stdout(predicates::str::ends_with(expected.to_string()));
+        };
+        // time shared by overlapping activities is counted once by the wall clock only
+        summary(&[], "Total      02:00:00\nWall clock 01:30:00\n");
+        summary(
+            &["--round", "15"],
+            "Total      02:00:00\nWall clock 01:30:00\n",
+        );
+    }
+
+    #[test]
+    fn summary_total_no_sub_second_drift() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        // 29:59.6 each: rows display 00:29:59
+        std::fs::write(
+            test_dir.path().join(".rtwh.json"),
+            concat!(
+                "{\"activities\":[",
+                "{\"id\":0,\"start_time\":\"2020-12-25T09:00:00.400+00:00\",",
+                "\"stop_time\":\"2020-12-25T09:30:00+00:00\",\"tags\":[\"foo\"]},",
+                "{\"id\":1,\"start_time\":\"2020-12-25T10:00:00.400+00:00\",",
+                "\"stop_time\":\"2020-12-25T10:30:00+00:00\",\"tags\":[\"bar\"]}",
+                "]}"
+            ),
+        )
+        .unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-24T00:00:00")
+            .arg("-")
+            .arg("2020-12-26T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("00:29:59\n").count(4))
+            .stdout(predicates::str::ends_with("Total 00:59:58\n"));
+    }
+
+    #[cfg(unix)]
+    fn write_editor(test_dir: &std::path::Path, script: &str) -> std::path::PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let editor_path = test_dir.join("editor.sh");
+        std::fs::write(&editor_path, script).unwrap();
+        std::fs::set_permissions(&editor_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        editor_path
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn edit_with_editor() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let editor = write_editor(
+            test_dir.path(),
+            "#!/bin/sh\nsed -i 's/\"foo\"/\"baz\"/' \"$1\"\n",
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("EDITOR", &editor)
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("edit")
+            .assert()
+            .success()
+            .stdout("Modified 1\nDeleted  0\nAdded    0\n");
+        assert!(!test_dir.path().join(".rtw-edit.json").exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("0 baz"))
+            .stdout(predicates::str::contains("foo").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("undo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn edit_invalid_rejected() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo")],
+        );
+        let editor = write_editor(test_dir.path(), "#!/bin/sh\necho garbage > \"$1\"\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.env("EDITOR", &editor)
+            .arg("-d")
+            .arg(test_dir_path)
+            .arg("edit")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("invalid edit, nothing changed"));
+        assert!(test_dir.path().join(".rtw-edit.json").exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"));
+    }
+
+    #[test]
+    fn current_tags_only() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .arg("--tags-only")
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr("");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("foo")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .arg("--tags-only")
+            .assert()
+            .code(0)
+            .stdout("foo bar\n");
+    }
+
+    #[test]
+    fn stop_before_start() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("2 min ago")
+            .arg("foo")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stop")
+            .arg("5 min ago")
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr(predicates::str::contains("is before start time"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"));
+    }
+
+    #[test]
+    fn track_with_duration() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("+1h")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Ended   2020-12-25T10:00:00"))
+            .stdout(predicates::str::contains("Total   01:00:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T11:00:00")
+            .arg("+90m")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Ended   2020-12-25T12:30:00"))
+            .stdout(predicates::str::contains("Total   01:30:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("--for")
+            .arg("90m")
+            .arg("2020-12-25T13:00:00")
+            .arg("baz")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Recorded baz"))
+            .stdout(predicates::str::contains("Total   01:30:00"));
+    }
+
+    #[test]
+    fn track_with_zero_duration_rejected() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("track")
+            .arg("2020-12-25T09:00:00")
+            .arg("+0m")
+            .arg("foo")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains("must be positive"));
+    }
+
+    #[test]
+    fn piped_output_has_no_color() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Total"))
+            .stdout(predicates::str::contains("\x1b[").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("bar")
+            .assert()
+            .success();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("current")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking bar"))
+            .stdout(predicates::str::contains("\x1b[").not());
+    }
+
+    #[test]
+    fn concurrent_writers_keep_all_activities() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap().to_string();
+        let writers: Vec<_> = ["foo", "bar"]
+            .iter()
+            .enumerate()
+            .map(|(writer, tag)| {
+                let test_dir_path = test_dir_path.clone();
+                std::thread::spawn(move || {
+                    for i in 0..5 {
+                        let hour = 10 * writer + i;
+                        let mut cmd = Command::cargo_bin("rtw").unwrap();
+                        cmd.arg("-d")
+                            .arg(&test_dir_path)
+                            .arg("track")
+                            .arg(format!("2020-12-25T{:02}:00:00", hour))
+                            .arg("-")
+                            .arg(format!("2020-12-25T{:02}:30:00", hour))
+                            .arg(tag)
+                            .assert()
+                            .success();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        let output = cmd
+            .arg("-d")
+            .arg(&test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(
+            stdout
+                .lines()
+                .filter(|l| l.starts_with("foo 2020-12-25T"))
+                .count(),
+            5
+        );
+        assert_eq!(
+            stdout
+                .lines()
+                .filter(|l| l.starts_with("bar 2020-12-25T"))
+                .count(),
+            5
+        );
+    }
+
+    #[test]
+    fn delete_prints_deleted_activity() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:30:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("delete")
+            .arg("0")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Deleted foo"))
+            .stdout(predicates::str::contains("Started 2020-12-25T09:00:00"))
+            .stdout(predicates::str::contains("Ended   2020-12-25T10:30:00"))
+            .stdout(predicates::str::contains("Total   01:30:00"));
+    }
+
+    #[test]
+    fn now_override() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T09:00:00")
+            .arg("start")
+            .arg("15min ago")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Started  2020-12-25T08:45:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T09:30:00")
+            .arg("stop")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Total   00:45:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T18:00:00")
+            .arg("summary")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "foo 2020-12-25T08:45:00 2020-12-25T09:30:00 00:45:00",
+            ));
+    }
+
+    #[test]
+    fn goal_add_status_remove() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T12:00:00", "foo"),
+                ("2020-12-25T13:00:00", "2020-12-25T14:00:00", "bar"),
+            ],
+        );
+        for (tag, target) in &[("foo", "2h"), ("bar", "90m")] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("goal")
+                .arg("add")
+                .arg(tag)
+                .arg(target)
+                .assert()
+                .success();
+        }
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("goal")
+            .arg("status")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout("bar 01:00:00 / 01:30:00 -00:30:00\nfoo 03:00:00 / 02:00:00 +01:00:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("goal")
+            .arg("remove")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout("Removed goal foo\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("goal")
+            .arg("status")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout("bar 01:00:00 / 01:30:00 -00:30:00\n");
+    }
+
+    #[test]
+    fn start_from_last() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("--from-last")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Tracking foo"))
+            .stderr(predicates::str::contains(
+                "no finished activity, starting now",
+            ));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("cancel")
+            .assert()
+            .success();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:15:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("--from-last")
+            .arg("bar")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Started  2020-12-25T10:15:00"));
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("start")
+            .arg("--from-last")
+            .arg("09:00")
+            .arg("baz")
+            .assert()
+            .failure()
+            .stderr(predicates::str::contains(
+                "cannot be combined with a time clue",
+            ));
+    }
+
+    #[test]
+    fn overlaps_report() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T12:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+                ("2020-12-25T13:00:00", "2020-12-25T14:00:00", "baz"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("overlaps")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout("0 foo overlaps 1 bar\n  2020-12-25T10:00:00 2020-12-25T11:00:00 01:00:00\n");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("overlaps")
+            .arg("2020-12-25T12:30:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout("No overlaps found.\n");
+    }
+
+    #[test]
+    fn stats_over_range() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stats")
+            .assert()
+            .success()
+            .stdout("No data.\n");
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-24T09:00:00", "2020-12-24T10:00:00", "foo"),
+                ("2020-12-25T09:00:00", "2020-12-25T09:30:00", "bar"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:30:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("stats")
+            .arg("2020-12-24T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:59:59")
+            .assert()
+            .success()
+            .stdout(concat!(
+                "Count    3\n",
+                "Total    03:00:00\n",
+                "Shortest 00:30:00\n",
+                "Longest  01:30:00\n",
+                "Mean     01:00:00\n",
+                "Busiest  2020-12-25 02:00:00\n"
+            ));
+    }
+
+    #[test]
+    fn summary_sort_duration_reverse() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "medium"),
+                ("2020-12-25T10:00:00", "2020-12-25T10:30:00", "short"),
+                ("2020-12-25T11:00:00", "2020-12-25T13:00:00", "long"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::is_match("(?s)^medium .*\nshort  .*\nlong   ").unwrap());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--sort")
+            .arg("duration")
+            .arg("--reverse")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::is_match("(?s)^long   .*\nmedium .*\nshort  ").unwrap());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--sort")
+            .arg("duration")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::is_match("(?s)^short  .*\nmedium .*\nlong   ").unwrap());
+    }
+
+    #[test]
+    fn profiles_are_independent() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        for (profile, tag) in &[("work", "foo"), ("home", "bar")] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("--profile")
+                .arg(profile)
+                .arg("track")
+                .arg("2020-12-25T09:00:00")
+                .arg("-")
+                .arg("2020-12-25T10:00:00")
+                .arg(tag)
+                .assert()
+                .success();
+        }
+        assert!(test_dir.path().join("work.rtwh.json").exists());
+        assert!(test_dir.path().join("home.rtwh.json").exists());
+        assert!(!test_dir.path().join(".rtwh.json").exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--profile")
+            .arg("work")
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("foo"))
+            .stdout(predicates::str::contains("bar").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--profile")
+            .arg("home")
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("bar"))
+            .stdout(predicates::str::contains("foo").not());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("No filtered data found."));
+    }
+
+    #[test]
+    fn stop_coalesce() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo")],
+        );
+        for (start, stop, tag) in &[
+            ("2020-12-25T10:00:30", "2020-12-25T11:00:00", "foo"),
+            ("2020-12-25T11:00:00", "2020-12-25T12:00:00", "bar"),
+        ] {
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("start")
+                .arg(start)
+                .arg(tag)
+                .assert()
+                .success();
+            let mut cmd = Command::cargo_bin("rtw").unwrap();
+            cmd.arg("-d")
+                .arg(test_dir_path)
+                .arg("stop")
+                .arg("--coalesce")
+                .arg(stop)
+                .assert()
+                .success();
+        }
+        // foo is merged with the previous foo, bar is not
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--id")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "0 foo 2020-12-25T09:00:00 2020-12-25T11:00:00 02:00:00",
+            ))
+            .stdout(predicates::str::contains(
+                "1 bar 2020-12-25T11:00:00 2020-12-25T12:00:00 01:00:00",
+            ));
+    }
+
+    #[test]
+    fn summary_week_grid() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-01T09:00:00", "2020-12-01T10:00:00", "foo"),
+                ("2020-12-02T09:00:00", "2020-12-02T11:00:00", "foo"),
+                ("2020-12-03T09:00:00", "2020-12-03T12:00:00", "foo"),
+                ("2020-12-07T09:00:00", "2020-12-07T16:00:00", "foo"),
+                ("2020-12-15T09:00:00", "2020-12-15T14:00:00", "foo"),
+            ],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--week-grid")
+            .arg("2020-12-01T00:00:00")
+            .arg("-")
+            .arg("2020-12-20T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(concat!(
+                "         Mo Tu We Th Fr Sa Su\n",
+                "2020-W49    ░░ ░░ ▒▒ ·· ·· ··\n",
+                "2020-W50 ██ ·· ·· ·· ·· ·· ··\n",
+                "2020-W51 ·· ██ ·· ·· ·· ·· ··\n",
+            )));
+    }
+
+    #[test]
+    fn track_until_now() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("--now")
+            .arg("2020-12-25T12:00:00")
+            .arg("track")
+            .arg("09:00")
+            .arg("now")
+            .arg("foo")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains("Started 2020-12-25T09:00:00"))
+            .stdout(predicates::str::contains("Ended   2020-12-25T12:00:00"))
+            .stdout(predicates::str::contains("Total   03:00:00"));
+    }
+
+    #[test]
+    fn summary_markdown() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo|bar")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("summary")
+            .arg("--format")
+            .arg("markdown")
+            .arg("2020-12-25T00:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::contains(
+                "| Start | End | Duration | Tags |\n|---|---|---|---|\n",
+            ))
+            .stdout(predicates::str::contains("| 01:00:00 | foo\\|bar |"))
+            .stdout(predicates::str::contains(
+                "| **Total** |  | **01:00:00** |  |",
+            ));
+    }
+
+    #[test]
+    fn report_clip() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T11:00:00", "foo"),
+                ("2020-12-25T12:00:00", "2020-12-25T13:00:00", "bar"),
+            ],
+        );
+        // the activity started before the range is ignored
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("report")
+            .arg("2020-12-25T10:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "2020-12-25 1 01:00:00\nTotal      1 01:00:00\n",
+            ));
+        // only its part within the range counts
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("report")
+            .arg("--clip")
+            .arg("2020-12-25T10:00:00")
+            .arg("-")
+            .arg("2020-12-25T23:00:00")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar(
+                "2020-12-25 2 02:00:00\nTotal      2 02:00:00\n",
+            ));
+    }
+
+    #[test]
+    fn tags_cache_rebuilt() {
+        let test_dir = tempdir().expect("could not create temp directory");
+        let test_dir_path = test_dir.path().to_str().unwrap();
+        track_all(
+            test_dir_path,
+            &[
+                ("2020-12-25T09:00:00", "2020-12-25T10:00:00", "foo"),
+                ("2020-12-25T10:00:00", "2020-12-25T11:00:00", "bar"),
+            ],
+        );
+        let cache_path = test_dir.path().join(".rtw-tags.json");
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar("bar 1 01:00:00\nfoo 1 01:00:00\n"));
+        assert!(cache_path.exists());
+        // updates the cache
+        track_all(
+            test_dir_path,
+            &[("2020-12-25T11:00:00", "2020-12-25T11:30:00", "foo")],
+        );
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar("foo 2 01:30:00\nbar 1 01:00:00\n"));
+        std::fs::remove_file(&cache_path).unwrap();
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar("foo 2 01:30:00\nbar 1 01:00:00\n"));
+        assert!(cache_path.exists());
+        let mut cmd = Command::cargo_bin("rtw").unwrap();
+        cmd.arg("-d")
+            .arg(test_dir_path)
+            .arg("tags")
+            .arg("--names")
+            .assert()
+            .success()
+            .stdout(predicates::str::similar("bar\nfoo\n"));
+    }
 }